@@ -0,0 +1,648 @@
+//! Main contract implementation for the Adelanta Compliance Registry
+//!
+//! The admin appoints compliance officers; officers keep the verification
+//! records and decide the levels required of each party. The level an
+//! account needs for an invoice is the higher of its party's level and the
+//! level of the invoice's country. Officers also freeze sanctioned
+//! accounts; the protocol's contracts hand payouts to frozen accounts to
+//! the registry, which holds them until an officer releases or redirects
+//! them.
+
+use soroban_sdk::{contract, contractimpl, token, Address, Env, String, Vec};
+
+use crate::errors::ComplianceError;
+use crate::events::Events;
+use crate::storage::Storage;
+use crate::types::{AccountFreeze, ComplianceConfig, KycPolicy, KycRecord, Party, RetainedPayout};
+
+#[contract]
+pub struct AdelantaComplianceContract;
+
+#[contractimpl]
+impl AdelantaComplianceContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the registry
+    ///
+    /// No verification is required until the officers set a policy.
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address, appoints compliance officers
+    pub fn initialize(env: Env, admin: Address) -> Result<(), ComplianceError> {
+        if Storage::has_config(&env) {
+            return Err(ComplianceError::AlreadyInitialized);
+        }
+
+        let config = ComplianceConfig { admin };
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    /// Appoint or remove a compliance officer (admin only)
+    pub fn set_officer(
+        env: Env,
+        admin: Address,
+        officer: Address,
+        authorized: bool,
+    ) -> Result<(), ComplianceError> {
+        admin.require_auth();
+
+        let config = Storage::get_config(&env).ok_or(ComplianceError::NotInitialized)?;
+        if admin != config.admin {
+            return Err(ComplianceError::Unauthorized);
+        }
+
+        Storage::set_officer(&env, &officer, authorized);
+
+        Events::officer_set(&env, &officer, authorized);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // VERIFICATIONS
+    // ============================================================
+
+    /// Record the KYC/KYB verification of an account (officer only)
+    ///
+    /// Replaces any earlier verification of the account.
+    ///
+    /// # Arguments
+    /// * `level` - Verification level, at least 1
+    /// * `jurisdiction` - Jurisdiction the account was verified in
+    /// * `expires_at` - Timestamp the verification lapses at
+    pub fn set_kyc(
+        env: Env,
+        officer: Address,
+        account: Address,
+        level: u32,
+        jurisdiction: String,
+        expires_at: u64,
+    ) -> Result<KycRecord, ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        if level == 0 || expires_at <= env.ledger().timestamp() {
+            return Err(ComplianceError::InvalidRecord);
+        }
+
+        let record = KycRecord {
+            account,
+            level,
+            jurisdiction,
+            expires_at,
+            verified_by: officer,
+            verified_at: env.ledger().timestamp(),
+        };
+        Storage::set_kyc(&env, &record);
+
+        Events::kyc_set(&env, &record);
+
+        Ok(record)
+    }
+
+    /// Remove the verification of an account (officer only)
+    pub fn revoke_kyc(env: Env, officer: Address, account: Address) -> Result<(), ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        if Storage::get_kyc(&env, &account).is_none() {
+            return Err(ComplianceError::NotVerified);
+        }
+        Storage::remove_kyc(&env, &account);
+
+        Events::kyc_revoked(&env, &account, &officer);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // POLICY
+    // ============================================================
+
+    /// Set the levels required of SMEs and lenders (officer only)
+    pub fn set_policy(env: Env, officer: Address, policy: KycPolicy) -> Result<(), ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        Storage::set_policy(&env, &policy);
+        Storage::extend_instance_ttl(&env);
+
+        Events::policy_set(&env, &officer, &policy);
+
+        Ok(())
+    }
+
+    /// Set the level required of every party to invoices in `country`; 0
+    /// removes the requirement (officer only)
+    pub fn set_country_level(
+        env: Env,
+        officer: Address,
+        country: String,
+        level: u32,
+    ) -> Result<(), ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        Storage::set_country_level(&env, &country, level);
+        Storage::extend_instance_ttl(&env);
+
+        Events::country_level_set(&env, &country, level);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // SANCTIONS FREEZES
+    // ============================================================
+
+    /// Freeze an account (officer only)
+    ///
+    /// Payouts to a frozen account are retained by the registry instead of
+    /// transferred: settlement and recovery shares, the advance, refunds and
+    /// position sale proceeds. Freezing an account again replaces its reason.
+    ///
+    /// # Arguments
+    /// * `account` - Lender or SME address to freeze
+    /// * `reason` - Reason code, e.g. the sanctions list entry
+    pub fn freeze_account(
+        env: Env,
+        officer: Address,
+        account: Address,
+        reason: String,
+    ) -> Result<AccountFreeze, ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        let freeze = AccountFreeze {
+            account,
+            reason,
+            frozen_by: officer,
+            frozen_at: env.ledger().timestamp(),
+        };
+        Storage::set_freeze(&env, &freeze);
+
+        Events::account_frozen(&env, &freeze);
+
+        Ok(freeze)
+    }
+
+    /// Lift the freeze on an account (officer only)
+    ///
+    /// Later payouts go to the account again. Payouts already retained stay
+    /// with the registry until released or redirected.
+    pub fn unfreeze_account(env: Env, officer: Address, account: Address) -> Result<(), ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        if Storage::get_freeze(&env, &account).is_some() {
+            Storage::remove_freeze(&env, &account);
+            Events::account_unfrozen(&env, &account, &officer);
+        }
+
+        Ok(())
+    }
+
+    /// Hold a payout destined for a frozen account (called by the protocol's
+    /// contracts when paying out)
+    ///
+    /// `amount` of `asset` is transferred from `from` to the registry.
+    /// Returns the retained payout ID.
+    ///
+    /// # Arguments
+    /// * `from` - Address the payout is paid from
+    /// * `account` - Frozen account the payout was destined for
+    /// * `invoice_id` - Invoice the payout belongs to
+    pub fn retain_payout(
+        env: Env,
+        from: Address,
+        account: Address,
+        asset: Address,
+        amount: i128,
+        invoice_id: u64,
+    ) -> Result<u64, ComplianceError> {
+        from.require_auth();
+
+        if Storage::get_freeze(&env, &account).is_none() {
+            return Err(ComplianceError::NotFrozen);
+        }
+        if amount <= 0 {
+            return Err(ComplianceError::InvalidAmount);
+        }
+
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&from, &env.current_contract_address(), &amount);
+
+        let payout = RetainedPayout {
+            id: Storage::next_retained_payout_id(&env),
+            invoice_id,
+            account,
+            asset,
+            amount,
+            retained_at: env.ledger().timestamp(),
+            paid_to: None,
+            paid_by: None,
+            paid_at: None,
+        };
+        Storage::set_retained_payout(&env, &payout);
+        Storage::extend_instance_ttl(&env);
+
+        Events::payout_retained(&env, &payout);
+
+        Ok(payout.id)
+    }
+
+    /// Pay a retained payout to the account it was destined for, once the
+    /// account is no longer frozen (officer only)
+    pub fn release_payout(env: Env, officer: Address, payout_id: u64) -> Result<RetainedPayout, ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        let payout = Self::get_unpaid_payout(&env, payout_id)?;
+        let account = payout.account.clone();
+
+        Self::pay_retained(&env, &officer, payout, &account)
+    }
+
+    /// Pay a retained payout to another address instead of its frozen
+    /// account, e.g. as ordered by a regulator (officer only)
+    pub fn redirect_payout(
+        env: Env,
+        officer: Address,
+        payout_id: u64,
+        to: Address,
+    ) -> Result<RetainedPayout, ComplianceError> {
+        officer.require_auth();
+
+        Self::require_officer(&env, &officer)?;
+
+        let payout = Self::get_unpaid_payout(&env, payout_id)?;
+
+        Self::pay_retained(&env, &officer, payout, &to)
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get registry configuration
+    pub fn get_config(env: Env) -> Result<ComplianceConfig, ComplianceError> {
+        Storage::get_config(&env).ok_or(ComplianceError::NotInitialized)
+    }
+
+    /// Check whether an account is a compliance officer
+    pub fn is_officer(env: Env, account: Address) -> bool {
+        Storage::is_officer(&env, &account)
+    }
+
+    /// Get the verification of an account
+    pub fn get_kyc(env: Env, account: Address) -> Option<KycRecord> {
+        Storage::get_kyc(&env, &account)
+    }
+
+    /// Get the levels required of SMEs and lenders
+    pub fn get_policy(env: Env) -> KycPolicy {
+        Storage::get_policy(&env)
+    }
+
+    /// Get the level required for invoices in a country
+    pub fn get_country_level(env: Env, country: String) -> u32 {
+        Storage::get_country_level(&env, &country)
+    }
+
+    /// Get the level `party` needs for an invoice in `country`
+    pub fn required_level(env: Env, party: Party, country: String) -> u32 {
+        Self::level_for(&env, party, &country)
+    }
+
+    /// Check that `account` may take part in an invoice in `country` as
+    /// `party`, failing with the reason it may not
+    pub fn check(env: Env, account: Address, party: Party, country: String) -> Result<(), ComplianceError> {
+        let level = Self::level_for(&env, party, &country);
+        if level == 0 {
+            return Ok(());
+        }
+
+        let record = Storage::get_kyc(&env, &account).ok_or(ComplianceError::NotVerified)?;
+        if record.expires_at <= env.ledger().timestamp() {
+            return Err(ComplianceError::Expired);
+        }
+        if record.level < level {
+            return Err(ComplianceError::LevelTooLow);
+        }
+
+        Ok(())
+    }
+
+    /// Whether `account` may take part in an invoice in `country` as `party`
+    pub fn is_eligible(env: Env, account: Address, party: Party, country: String) -> bool {
+        Self::check(env, account, party, country).is_ok()
+    }
+
+    /// Check whether an account is frozen
+    pub fn is_frozen(env: Env, account: Address) -> bool {
+        Storage::get_freeze(&env, &account).is_some()
+    }
+
+    /// Get the freeze on an account, if it is frozen
+    pub fn get_account_freeze(env: Env, account: Address) -> Option<AccountFreeze> {
+        Storage::get_freeze(&env, &account)
+    }
+
+    /// Get a retained payout
+    pub fn get_retained_payout(env: Env, payout_id: u64) -> Option<RetainedPayout> {
+        Storage::get_retained_payout(&env, payout_id)
+    }
+
+    /// Get every payout retained from an account, paid or not
+    pub fn get_retained_payouts(env: Env, account: Address) -> Vec<RetainedPayout> {
+        let mut payouts = Vec::new(&env);
+        for id in Storage::get_account_retained_payouts(&env, &account).iter() {
+            if let Some(payout) = Storage::get_retained_payout(&env, id) {
+                payouts.push_back(payout);
+            }
+        }
+        payouts
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    fn require_officer(env: &Env, account: &Address) -> Result<(), ComplianceError> {
+        if !Storage::has_config(env) {
+            return Err(ComplianceError::NotInitialized);
+        }
+        if !Storage::is_officer(env, account) {
+            return Err(ComplianceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Load a retained payout that has not been paid yet
+    fn get_unpaid_payout(env: &Env, payout_id: u64) -> Result<RetainedPayout, ComplianceError> {
+        Storage::get_retained_payout(env, payout_id)
+            .filter(|payout| payout.paid_to.is_none())
+            .ok_or(ComplianceError::PayoutNotFound)
+    }
+
+    /// Pay a retained payout to `to`, which must not be frozen, and keep
+    /// the record of who paid it where
+    fn pay_retained(
+        env: &Env,
+        officer: &Address,
+        mut payout: RetainedPayout,
+        to: &Address,
+    ) -> Result<RetainedPayout, ComplianceError> {
+        if Storage::get_freeze(env, to).is_some() {
+            return Err(ComplianceError::AccountFrozen);
+        }
+
+        let token_client = token::Client::new(env, &payout.asset);
+        token_client.transfer(&env.current_contract_address(), to, &payout.amount);
+
+        payout.paid_to = Some(to.clone());
+        payout.paid_by = Some(officer.clone());
+        payout.paid_at = Some(env.ledger().timestamp());
+        Storage::set_retained_payout(env, &payout);
+
+        Events::payout_released(env, &payout);
+
+        Ok(payout)
+    }
+
+    fn level_for(env: &Env, party: Party, country: &String) -> u32 {
+        let policy = Storage::get_policy(env);
+        let party_level = match party {
+            Party::Sme => policy.sme_min_level,
+            Party::Lender => policy.lender_min_level,
+        };
+        party_level.max(Storage::get_country_level(env, country))
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::token::{Client as TokenClient, StellarAssetClient};
+
+    struct TestContext {
+        env: Env,
+        client: AdelantaComplianceContractClient<'static>,
+        admin: Address,
+        officer: Address,
+    }
+
+    /// Initialized registry at timestamp 1000 with one officer
+    fn setup() -> TestContext {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        let admin = Address::generate(&env);
+        let officer = Address::generate(&env);
+
+        let contract_id = env.register_contract(None, AdelantaComplianceContract);
+        let client = AdelantaComplianceContractClient::new(&env, &contract_id);
+        client.initialize(&admin);
+        client.set_officer(&admin, &officer, &true);
+
+        TestContext {
+            env,
+            client,
+            admin,
+            officer,
+        }
+    }
+
+    #[test]
+    fn test_officers_manage_records() {
+        let ctx = setup();
+        let account = Address::generate(&ctx.env);
+        let mx = String::from_str(&ctx.env, "MX");
+
+        assert_eq!(
+            ctx.client.try_initialize(&ctx.admin),
+            Err(Ok(ComplianceError::AlreadyInitialized))
+        );
+        assert!(ctx.client.is_officer(&ctx.officer));
+        assert_eq!(
+            ctx.client.try_set_officer(&ctx.officer, &account, &true),
+            Err(Ok(ComplianceError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_kyc(&ctx.admin, &account, &2, &mx, &5000),
+            Err(Ok(ComplianceError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_kyc(&ctx.officer, &account, &0, &mx, &5000),
+            Err(Ok(ComplianceError::InvalidRecord))
+        );
+        assert_eq!(
+            ctx.client.try_set_kyc(&ctx.officer, &account, &2, &mx, &1000),
+            Err(Ok(ComplianceError::InvalidRecord))
+        );
+
+        let record = ctx.client.set_kyc(&ctx.officer, &account, &2, &mx, &5000);
+        assert_eq!(record.verified_by, ctx.officer);
+        assert_eq!(record.verified_at, 1000);
+        assert_eq!(ctx.client.get_kyc(&account), Some(record));
+
+        ctx.client.revoke_kyc(&ctx.officer, &account);
+        assert_eq!(ctx.client.get_kyc(&account), None);
+        assert_eq!(
+            ctx.client.try_revoke_kyc(&ctx.officer, &account),
+            Err(Ok(ComplianceError::NotVerified))
+        );
+
+        // Removed officers lose access
+        ctx.client.set_officer(&ctx.admin, &ctx.officer, &false);
+        assert_eq!(
+            ctx.client.try_set_kyc(&ctx.officer, &account, &2, &mx, &5000),
+            Err(Ok(ComplianceError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_policy_and_country_levels() {
+        let ctx = setup();
+        let account = Address::generate(&ctx.env);
+        let mx = String::from_str(&ctx.env, "MX");
+        let br = String::from_str(&ctx.env, "BR");
+
+        // Nothing is required until a policy is set
+        assert!(ctx.client.is_eligible(&account, &Party::Sme, &mx));
+
+        ctx.client.set_policy(
+            &ctx.officer,
+            &KycPolicy {
+                sme_min_level: 1,
+                lender_min_level: 2,
+            },
+        );
+        ctx.client.set_country_level(&ctx.officer, &br, &3);
+        assert_eq!(ctx.client.required_level(&Party::Sme, &mx), 1);
+        assert_eq!(ctx.client.required_level(&Party::Lender, &mx), 2);
+        assert_eq!(ctx.client.required_level(&Party::Sme, &br), 3);
+
+        assert_eq!(
+            ctx.client.try_check(&account, &Party::Sme, &mx),
+            Err(Ok(ComplianceError::NotVerified))
+        );
+
+        ctx.client.set_kyc(&ctx.officer, &account, &2, &mx, &5000);
+        assert!(ctx.client.is_eligible(&account, &Party::Sme, &mx));
+        assert!(ctx.client.is_eligible(&account, &Party::Lender, &mx));
+        assert_eq!(
+            ctx.client.try_check(&account, &Party::Lender, &br),
+            Err(Ok(ComplianceError::LevelTooLow))
+        );
+
+        // Verifications lapse at their expiry
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 5000;
+        });
+        assert_eq!(
+            ctx.client.try_check(&account, &Party::Sme, &mx),
+            Err(Ok(ComplianceError::Expired))
+        );
+
+        // Lifting the country requirement falls back to the policy
+        ctx.client.set_country_level(&ctx.officer, &br, &0);
+        assert_eq!(ctx.client.get_country_level(&br), 0);
+        assert_eq!(ctx.client.required_level(&Party::Lender, &br), 2);
+    }
+
+    #[test]
+    fn test_frozen_payouts_retained_until_released() {
+        let ctx = setup();
+        let payer = Address::generate(&ctx.env);
+        let lender = Address::generate(&ctx.env);
+        let regulator = Address::generate(&ctx.env);
+        let sanctions = String::from_str(&ctx.env, "OFAC-SDN");
+
+        let usdc = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone()).address();
+        StellarAssetClient::new(&ctx.env, &usdc).mint(&payer, &1000_0000000);
+        let token = TokenClient::new(&ctx.env, &usdc);
+
+        assert_eq!(
+            ctx.client.try_freeze_account(&lender, &lender, &sanctions),
+            Err(Ok(ComplianceError::Unauthorized))
+        );
+
+        // Only payouts to frozen accounts are retained
+        assert_eq!(
+            ctx.client.try_retain_payout(&payer, &lender, &usdc, &400_0000000, &1),
+            Err(Ok(ComplianceError::NotFrozen))
+        );
+        let freeze = ctx.client.freeze_account(&ctx.officer, &lender, &sanctions);
+        assert_eq!(freeze.frozen_by, ctx.officer);
+        assert!(ctx.client.is_frozen(&lender));
+        assert_eq!(ctx.client.get_account_freeze(&lender), Some(freeze));
+        assert_eq!(
+            ctx.client.try_retain_payout(&payer, &lender, &usdc, &0, &1),
+            Err(Ok(ComplianceError::InvalidAmount))
+        );
+
+        let share_id = ctx.client.retain_payout(&payer, &lender, &usdc, &400_0000000, &1);
+        let refund_id = ctx.client.retain_payout(&payer, &lender, &usdc, &100_0000000, &2);
+        let share = ctx.client.get_retained_payout(&share_id).unwrap();
+        let refund = ctx.client.get_retained_payout(&refund_id).unwrap();
+        assert_eq!(share.invoice_id, 1);
+        assert_eq!(share.retained_at, 1000);
+        assert_eq!(share.paid_to, None);
+        assert_eq!(ctx.client.get_retained_payouts(&lender).len(), 2);
+        assert_eq!(token.balance(&ctx.client.address), 500_0000000);
+
+        // Payouts cannot go to frozen accounts
+        assert_eq!(
+            ctx.client.try_release_payout(&ctx.officer, &share.id),
+            Err(Ok(ComplianceError::AccountFrozen))
+        );
+        assert_eq!(
+            ctx.client.try_redirect_payout(&ctx.officer, &share.id, &lender),
+            Err(Ok(ComplianceError::AccountFrozen))
+        );
+
+        // The refund is redirected as ordered by a regulator
+        let redirected = ctx.client.redirect_payout(&ctx.officer, &refund.id, &regulator);
+        assert_eq!(redirected.paid_to, Some(regulator.clone()));
+        assert_eq!(redirected.paid_by, Some(ctx.officer.clone()));
+        assert_eq!(redirected.paid_at, Some(1000));
+        assert_eq!(token.balance(&regulator), 100_0000000);
+        assert_eq!(
+            ctx.client.try_redirect_payout(&ctx.officer, &refund.id, &regulator),
+            Err(Ok(ComplianceError::PayoutNotFound))
+        );
+
+        // The lender is cleared and its share released to it
+        ctx.client.unfreeze_account(&ctx.officer, &lender);
+        assert!(!ctx.client.is_frozen(&lender));
+        let released = ctx.client.release_payout(&ctx.officer, &share.id);
+        assert_eq!(released.paid_to, Some(lender.clone()));
+        assert_eq!(token.balance(&lender), 400_0000000);
+        assert_eq!(ctx.client.get_retained_payout(&share.id), Some(released));
+        assert_eq!(token.balance(&ctx.client.address), 0);
+    }
+}
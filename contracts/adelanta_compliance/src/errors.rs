@@ -0,0 +1,31 @@
+//! Custom error types for the Adelanta Compliance Contract
+
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ComplianceError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Unauthorized caller
+    Unauthorized = 3,
+    /// Verification without a level or already expired
+    InvalidRecord = 4,
+    /// Account has no verification
+    NotVerified = 5,
+    /// Account's verification has expired
+    Expired = 6,
+    /// Account's verification level is below the required level
+    LevelTooLow = 7,
+    /// Invalid amount (zero or negative)
+    InvalidAmount = 8,
+    /// Account is not frozen
+    NotFrozen = 9,
+    /// Account is frozen and cannot be paid
+    AccountFrozen = 10,
+    /// Retained payout not found or already paid
+    PayoutNotFound = 11,
+}
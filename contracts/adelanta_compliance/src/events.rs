@@ -0,0 +1,110 @@
+//! Contract events for the Adelanta Compliance Contract
+//!
+//! Events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. Together they form the audit trail of every
+//! verification, policy change, freeze and retained payout.
+
+use soroban_sdk::{symbol_short, Address, Env, String, Symbol};
+
+use crate::types::{AccountFreeze, ComplianceConfig, KycPolicy, KycRecord, RetainedPayout};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const OFFICER_SET: Symbol = symbol_short!("officer");
+pub const KYC_SET: Symbol = symbol_short!("kyc_set");
+pub const KYC_REVOKED: Symbol = symbol_short!("kyc_rvk");
+pub const POLICY_SET: Symbol = symbol_short!("policy");
+pub const COUNTRY_LEVEL_SET: Symbol = symbol_short!("country");
+pub const ACCOUNT_FROZEN: Symbol = symbol_short!("frozen");
+pub const ACCOUNT_UNFROZEN: Symbol = symbol_short!("unfrozen");
+pub const PAYOUT_RETAINED: Symbol = symbol_short!("retained");
+pub const PAYOUT_RELEASED: Symbol = symbol_short!("ret_paid");
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    /// Topics: `("init", version, admin)`
+    pub fn initialized(env: &Env, config: &ComplianceConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.admin.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("officer", version, account)`; payload is whether the
+    /// account is an officer
+    pub fn officer_set(env: &Env, account: &Address, authorized: bool) {
+        env.events().publish(
+            (OFFICER_SET, EVENTS_VERSION, account.clone()),
+            authorized,
+        );
+    }
+
+    /// Topics: `("kyc_set", version, account)`
+    pub fn kyc_set(env: &Env, record: &KycRecord) {
+        env.events().publish(
+            (KYC_SET, EVENTS_VERSION, record.account.clone()),
+            record.clone(),
+        );
+    }
+
+    /// Topics: `("kyc_rvk", version, account)`; payload is the revoking officer
+    pub fn kyc_revoked(env: &Env, account: &Address, officer: &Address) {
+        env.events().publish(
+            (KYC_REVOKED, EVENTS_VERSION, account.clone()),
+            officer.clone(),
+        );
+    }
+
+    /// Topics: `("policy", version, officer)`
+    pub fn policy_set(env: &Env, officer: &Address, policy: &KycPolicy) {
+        env.events().publish(
+            (POLICY_SET, EVENTS_VERSION, officer.clone()),
+            policy.clone(),
+        );
+    }
+
+    /// Topics: `("country", version, country)`; payload is the required level
+    pub fn country_level_set(env: &Env, country: &String, level: u32) {
+        env.events().publish(
+            (COUNTRY_LEVEL_SET, EVENTS_VERSION, country.clone()),
+            level,
+        );
+    }
+
+    /// Topics: `("frozen", version, account)`
+    pub fn account_frozen(env: &Env, freeze: &AccountFreeze) {
+        env.events().publish(
+            (ACCOUNT_FROZEN, EVENTS_VERSION, freeze.account.clone()),
+            freeze.clone(),
+        );
+    }
+
+    /// Topics: `("unfrozen", version, account)`; payload is the compliance officer
+    pub fn account_unfrozen(env: &Env, account: &Address, officer: &Address) {
+        env.events().publish(
+            (ACCOUNT_UNFROZEN, EVENTS_VERSION, account.clone()),
+            officer.clone(),
+        );
+    }
+
+    /// Topics: `("retained", version, invoice_id, account)`
+    pub fn payout_retained(env: &Env, payout: &RetainedPayout) {
+        env.events().publish(
+            (PAYOUT_RETAINED, EVENTS_VERSION, payout.invoice_id, payout.account.clone()),
+            payout.clone(),
+        );
+    }
+
+    /// Topics: `("ret_paid", version, invoice_id, account)`; `paid_to` in the
+    /// payload is the account itself on release, or the redirect recipient
+    pub fn payout_released(env: &Env, payout: &RetainedPayout) {
+        env.events().publish(
+            (PAYOUT_RELEASED, EVENTS_VERSION, payout.invoice_id, payout.account.clone()),
+            payout.clone(),
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! # Adelanta Compliance Contract
+//!
+//! A Soroban smart contract keeping the KYC/KYB registry of the Adelanta
+//! protocol.
+//!
+//! ## Overview
+//! Compliance officers record the verification level, jurisdiction and
+//! expiry of SME and lender accounts. The invoice contract asks the
+//! registry whether an account may create or fund an invoice, under the
+//! levels the officers require per party and per invoice country. Payouts
+//! to accounts the officers froze are handed to the registry instead.
+//!
+//! ## Key Features
+//! - Verification records with level, jurisdiction and expiry
+//! - Minimum levels for SMEs and lenders
+//! - Stricter levels for invoices in specific countries
+//! - Sanctions freezes, with payouts to frozen accounts held until released
+//!   or redirected
+//! - Every change published as an event for audit
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+
+pub use contract::AdelantaComplianceContract;
@@ -0,0 +1,175 @@
+//! Storage keys and helpers for the Adelanta Compliance Contract
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, String, Val, Vec};
+
+use crate::types::{AccountFreeze, ComplianceConfig, KycPolicy, KycRecord, RetainedPayout};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Registry configuration
+    Config,
+    /// Compliance officer flag by account
+    Officer(Address),
+    /// Verification by account
+    Kyc(Address),
+    /// Levels required of SMEs and lenders
+    Policy,
+    /// Level required for invoices in a country
+    CountryLevel(String),
+    /// Sanctions freeze by account
+    Frozen(Address),
+    /// Payout retained from a frozen account by ID
+    RetainedPayout(u64),
+    /// Next retained payout ID counter
+    NextRetainedPayoutId,
+    /// IDs of the payouts retained from an account
+    AccountRetainedPayouts(Address),
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<ComplianceConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &ComplianceConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== OFFICERS ==========
+
+    pub fn is_officer(env: &Env, account: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Officer(account.clone()))
+    }
+
+    pub fn set_officer(env: &Env, account: &Address, authorized: bool) {
+        let key = DataKey::Officer(account.clone());
+        if authorized {
+            Self::set_persistent(env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    // ========== RECORDS ==========
+
+    pub fn get_kyc(env: &Env, account: &Address) -> Option<KycRecord> {
+        env.storage().persistent().get(&DataKey::Kyc(account.clone()))
+    }
+
+    pub fn set_kyc(env: &Env, record: &KycRecord) {
+        Self::set_persistent(env, &DataKey::Kyc(record.account.clone()), record);
+    }
+
+    pub fn remove_kyc(env: &Env, account: &Address) {
+        env.storage().persistent().remove(&DataKey::Kyc(account.clone()));
+    }
+
+    // ========== POLICY ==========
+
+    pub fn get_policy(env: &Env) -> KycPolicy {
+        env.storage()
+            .instance()
+            .get(&DataKey::Policy)
+            .unwrap_or(KycPolicy {
+                sme_min_level: 0,
+                lender_min_level: 0,
+            })
+    }
+
+    pub fn set_policy(env: &Env, policy: &KycPolicy) {
+        env.storage().instance().set(&DataKey::Policy, policy);
+    }
+
+    pub fn get_country_level(env: &Env, country: &String) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::CountryLevel(country.clone()))
+            .unwrap_or(0u32)
+    }
+
+    pub fn set_country_level(env: &Env, country: &String, level: u32) {
+        let key = DataKey::CountryLevel(country.clone());
+        if level == 0 {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, &level);
+        }
+    }
+
+    // ========== FREEZES ==========
+
+    pub fn get_freeze(env: &Env, account: &Address) -> Option<AccountFreeze> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Frozen(account.clone()))
+    }
+
+    pub fn set_freeze(env: &Env, freeze: &AccountFreeze) {
+        Self::set_persistent(env, &DataKey::Frozen(freeze.account.clone()), freeze);
+    }
+
+    pub fn remove_freeze(env: &Env, account: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Frozen(account.clone()));
+    }
+
+    pub fn get_retained_payout(env: &Env, id: u64) -> Option<RetainedPayout> {
+        env.storage().persistent().get(&DataKey::RetainedPayout(id))
+    }
+
+    /// Store a retained payout, indexing new ones under their account
+    pub fn set_retained_payout(env: &Env, payout: &RetainedPayout) {
+        let key = DataKey::RetainedPayout(payout.id);
+        if !env.storage().persistent().has(&key) {
+            let mut ids = Self::get_account_retained_payouts(env, &payout.account);
+            ids.push_back(payout.id);
+            Self::set_persistent(env, &DataKey::AccountRetainedPayouts(payout.account.clone()), &ids);
+        }
+        Self::set_persistent(env, &key, payout);
+    }
+
+    pub fn get_account_retained_payouts(env: &Env, account: &Address) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AccountRetainedPayouts(account.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn next_retained_payout_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextRetainedPayoutId)
+            .unwrap_or(1u64);
+        env.storage().instance().set(&DataKey::NextRetainedPayoutId, &(id + 1));
+        id
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
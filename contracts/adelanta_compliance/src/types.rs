@@ -0,0 +1,88 @@
+//! Data types for the Adelanta Compliance Contract
+
+use soroban_sdk::{contracttype, Address, String};
+
+/// Registry configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ComplianceConfig {
+    /// Admin address, appoints compliance officers
+    pub admin: Address,
+}
+
+/// Side an account takes in an invoice
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Party {
+    /// SME creating the invoice
+    Sme,
+    /// Lender funding the invoice
+    Lender,
+}
+
+/// KYC/KYB verification of an account
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KycRecord {
+    /// Verified account
+    pub account: Address,
+    /// Verification level (higher is more thorough)
+    pub level: u32,
+    /// Jurisdiction the account was verified in (e.g. "MX")
+    pub jurisdiction: String,
+    /// Timestamp the verification expires at
+    pub expires_at: u64,
+    /// Compliance officer who recorded the verification
+    pub verified_by: Address,
+    /// Timestamp the verification was recorded
+    pub verified_at: u64,
+}
+
+/// Verification levels required of each party; a level of 0 disables the
+/// check
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KycPolicy {
+    /// Minimum level of the SME creating an invoice
+    pub sme_min_level: u32,
+    /// Minimum level of a lender funding an invoice
+    pub lender_min_level: u32,
+}
+
+/// Sanctions freeze on an account; payouts to it are retained
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccountFreeze {
+    /// Frozen account
+    pub account: Address,
+    /// Reason code, e.g. the sanctions list entry
+    pub reason: String,
+    /// Compliance officer who froze the account
+    pub frozen_by: Address,
+    /// Freeze timestamp
+    pub frozen_at: u64,
+}
+
+/// Payout held by the registry because its recipient was frozen
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetainedPayout {
+    /// Retained payout identifier
+    pub id: u64,
+    /// Invoice the payout belongs to
+    pub invoice_id: u64,
+    /// Frozen account the payout was destined for
+    pub account: Address,
+    /// Asset held
+    pub asset: Address,
+    /// Amount held
+    pub amount: i128,
+    /// Retention timestamp
+    pub retained_at: u64,
+    /// Address the payout was paid to, once released or redirected
+    pub paid_to: Option<Address>,
+    /// Compliance officer who released or redirected the payout
+    pub paid_by: Option<Address>,
+    /// Release or redirection timestamp
+    pub paid_at: Option<u64>,
+}
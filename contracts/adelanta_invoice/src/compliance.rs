@@ -0,0 +1,49 @@
+//! KYC/KYB checks against the compliance registry
+//!
+//! The registry is a sibling contract where compliance officers record the
+//! verification level, jurisdiction and expiry of accounts, and set the
+//! levels each party to an invoice needs, overall and per country. Payouts
+//! to accounts the officers froze go through `adelanta_types::compliance`.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, String};
+
+use crate::errors::AdelantaError;
+use crate::storage::Storage;
+
+/// Side of an invoice an account takes part in, as known to the registry
+#[contracttype(export = false)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Party {
+    /// SME selling the invoice
+    Sme,
+    /// Lender funding the invoice
+    Lender,
+}
+
+/// Subset of the compliance registry interface used by the contract
+#[allow(dead_code)]
+#[contractclient(name = "ComplianceRegistryClient")]
+pub trait ComplianceRegistry {
+    /// Whether `account` may take part in an invoice in `country` as `party`
+    fn is_eligible(env: Env, account: Address, party: Party, country: String) -> bool;
+}
+
+/// Require `account` to be eligible as `party` for an invoice in `country`,
+/// if a registry is configured
+pub fn require_eligible(
+    env: &Env,
+    account: &Address,
+    party: Party,
+    country: &String,
+) -> Result<(), AdelantaError> {
+    let Some(registry) = Storage::get_compliance_registry(env) else {
+        return Ok(());
+    };
+
+    let client = ComplianceRegistryClient::new(env, &registry);
+    if !client.is_eligible(account, &party, country) {
+        return Err(AdelantaError::KycRequired);
+    }
+
+    Ok(())
+}
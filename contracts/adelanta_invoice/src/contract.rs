@@ -4,17 +4,49 @@
 //! 1. Initialization with USDC token and config
 //! 2. Invoice creation by SMEs
 //! 3. Funding by liquidity providers
-//! 4. Settlement triggered by Anchor payment
+//! 4. Payments recorded by the settlement contract
 //! 5. Reputation tracking
 
-use soroban_sdk::{contract, contractimpl, token, Address, Env, String};
+use soroban_sdk::{
+    contract, contractimpl, token, Address, BytesN, Env, String, Symbol, Vec,
+};
+
+use adelanta_types::{compliance::pay_out, fx, interest};
 
+use crate::compliance::{self, Party};
 use crate::errors::AdelantaError;
+use crate::events::Events;
+use crate::migration::SCHEMA_VERSION;
+use crate::risk::{self, CreditEvent};
 use crate::storage::Storage;
 use crate::types::{
-    ContractConfig, FundingEvent, Invoice, InvoiceStatus, SettlementResult, SmeReputation,
+    AssetConfig, ContractConfig, DisputeResolution, FaceValue, FeeModel, FundingEvent, FxConfig, Invoice, InvoiceIndex,
+    InvoicePage, InvoiceStatus, LenderContribution, LenderPosition, Operation, Payment, PositionTransfer,
+    ProtocolStats, Role,
 };
 
+/// Roles granted to the admin at initialization; `Marketplace`,
+/// `Settlement` and `Payers` are only granted to the marketplace,
+/// settlement and payers contracts
+const ALL_ROLES: [Role; 6] = [
+    Role::Admin,
+    Role::Settler,
+    Role::Pauser,
+    Role::FeeManager,
+    Role::Compliance,
+    Role::Arbiter,
+];
+
+/// Maximum number of lenders that can syndicate a single invoice
+const MAX_LENDERS_PER_INVOICE: u32 = 20;
+
+/// Maximum invoices returned per index query, keeping a query within the
+/// ledger entry read limits of a single transaction
+const MAX_PAGE_LIMIT: u32 = 25;
+
+/// Default time after `due_date` before an unpaid invoice can be defaulted
+pub(crate) const DEFAULT_GRACE_PERIOD_SECS: u64 = 30 * 24 * 60 * 60;
+
 #[contract]
 pub struct AdelantaInvoiceContract;
 
@@ -27,8 +59,8 @@ impl AdelantaInvoiceContract {
     /// Initialize the contract with configuration
     /// 
     /// # Arguments
-    /// * `admin` - Admin address with special privileges
-    /// * `usdc_token` - USDC token contract address
+    /// * `admin` - Admin address, initially granted every role
+    /// * `usdc_token` - USDC token contract address, approved as the first asset
     /// * `treasury` - Treasury address for protocol fees
     /// * `default_advance_bps` - Default advance percentage (basis points, e.g., 9000 = 90%)
     /// * `protocol_fee_bps` - Protocol fee (basis points, e.g., 50 = 0.5%)
@@ -64,12 +96,30 @@ impl AdelantaInvoiceContract {
             protocol_fee_bps,
             min_invoice_amount: 50_0000000, // 50 USDC (7 decimals)
             max_invoice_amount: 100000_0000000, // 100,000 USDC
+            grace_period_secs: DEFAULT_GRACE_PERIOD_SECS,
+            require_acknowledgement: false,
+            acknowledged_advance_bps: 0,
         };
 
         Storage::set_config(&env, &config);
+        Storage::set_schema_version(&env, SCHEMA_VERSION);
+        Storage::set_asset(
+            &env,
+            &AssetConfig {
+                asset: config.usdc_token.clone(),
+                min_invoice_amount: config.min_invoice_amount,
+                max_invoice_amount: config.max_invoice_amount,
+                enabled: true,
+            },
+        );
         Storage::set_next_invoice_id(&env, 1);
+        for role in ALL_ROLES {
+            Storage::grant_role(&env, role, &config.admin);
+        }
         Storage::extend_instance_ttl(&env);
 
+        Events::initialized(&env, &config);
+
         Ok(())
     }
 
@@ -82,83 +132,75 @@ impl AdelantaInvoiceContract {
     /// # Arguments
     /// * `sme` - SME address (must be the caller)
     /// * `payer_id` - Corporate payer identifier
-    /// * `amount` - Invoice amount in USDC
+    /// * `fingerprint` - Canonical document fingerprint, e.g. SHA-256 of the e-invoice
+    ///   XML or of the CFDI UUID plus issuer tax ID; must not be registered yet
+    /// * `asset` - Approved asset the invoice is funded and settled in
+    /// * `amount` - Invoice amount in `asset`
     /// * `due_date` - Payment due date timestamp
     /// * `country` - Country code (MX, CO, BR, etc.)
     /// * `industry` - Industry/sector code
-    /// * `fee_bps` - Fee percentage in basis points
+    /// * `fee_bps` - Fee percentage in basis points (minimum fee under `FeeModel::Accrued`)
+    /// * `fee_model` - Flat fee, or an annualized rate accruing from funding to settlement
     /// 
     /// # Returns
     /// * Invoice ID
+    #[allow(clippy::too_many_arguments, reason = "every invoice field is an argument of the entry point")]
     pub fn create_invoice(
         env: Env,
         sme: Address,
         payer_id: String,
+        fingerprint: BytesN<32>,
+        asset: Address,
         amount: i128,
         due_date: u64,
         country: String,
         industry: String,
         fee_bps: u32,
+        fee_model: FeeModel,
     ) -> Result<u64, AdelantaError> {
         // Require SME authorization
         sme.require_auth();
 
-        // Get config
-        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
-
-        // Validate amount
-        if amount <= 0 {
-            return Err(AdelantaError::InvalidAmount);
-        }
-        if amount < config.min_invoice_amount || amount > config.max_invoice_amount {
-            return Err(AdelantaError::InvalidAmount);
-        }
-
-        // Validate fee
-        if fee_bps > 1000 {
-            // Max 10%
-            return Err(AdelantaError::InvalidFeePercentage);
-        }
-
-        // Calculate advance amount (default 90%)
-        let advance_amount = (amount * config.default_advance_bps as i128) / 10000;
-
-        // Get next invoice ID
-        let invoice_id = Storage::get_next_invoice_id(&env);
-        Storage::set_next_invoice_id(&env, invoice_id + 1);
-
-        // Create invoice
-        let invoice = Invoice {
-            id: invoice_id,
-            sme: sme.clone(),
-            payer_id,
-            amount,
-            advance_amount,
-            fee_bps,
-            lender: None,
-            status: InvoiceStatus::Created,
-            created_at: env.ledger().timestamp(),
-            funded_at: None,
-            settled_at: None,
-            due_date,
-            country,
-            industry,
-        };
-
-        // Store invoice
-        Storage::set_invoice(&env, &invoice);
-        Storage::increment_total_invoices(&env);
-        Storage::extend_invoice_ttl(&env, invoice_id);
+        Self::new_invoice(
+            &env, sme, payer_id, fingerprint, asset, None, amount, due_date, country, industry,
+            fee_bps, fee_model,
+        )
+    }
 
-        // Update SME reputation (create if not exists)
-        Self::update_sme_reputation_on_create(&env, &sme);
+    /// Create an invoice denominated in a fiat currency (called by SME)
+    ///
+    /// The invoice is funded and settled in `asset`. Its amount is the face
+    /// value converted at the oracle rate, quoted now and fixed when funding
+    /// starts, provided the rate has not moved beyond the slippage tolerance
+    /// in between. Takes the same arguments as `create_invoice`, with the
+    /// invoice amount given as a `FaceValue` in a fiat currency quoted by
+    /// the oracle.
+    #[allow(clippy::too_many_arguments, reason = "takes the arguments of `create_invoice`")]
+    pub fn create_fiat_invoice(
+        env: Env,
+        sme: Address,
+        payer_id: String,
+        fingerprint: BytesN<32>,
+        asset: Address,
+        face_value: FaceValue,
+        due_date: u64,
+        country: String,
+        industry: String,
+        fee_bps: u32,
+        fee_model: FeeModel,
+    ) -> Result<u64, AdelantaError> {
+        sme.require_auth();
 
-        Ok(invoice_id)
+        Self::new_invoice(
+            &env, sme, payer_id, fingerprint, asset, Some(face_value.currency), face_value.amount,
+            due_date, country, industry, fee_bps, fee_model,
+        )
     }
 
     /// Fund an invoice (called by Liquidity Provider)
     /// 
-    /// Transfers USDC from lender to SME and locks the invoice.
+    /// Subscribes the whole unfunded remainder of the advance. If other
+    /// lenders already contributed, this completes the syndicate.
     /// 
     /// # Arguments
     /// * `lender` - Lender address (must be the caller)
@@ -167,132 +209,101 @@ impl AdelantaInvoiceContract {
         // Require lender authorization
         lender.require_auth();
 
-        // Get config and invoice
-        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
         let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        Self::fix_funding_rate(&env, &mut invoice)?;
+        let remaining = invoice.advance_amount - invoice.funded_amount;
 
-        // Check invoice status
-        if invoice.status != InvoiceStatus::Created {
-            return Err(AdelantaError::InvoiceAlreadyFunded);
-        }
-
-        // Check not expired
-        if env.ledger().timestamp() > invoice.due_date {
-            return Err(AdelantaError::InvoiceExpired);
-        }
-
-        // Transfer USDC from lender to SME (advance amount)
-        let usdc_client = token::Client::new(&env, &config.usdc_token);
-        usdc_client.transfer(&lender, &invoice.sme, &invoice.advance_amount);
-
-        // Update invoice
-        invoice.status = InvoiceStatus::Funded;
-        invoice.lender = Some(lender.clone());
-        invoice.funded_at = Some(env.ledger().timestamp());
-
-        Storage::set_invoice(&env, &invoice);
-        Storage::add_volume_funded(&env, invoice.advance_amount);
-        Storage::extend_instance_ttl(&env);
-
-        Ok(FundingEvent {
-            invoice_id,
-            lender,
-            amount: invoice.advance_amount,
-            timestamp: env.ledger().timestamp(),
-        })
+        Self::contribute(&env, lender, invoice, remaining)
     }
 
-    /// Settle an invoice (called by Anchor or Admin after fiat payment received)
-    /// 
-    /// This is the core "auto-split" function:
-    /// - Receives the full invoice amount
-    /// - Pays lender: principal + fee
-    /// - Pays SME: remainder
-    /// - Pays treasury: protocol fee
-    /// 
+    /// Fund part of an invoice's advance (called by Liquidity Provider)
+    ///
+    /// Contributions are held by the contract until the advance is fully
+    /// subscribed, at which point the SME is paid and the invoice flips to
+    /// `Funded`. Settlement is split pro rata across contributors.
+    ///
     /// # Arguments
-    /// * `caller` - Must be admin (simulating Anchor callback)
-    /// * `invoice_id` - Invoice to settle
-    /// * `settlement_amount` - Amount received from corporate payer (in USDC)
-    pub fn settle_invoice(
+    /// * `lender` - Lender address (must be the caller)
+    /// * `invoice_id` - Invoice to fund
+    /// * `amount` - Portion of the advance to contribute (in the invoice's asset)
+    pub fn fund_invoice_partial(
         env: Env,
-        caller: Address,
+        lender: Address,
         invoice_id: u64,
-        settlement_amount: i128,
-    ) -> Result<SettlementResult, AdelantaError> {
-        // Only admin can settle (in production, this would be Anchor callback)
-        caller.require_auth();
-        
-        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
-        
-        // Verify caller is admin
-        if caller != config.admin {
-            return Err(AdelantaError::Unauthorized);
-        }
+        amount: i128,
+    ) -> Result<FundingEvent, AdelantaError> {
+        lender.require_auth();
 
         let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        Self::fix_funding_rate(&env, &mut invoice)?;
 
-        // Check invoice is funded
-        if invoice.status != InvoiceStatus::Funded {
-            if invoice.status == InvoiceStatus::Settled {
-                return Err(AdelantaError::InvoiceAlreadySettled);
-            }
-            return Err(AdelantaError::InvoiceNotFunded);
-        }
-
-        // Validate settlement amount
-        if settlement_amount < invoice.amount {
-            return Err(AdelantaError::InsufficientSettlement);
-        }
-
-        let lender = invoice.lender.clone().ok_or(AdelantaError::InvoiceNotFunded)?;
-
-        // Calculate distributions
-        // Lender gets: advance_amount + fee
-        let lender_fee = (invoice.advance_amount * invoice.fee_bps as i128) / 10000;
-        let lender_amount = invoice.advance_amount + lender_fee;
-
-        // Protocol fee on the total amount
-        let protocol_fee = (invoice.amount * config.protocol_fee_bps as i128) / 10000;
+        Self::contribute(&env, lender, invoice, amount)
+    }
 
-        // SME gets: settlement_amount - lender_amount - protocol_fee
-        let sme_amount = settlement_amount - lender_amount - protocol_fee;
+    /// Record a payment split out by the settlement contract (settlement
+    /// contract only)
+    ///
+    /// The settlement contract runs each payment through the waterfall and
+    /// pays out lenders, treasury and SME; this records the amounts paid on
+    /// the invoice. The invoice is `PartiallySettled` until the cumulative
+    /// amount received covers the invoice amount, then it becomes `Settled`.
+    /// Fiat-denominated invoices are settled once the payments cover their
+    /// face value.
+    ///
+    /// # Arguments
+    /// * `settlement` - Must hold the `Settlement` role
+    /// * `invoice_id` - Invoice the payment is for
+    /// * `payment` - Amount paid and how it was split
+    pub fn record_payment(
+        env: Env,
+        settlement: Address,
+        invoice_id: u64,
+        payment: Payment,
+    ) -> Result<Invoice, AdelantaError> {
+        settlement.require_auth();
 
-        // Transfer from caller (who has received USDC from Anchor)
-        let usdc_client = token::Client::new(&env, &config.usdc_token);
+        Self::require_role(&env, Role::Settlement, &settlement)?;
+        Self::require_not_paused(&env, Operation::Settle)?;
 
-        // Pay lender
-        usdc_client.transfer(&caller, &lender, &lender_amount);
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        let is_recovery = match invoice.status {
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled => false,
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => true,
+            InvoiceStatus::Settled => return Err(AdelantaError::InvoiceAlreadySettled),
+            InvoiceStatus::Disputed => return Err(AdelantaError::InvoiceDisputed),
+            _ => return Err(AdelantaError::InvoiceNotFunded),
+        };
 
-        // Pay SME remainder
-        if sme_amount > 0 {
-            usdc_client.transfer(&caller, &invoice.sme, &sme_amount);
+        invoice.amount_received += payment.amount;
+        if invoice.currency.is_some() {
+            invoice.face_value_received += payment.face_value_paid;
+            invoice.settlement_fx_rate = payment.fx_rate;
         }
-
-        // Pay protocol fee to treasury
-        if protocol_fee > 0 {
-            usdc_client.transfer(&caller, &config.treasury, &protocol_fee);
+        invoice.lender_paid += payment.lender_amount;
+        invoice.protocol_fee_paid += payment.protocol_fee;
+
+        // Update invoice status; defaulted invoices keep their status until
+        // the full amount has been recovered. Fiat invoices are settled once
+        // their face value is paid, whatever it converted to.
+        let fully_settled = match invoice.currency {
+            Some(_) => invoice.face_value_received >= invoice.face_value,
+            None => invoice.amount_received >= invoice.amount,
+        };
+        if fully_settled {
+            Self::transition(&env, &mut invoice, InvoiceStatus::Settled);
+            invoice.settled_at = Some(env.ledger().timestamp());
+        } else if !is_recovery {
+            Self::transition(&env, &mut invoice, InvoiceStatus::PartiallySettled);
         }
 
-        // Update invoice status
-        invoice.status = InvoiceStatus::Settled;
-        invoice.settled_at = Some(env.ledger().timestamp());
-        
         Storage::set_invoice(&env, &invoice);
-        Storage::add_volume_settled(&env, settlement_amount);
+        Storage::add_volume_settled(&env, &invoice.asset, payment.amount);
 
-        // Update SME reputation
-        Self::update_sme_reputation_on_settle(&env, &invoice);
+        risk::record(&env, &invoice, CreditEvent::Paid);
 
         Storage::extend_instance_ttl(&env);
 
-        Ok(SettlementResult {
-            invoice_id,
-            lender_amount,
-            sme_amount,
-            protocol_fee,
-            timestamp: env.ledger().timestamp(),
-        })
+        Ok(invoice)
     }
 
     /// Cancel an invoice (only if not funded yet)
@@ -302,322 +313,3213 @@ impl AdelantaInvoiceContract {
         let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
 
         // Only SME or admin can cancel
-        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
-        if caller != invoice.sme && caller != config.admin {
-            return Err(AdelantaError::Unauthorized);
+        if caller != invoice.sme {
+            Self::require_role(&env, Role::Admin, &caller)?;
         }
 
         // Can only cancel if not funded
-        if invoice.status != InvoiceStatus::Created {
-            return Err(AdelantaError::InvoiceAlreadyFunded);
+        match invoice.status {
+            InvoiceStatus::Created | InvoiceStatus::Acknowledged => {}
+            InvoiceStatus::Disputed => return Err(AdelantaError::InvoiceDisputed),
+            _ => return Err(AdelantaError::InvoiceAlreadyFunded),
         }
 
-        invoice.status = InvoiceStatus::Cancelled;
-        Storage::set_invoice(&env, &invoice);
+        Self::cancel(&env, &mut invoice, &caller);
 
         Ok(())
     }
 
     // ============================================================
-    // VIEW FUNCTIONS
+    // PAYER ACKNOWLEDGEMENT
     // ============================================================
 
-    /// Get invoice details
-    pub fn get_invoice(env: Env, invoice_id: u64) -> Result<Invoice, AdelantaError> {
-        Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)
-    }
+    /// Mark an invoice acknowledged by its payer (payers contract only)
+    ///
+    /// The payers contract has checked the signer and the confirmed amount
+    /// and due date. If funding has not started, the advance is raised to
+    /// `acknowledged_advance_bps` when that is above the invoice's rate,
+    /// but never above the pricing grid's advance for the invoice's risk.
+    ///
+    /// # Arguments
+    /// * `payers` - Must hold the `Payers` role
+    /// * `invoice_id` - Invoice its payer acknowledged
+    pub fn record_acknowledgement(
+        env: Env,
+        payers: Address,
+        invoice_id: u64,
+    ) -> Result<Invoice, AdelantaError> {
+        payers.require_auth();
 
-    /// Get SME reputation
-    pub fn get_sme_reputation(env: Env, sme: Address) -> Option<SmeReputation> {
-        Storage::get_sme_reputation(&env, &sme)
-    }
+        Self::require_role(&env, Role::Payers, &payers)?;
 
-    /// Get contract configuration
-    pub fn get_config(env: Env) -> Result<ContractConfig, AdelantaError> {
-        Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)
-    }
+        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
 
-    /// Get contract stats
-    pub fn get_stats(env: Env) -> (u32, i128, i128) {
-        (
-            Storage::get_total_invoices(&env),
-            Storage::get_total_volume_funded(&env),
-            Storage::get_total_volume_settled(&env),
-        )
-    }
+        match invoice.status {
+            InvoiceStatus::Created => {}
+            InvoiceStatus::Acknowledged => return Err(AdelantaError::AlreadyAcknowledged),
+            InvoiceStatus::Disputed => return Err(AdelantaError::InvoiceDisputed),
+            _ => return Err(AdelantaError::InvoiceAlreadyFunded),
+        }
 
-    /// Calculate expected settlement distribution
-    pub fn preview_settlement(
-        env: Env,
-        invoice_id: u64,
-        settlement_amount: i128,
-    ) -> Result<(i128, i128, i128), AdelantaError> {
-        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
-        let invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        // A confirmed receivable can be advanced at the acknowledged rate, up
+        // to what the grid allows; risk the grid no longer prices keeps the
+        // advance it was created with
+        let quote = risk::quote(&env, &invoice.sme, &invoice.payer_id, &invoice.country, &invoice.industry);
+        let advance_bps = match quote {
+            Ok(Some((advance_bps, _))) => config.acknowledged_advance_bps.min(advance_bps),
+            Ok(None) => config.acknowledged_advance_bps,
+            Err(AdelantaError::RiskNotPriced) => 0,
+            Err(error) => return Err(error),
+        };
+        let acknowledged_advance = (invoice.amount * advance_bps as i128) / 10000;
+        if invoice.funded_amount == 0 && acknowledged_advance > invoice.advance_amount {
+            invoice.advance_amount = acknowledged_advance;
+        }
 
-        let lender_fee = (invoice.advance_amount * invoice.fee_bps as i128) / 10000;
-        let lender_amount = invoice.advance_amount + lender_fee;
-        let protocol_fee = (invoice.amount * config.protocol_fee_bps as i128) / 10000;
-        let sme_amount = settlement_amount - lender_amount - protocol_fee;
+        Self::transition(&env, &mut invoice, InvoiceStatus::Acknowledged);
+        Storage::set_invoice(&env, &invoice);
+
+        risk::record(&env, &invoice, CreditEvent::Acknowledged);
+        Storage::extend_instance_ttl(&env);
 
-        Ok((lender_amount, sme_amount, protocol_fee))
+        Ok(invoice)
     }
 
     // ============================================================
-    // ADMIN FUNCTIONS
+    // ASSETS
     // ============================================================
 
-    /// Update contract configuration (admin only)
-    pub fn update_config(
+    /// Approve a Stellar asset contract for new invoices, or update the
+    /// invoice limits of an approved asset (admin only)
+    pub fn approve_asset(
         env: Env,
         admin: Address,
-        new_treasury: Option<Address>,
-        new_advance_bps: Option<u32>,
-        new_protocol_fee_bps: Option<u32>,
+        asset: Address,
+        min_invoice_amount: i128,
+        max_invoice_amount: i128,
     ) -> Result<(), AdelantaError> {
         admin.require_auth();
 
-        let mut config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
+        Self::require_role(&env, Role::Admin, &admin)?;
 
-        if admin != config.admin {
-            return Err(AdelantaError::Unauthorized);
+        if min_invoice_amount <= 0 || min_invoice_amount > max_invoice_amount {
+            return Err(AdelantaError::InvalidAmount);
         }
 
-        if let Some(treasury) = new_treasury {
-            config.treasury = treasury;
-        }
+        let asset_config = AssetConfig {
+            asset,
+            min_invoice_amount,
+            max_invoice_amount,
+            enabled: true,
+        };
+        Storage::set_asset(&env, &asset_config);
+        Storage::extend_instance_ttl(&env);
 
-        if let Some(advance_bps) = new_advance_bps {
-            if advance_bps > 10000 {
-                return Err(AdelantaError::InvalidAdvancePercentage);
-            }
-            config.default_advance_bps = advance_bps;
-        }
+        Events::asset_set(&env, &asset_config);
 
-        if let Some(fee_bps) = new_protocol_fee_bps {
-            if fee_bps > 1000 {
-                return Err(AdelantaError::InvalidFeePercentage);
-            }
-            config.protocol_fee_bps = fee_bps;
-        }
+        Ok(())
+    }
 
-        Storage::set_config(&env, &config);
-        Storage::extend_instance_ttl(&env);
+    /// Stop accepting new invoices in an asset (admin only)
+    ///
+    /// Existing invoices in the asset are still funded and settled in it.
+    pub fn revoke_asset(env: Env, admin: Address, asset: Address) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        let mut asset_config =
+            Storage::get_asset(&env, &asset).ok_or(AdelantaError::AssetNotSupported)?;
+        asset_config.enabled = false;
+        Storage::set_asset(&env, &asset_config);
+
+        Events::asset_set(&env, &asset_config);
 
         Ok(())
     }
 
     // ============================================================
-    // INTERNAL HELPERS
+    // DEFAULT & RECOVERY
     // ============================================================
 
-    fn update_sme_reputation_on_create(env: &Env, sme: &Address) {
-        let mut reputation = Storage::get_sme_reputation(env, sme).unwrap_or(SmeReputation {
-            sme: sme.clone(),
-            total_invoices: 0,
-            settled_invoices: 0,
-            total_volume: 0,
-            avg_settlement_days: 0,
-            on_time_rate_bps: 10000, // Start at 100%
-            risk_score: 500,         // Medium risk initially
-        });
+    /// Mark an overdue invoice as defaulted (callable by anyone)
+    ///
+    /// Only possible once the grace period after `due_date` has expired
+    /// and the payer has not covered the full amount.
+    pub fn mark_defaulted(env: Env, invoice_id: u64) -> Result<(), AdelantaError> {
+        Self::require_not_paused(&env, Operation::Settle)?;
 
-        reputation.total_invoices += 1;
-        Storage::set_sme_reputation(env, &reputation);
-    }
-
-    fn update_sme_reputation_on_settle(env: &Env, invoice: &Invoice) {
-        if let Some(mut reputation) = Storage::get_sme_reputation(env, &invoice.sme) {
-            reputation.settled_invoices += 1;
-            reputation.total_volume += invoice.amount;
-
-            // Calculate on-time rate
-            let is_on_time = invoice.settled_at.unwrap_or(0) <= invoice.due_date;
-            if reputation.settled_invoices > 1 {
-                let prev_on_time_count = (reputation.on_time_rate_bps as u64
-                    * (reputation.settled_invoices - 1) as u64)
-                    / 10000;
-                let new_on_time_count = if is_on_time {
-                    prev_on_time_count + 1
-                } else {
-                    prev_on_time_count
-                };
-                reputation.on_time_rate_bps =
-                    ((new_on_time_count * 10000) / reputation.settled_invoices as u64) as u32;
-            }
+        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
 
-            // Update risk score (lower is better)
-            // Based on: on-time rate, volume, number of settled invoices
-            let base_score = 1000 - (reputation.on_time_rate_bps / 10);
-            let volume_bonus = if reputation.total_volume > 100000_0000000 {
-                100
-            } else {
-                0
-            };
-            let history_bonus = if reputation.settled_invoices > 10 { 100 } else { 0 };
-            reputation.risk_score = base_score.saturating_sub(volume_bonus + history_bonus);
+        Self::require_settleable(&invoice)?;
 
-            Storage::set_sme_reputation(env, &reputation);
+        if env.ledger().timestamp() <= invoice.due_date.saturating_add(config.grace_period_secs) {
+            return Err(AdelantaError::GracePeriodActive);
         }
+
+        Self::default_invoice(&env, &mut invoice, true);
+
+        Ok(())
     }
-}
 
-// ============================================================
-// TESTS
-// ============================================================
+    /// Write off a defaulted invoice (admin only)
+    ///
+    /// The invoice remains open for recoveries.
+    pub fn write_off(env: Env, admin: Address, invoice_id: u64) -> Result<(), AdelantaError> {
+        admin.require_auth();
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{
-        testutils::{Address as _, Ledger},
-        token::{StellarAssetClient, Client as TokenClient},
-        Env,
-    };
+        Self::require_role(&env, Role::Admin, &admin)?;
 
-    fn create_usdc_token<'a>(env: &Env, admin: &Address) -> (Address, TokenClient<'a>, StellarAssetClient<'a>) {
-        let contract_address = env.register_stellar_asset_contract_v2(admin.clone());
-        (
-            contract_address.address(),
-            TokenClient::new(env, &contract_address.address()),
-            StellarAssetClient::new(env, &contract_address.address()),
-        )
-    }
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        if invoice.status != InvoiceStatus::Defaulted {
+            return Err(AdelantaError::InvoiceNotDefaulted);
+        }
 
-    #[test]
-    fn test_full_invoice_lifecycle() {
-        let env = Env::default();
-        env.mock_all_auths();
+        Self::transition(&env, &mut invoice, InvoiceStatus::WrittenOff);
+        Storage::set_invoice(&env, &invoice);
 
-        // Setup accounts
-        let admin = Address::generate(&env);
-        let treasury = Address::generate(&env);
-        let sme = Address::generate(&env);
-        let lender = Address::generate(&env);
+        Events::invoice_written_off(&env, &invoice);
 
-        // Setup USDC token
-        let (usdc_address, usdc_client, usdc_admin) = create_usdc_token(&env, &admin);
+        Ok(())
+    }
 
-        // Mint USDC to lender and admin (for settlement)
-        usdc_admin.mint(&lender, &10000_0000000); // 10,000 USDC
-        usdc_admin.mint(&admin, &10000_0000000);  // For settlement
+    // ============================================================
+    // DISPUTES
+    // ============================================================
 
-        // Deploy and initialize contract
-        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
-        let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+    /// Mark an invoice disputed (payers contract only)
+    ///
+    /// The payers contract keeps the dispute; the invoice's status is kept
+    /// here to be restored when it is closed. Until then the invoice cannot
+    /// be funded, settled, traded, cancelled or marked defaulted. The
+    /// dispute counts in the payer's reputation.
+    ///
+    /// # Arguments
+    /// * `payers` - Must hold the `Payers` role
+    /// * `invoice_id` - Invoice to dispute (unsettled and not defaulted)
+    pub fn open_dispute(env: Env, payers: Address, invoice_id: u64) -> Result<Invoice, AdelantaError> {
+        payers.require_auth();
 
-        client.initialize(
-            &admin,
-            &usdc_address,
-            &treasury,
-            &9000, // 90% advance
-            &50,   // 0.5% protocol fee
-        );
+        Self::require_role(&env, Role::Payers, &payers)?;
+        Self::require_not_paused(&env, Operation::Settle)?;
 
-        // Set timestamp
-        env.ledger().with_mut(|li| {
-            li.timestamp = 1000;
-        });
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
 
-        // 1. SME creates invoice
-        let invoice_id = client.create_invoice(
-            &sme,
-            &String::from_str(&env, "CORP-001"),
-            &1000_0000000, // 1000 USDC
-            &2000,         // Due date
-            &String::from_str(&env, "MX"),
-            &String::from_str(&env, "DESIGN"),
-            &200, // 2% fee
-        );
+        match invoice.status {
+            InvoiceStatus::Created
+            | InvoiceStatus::Acknowledged
+            | InvoiceStatus::Funded
+            | InvoiceStatus::PartiallySettled => {}
+            InvoiceStatus::Disputed => return Err(AdelantaError::InvoiceDisputed),
+            InvoiceStatus::Settled => return Err(AdelantaError::InvoiceAlreadySettled),
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => {
+                return Err(AdelantaError::InvoiceDefaulted)
+            }
+            InvoiceStatus::Cancelled => return Err(AdelantaError::InvoiceCancelled),
+        }
 
-        assert_eq!(invoice_id, 1);
+        Storage::set_disputed_status(&env, invoice_id, &invoice.status);
+        Self::transition(&env, &mut invoice, InvoiceStatus::Disputed);
+        Storage::set_invoice(&env, &invoice);
 
-        // Check invoice created
-        let invoice = client.get_invoice(&invoice_id);
-        assert_eq!(invoice.status, InvoiceStatus::Created);
-        assert_eq!(invoice.amount, 1000_0000000);
-        assert_eq!(invoice.advance_amount, 900_0000000); // 90%
+        risk::record(&env, &invoice, CreditEvent::Disputed);
+
+        Ok(invoice)
+    }
+
+    /// Apply an arbiter's decision on a disputed invoice (payers contract
+    /// only)
+    ///
+    /// - `Valid`: the invoice returns to its status before the dispute.
+    /// - `Adjusted(amount)`: likewise, with `amount` now due from the payer.
+    ///   It must be below the invoice amount, above what was already
+    ///   received and, once funded, cover the lenders' advance and the fee
+    ///   accrued so far. Settlement runs through the usual waterfall, so the cut
+    ///   comes out of the SME's remainder first. An unfunded invoice has
+    ///   its advance scaled down with the amount, which must stay above
+    ///   what lenders already contributed.
+    /// - `Invalid`: an unfunded invoice is cancelled and contributions are
+    ///   refunded to its lenders. A funded invoice defaults against the
+    ///   SME's reputation only, and the lenders' recourse against the SME
+    ///   is collected through the settlement contract's `recover_invoice`.
+    ///
+    /// # Arguments
+    /// * `payers` - Must hold the `Payers` role
+    /// * `arbiter` - Arbiter who resolved the dispute
+    /// * `invoice_id` - Disputed invoice
+    /// * `resolution` - Arbiter's decision
+    pub fn close_dispute(
+        env: Env,
+        payers: Address,
+        arbiter: Address,
+        invoice_id: u64,
+        resolution: DisputeResolution,
+    ) -> Result<Invoice, AdelantaError> {
+        payers.require_auth();
+
+        Self::require_role(&env, Role::Payers, &payers)?;
+        Self::require_not_paused(&env, Operation::Settle)?;
+
+        let mut invoice = Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+        if invoice.status != InvoiceStatus::Disputed {
+            return Err(AdelantaError::InvoiceNotDisputed);
+        }
+        let previous_status =
+            Storage::take_disputed_status(&env, invoice_id).ok_or(AdelantaError::InvoiceNotDisputed)?;
+        let funded = matches!(
+            previous_status,
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled
+        );
+
+        match resolution {
+            DisputeResolution::Valid => {
+                Self::transition(&env, &mut invoice, previous_status);
+                Storage::set_invoice(&env, &invoice);
+            }
+            DisputeResolution::Adjusted(amount) => {
+                if amount <= invoice.amount_received || amount >= invoice.amount {
+                    return Err(AdelantaError::InvalidAmount);
+                }
+                // Lenders are paid first, so the cut may not reach into
+                // their advance and the fee accrued so far
+                if funded
+                    && amount < invoice.advance_amount + interest::lender_fee(&invoice, env.ledger().timestamp())
+                {
+                    return Err(AdelantaError::InvalidAmount);
+                }
+                if !funded {
+                    let advance_amount = (invoice.advance_amount * amount) / invoice.amount;
+                    if advance_amount <= invoice.funded_amount {
+                        return Err(AdelantaError::InvalidAmount);
+                    }
+                    invoice.advance_amount = advance_amount;
+                }
+                invoice.face_value = (invoice.face_value * amount) / invoice.amount;
+                invoice.amount = amount;
+
+                Self::transition(&env, &mut invoice, previous_status);
+                Storage::set_invoice(&env, &invoice);
+            }
+            DisputeResolution::Invalid if funded => {
+                Self::default_invoice(&env, &mut invoice, false);
+            }
+            DisputeResolution::Invalid => {
+                Self::cancel(&env, &mut invoice, &arbiter);
+            }
+        }
+
+        Ok(invoice)
+    }
+
+    // ============================================================
+    // SECONDARY MARKET
+    // ============================================================
+
+    /// Transfer a lender position directly to another address
+    ///
+    /// The new holder receives the settlement share of the position.
+    pub fn transfer_position(
+        env: Env,
+        from: Address,
+        to: Address,
+        invoice_id: u64,
+    ) -> Result<PositionTransfer, AdelantaError> {
+        from.require_auth();
+        Self::require_not_paused(&env, Operation::Trade)?;
+
+        let invoice = Self::get_tradable_invoice(&env, invoice_id)?;
+        let transfer = Self::move_position(&env, invoice, &from, &to, 0)?;
+
+        Ok(transfer)
+    }
+
+    /// Sell a lender position on behalf of the marketplace contract
+    /// (`Marketplace` role only)
+    ///
+    /// The marketplace keeps the listings and has checked the seller's
+    /// asking price; `buyer` pays it to `seller` in the invoice's asset and
+    /// receives the whole position.
+    pub fn trade_position(
+        env: Env,
+        marketplace: Address,
+        seller: Address,
+        buyer: Address,
+        invoice_id: u64,
+        price: i128,
+    ) -> Result<PositionTransfer, AdelantaError> {
+        marketplace.require_auth();
+        buyer.require_auth();
+
+        Self::require_role(&env, Role::Marketplace, &marketplace)?;
+        Self::require_not_paused(&env, Operation::Trade)?;
+
+        if price <= 0 {
+            return Err(AdelantaError::InvalidAmount);
+        }
+
+        let invoice = Self::get_tradable_invoice(&env, invoice_id)?;
+
+        if buyer == seller {
+            return Err(AdelantaError::InvalidRecipient);
+        }
+
+        let token_client = token::Client::new(&env, &invoice.asset);
+        let registry = Storage::get_compliance_registry(&env);
+        pay_out(&env, &registry, &token_client, &buyer, &seller, invoice_id, price);
+
+        Self::move_position(&env, invoice, &seller, &buyer, price)
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get invoice details
+    pub fn get_invoice(env: Env, invoice_id: u64) -> Result<Invoice, AdelantaError> {
+        Storage::get_invoice(&env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)
+    }
+
+    /// Get a page of an SME's invoices, starting at `cursor` (0 for the
+    /// first page) with at most `limit` invoices
+    pub fn get_invoices_by_sme(env: Env, sme: Address, cursor: u32, limit: u32) -> InvoicePage {
+        Self::query_index(&env, InvoiceIndex::Sme(sme), cursor, limit)
+    }
+
+    /// Get a page of the invoices a lender currently holds a position in
+    pub fn get_invoices_by_lender(env: Env, lender: Address, cursor: u32, limit: u32) -> InvoicePage {
+        Self::query_index(&env, InvoiceIndex::Lender(lender), cursor, limit)
+    }
+
+    /// Get a page of the invoices owed by a corporate payer
+    pub fn get_invoices_by_payer(env: Env, payer_id: String, cursor: u32, limit: u32) -> InvoicePage {
+        Self::query_index(&env, InvoiceIndex::Payer(payer_id), cursor, limit)
+    }
+
+    /// Get a page of the invoices currently in `status`
+    pub fn get_invoices_by_status(
+        env: Env,
+        status: InvoiceStatus,
+        cursor: u32,
+        limit: u32,
+    ) -> InvoicePage {
+        Self::query_index(&env, InvoiceIndex::Status(status), cursor, limit)
+    }
+
+    /// Get the document fingerprint an invoice was registered with
+    pub fn get_invoice_fingerprint(env: Env, invoice_id: u64) -> Option<BytesN<32>> {
+        Storage::get_invoice_fingerprint(&env, invoice_id)
+    }
+
+    /// Get the invoice currently registered under a document fingerprint
+    pub fn find_invoice_by_fingerprint(env: Env, fingerprint: BytesN<32>) -> Option<u64> {
+        Storage::get_fingerprint_invoice(&env, &fingerprint)
+    }
+
+    /// Get the lenders that funded an invoice and their contributions
+    pub fn get_invoice_lenders(env: Env, invoice_id: u64) -> Vec<LenderContribution> {
+        Storage::get_contributions(&env, invoice_id)
+    }
+
+    /// Get a lender's position in an invoice
+    ///
+    /// `paid` is the lender's pro rata share of the settlement proceeds paid
+    /// out so far. Lets lenders such as pool contracts value their book
+    /// without reading whole invoices.
+    pub fn get_lender_position(env: Env, invoice_id: u64, lender: Address) -> Option<LenderPosition> {
+        let invoice = Storage::get_invoice(&env, invoice_id)?;
+        let (_, contribution) = Self::find_contribution(&env, invoice_id, &lender).ok()?;
+
+        let paid = match invoice.status {
+            InvoiceStatus::Cancelled => contribution.amount,
+            _ if invoice.funded_amount == invoice.advance_amount => {
+                (invoice.lender_paid * contribution.amount) / invoice.advance_amount
+            }
+            _ => 0,
+        };
+
+        Some(LenderPosition {
+            invoice_id,
+            lender,
+            amount: contribution.amount,
+            paid,
+            closed: matches!(
+                invoice.status,
+                InvoiceStatus::Settled | InvoiceStatus::Cancelled | InvoiceStatus::WrittenOff
+            ),
+        })
+    }
+
+    /// Get the history of lender position transfers for an invoice
+    pub fn get_position_history(env: Env, invoice_id: u64) -> Vec<PositionTransfer> {
+        Storage::get_position_history(&env, invoice_id)
+    }
+
+    /// Get contract configuration
+    pub fn get_config(env: Env) -> Result<ContractConfig, AdelantaError> {
+        Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)
+    }
+
+    /// Get the price oracle settings for fiat-denominated invoices
+    pub fn get_fx_config(env: Env) -> Option<FxConfig> {
+        Storage::get_fx_config(&env)
+    }
+
+    /// Get the compliance registry checked on invoice creation and funding
+    pub fn get_compliance_registry(env: Env) -> Option<Address> {
+        Storage::get_compliance_registry(&env)
+    }
+
+    /// Get the risk engine pricing invoices and limiting exposure
+    pub fn get_risk_engine(env: Env) -> Option<Address> {
+        Storage::get_risk_engine(&env)
+    }
+
+    /// Get contract stats, with funded and settled volume per asset
+    pub fn get_stats(env: Env) -> ProtocolStats {
+        let mut assets = Vec::new(&env);
+        for asset in Storage::get_assets(&env).iter() {
+            assets.push_back(Storage::get_asset_stats(&env, &asset));
+        }
+        ProtocolStats {
+            total_invoices: Storage::get_total_invoices(&env),
+            assets,
+        }
+    }
+
+    /// Get an approved asset and its invoice limits
+    pub fn get_asset(env: Env, asset: Address) -> Option<AssetConfig> {
+        Storage::get_asset(&env, &asset)
+    }
+
+    /// Get every approved asset, including revoked ones
+    pub fn get_assets(env: Env) -> Vec<AssetConfig> {
+        let mut assets = Vec::new(&env);
+        for asset in Storage::get_assets(&env).iter() {
+            if let Some(asset_config) = Storage::get_asset(&env, &asset) {
+                assets.push_back(asset_config);
+            }
+        }
+        assets
+    }
+
+    // ============================================================
+    // ADMIN FUNCTIONS
+    // ============================================================
+
+    /// Update contract configuration
+    ///
+    /// Treasury, grace period and the acknowledgement requirement need the
+    /// `Admin` role; advance and protocol fee parameters need `FeeManager`.
+    #[allow(clippy::too_many_arguments, reason = "one optional argument per configurable field")]
+    pub fn update_config(
+        env: Env,
+        caller: Address,
+        new_treasury: Option<Address>,
+        new_advance_bps: Option<u32>,
+        new_protocol_fee_bps: Option<u32>,
+        new_grace_period_secs: Option<u64>,
+        new_require_acknowledgement: Option<bool>,
+        new_acknowledged_advance_bps: Option<u32>,
+    ) -> Result<(), AdelantaError> {
+        caller.require_auth();
+
+        let mut config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
+
+        if new_treasury.is_some()
+            || new_grace_period_secs.is_some()
+            || new_require_acknowledgement.is_some()
+        {
+            Self::require_role(&env, Role::Admin, &caller)?;
+        }
+        if new_advance_bps.is_some()
+            || new_protocol_fee_bps.is_some()
+            || new_acknowledged_advance_bps.is_some()
+        {
+            Self::require_role(&env, Role::FeeManager, &caller)?;
+        }
+
+        if let Some(treasury) = new_treasury {
+            config.treasury = treasury;
+        }
+
+        if let Some(advance_bps) = new_advance_bps {
+            if advance_bps > 10000 {
+                return Err(AdelantaError::InvalidAdvancePercentage);
+            }
+            config.default_advance_bps = advance_bps;
+        }
+
+        if let Some(fee_bps) = new_protocol_fee_bps {
+            if fee_bps > 1000 {
+                return Err(AdelantaError::InvalidFeePercentage);
+            }
+            config.protocol_fee_bps = fee_bps;
+        }
+
+        if let Some(grace_period_secs) = new_grace_period_secs {
+            config.grace_period_secs = grace_period_secs;
+        }
+
+        if let Some(require_acknowledgement) = new_require_acknowledgement {
+            config.require_acknowledgement = require_acknowledgement;
+        }
+
+        if let Some(advance_bps) = new_acknowledged_advance_bps {
+            if advance_bps > 10000 {
+                return Err(AdelantaError::InvalidAdvancePercentage);
+            }
+            config.acknowledged_advance_bps = advance_bps;
+        }
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::config_updated(&env, &caller, &config);
+
+        Ok(())
+    }
+
+    /// Set the price oracle used for fiat-denominated invoices (admin only)
+    ///
+    /// # Arguments
+    /// * `oracle` - SEP-40 price oracle quoting currencies and assets in a common base
+    /// * `max_price_age_secs` - Maximum age of an oracle price
+    /// * `max_slippage_bps` - Maximum FX rate deviation accepted between the
+    ///   quote and funding, or between the oracle and an attested conversion
+    pub fn set_fx_config(
+        env: Env,
+        admin: Address,
+        oracle: Address,
+        max_price_age_secs: u64,
+        max_slippage_bps: u32,
+    ) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if max_slippage_bps > 10000 {
+            return Err(AdelantaError::InvalidFeePercentage);
+        }
+
+        let fx_config = FxConfig {
+            oracle,
+            max_price_age_secs,
+            max_slippage_bps,
+        };
+        Storage::set_fx_config(&env, &fx_config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::fx_config_updated(&env, &admin, &fx_config);
+
+        Ok(())
+    }
+
+    /// Set the compliance registry SMEs and lenders are checked against
+    /// (compliance officer only)
+    ///
+    /// SMEs must be eligible to create invoices, and lenders to fund them or
+    /// acquire positions, at the levels the registry requires for the
+    /// invoice's country. `None` disables the checks.
+    pub fn set_compliance_registry(
+        env: Env,
+        caller: Address,
+        registry: Option<Address>,
+    ) -> Result<(), AdelantaError> {
+        caller.require_auth();
+
+        Self::require_role(&env, Role::Compliance, &caller)?;
+
+        Storage::set_compliance_registry(&env, registry.clone());
+        Storage::extend_instance_ttl(&env);
+
+        Events::compliance_registry_set(&env, &caller, &registry);
+
+        Ok(())
+    }
+
+    /// Set the risk engine pricing invoices and limiting exposure (admin only)
+    ///
+    /// New invoices are advanced at the rate of the engine's quote and must
+    /// carry at least its fee; fundings the engine's exposure limits reject
+    /// fail. Every credit event is reported to the engine. `None` prices
+    /// invoices at the default advance rate without limits.
+    pub fn set_risk_engine(env: Env, admin: Address, engine: Option<Address>) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        Storage::set_risk_engine(&env, engine.clone());
+        Storage::extend_instance_ttl(&env);
+
+        Events::risk_engine_set(&env, &admin, &engine);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // ACCESS CONTROL
+    // ============================================================
+
+    /// Grant a role to an account (admin only)
+    pub fn grant_role(
+        env: Env,
+        admin: Address,
+        role: Role,
+        account: Address,
+    ) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if !Storage::has_role(&env, role, &account) {
+            Storage::grant_role(&env, role, &account);
+            Events::role_granted(&env, role, &account, &admin);
+        }
+
+        Ok(())
+    }
+
+    /// Revoke a role from an account (admin only)
+    pub fn revoke_role(
+        env: Env,
+        admin: Address,
+        role: Role,
+        account: Address,
+    ) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+        Self::remove_role(&env, role, &account, &admin)
+    }
+
+    /// Give up a role held by the caller
+    pub fn renounce_role(env: Env, account: Address, role: Role) -> Result<(), AdelantaError> {
+        account.require_auth();
+
+        Self::require_role(&env, role, &account)?;
+        Self::remove_role(&env, role, &account, &account)
+    }
+
+    /// Check whether an account holds a role
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        Storage::has_role(&env, role, &account)
+    }
+
+    // ============================================================
+    // EMERGENCY PAUSE
+    // ============================================================
+
+    /// Pause an operation (guardian, i.e. `Pauser` role)
+    ///
+    /// Operations are paused independently, e.g. funding can be stopped
+    /// while settlement stays open so lenders still get repaid.
+    pub fn pause(env: Env, guardian: Address, operation: Operation) -> Result<(), AdelantaError> {
+        guardian.require_auth();
+
+        Self::require_role(&env, Role::Pauser, &guardian)?;
+
+        Storage::set_paused(&env, operation, true);
+        Storage::extend_instance_ttl(&env);
+
+        Events::paused(&env, operation, &guardian);
+
+        Ok(())
+    }
+
+    /// Resume a paused operation (admin only)
+    pub fn unpause(env: Env, admin: Address, operation: Operation) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        Storage::set_paused(&env, operation, false);
+        Storage::extend_instance_ttl(&env);
+
+        Events::unpaused(&env, operation, &admin);
+
+        Ok(())
+    }
+
+    /// Check whether an operation is paused
+    pub fn is_paused(env: Env, operation: Operation) -> bool {
+        Storage::is_paused(&env, operation)
+    }
+
+    // ============================================================
+    // UPGRADES
+    // ============================================================
+
+    /// Replace the contract code with an uploaded Wasm (admin only)
+    ///
+    /// Storage is kept as is. If the new build changes a stored layout, call
+    /// `migrate` afterwards to rewrite existing records.
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) -> Result<(), AdelantaError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+        Storage::extend_instance_ttl(&env);
+
+        Events::upgraded(&env, &admin, &new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Rewrite up to `limit` invoice IDs into the current storage layout,
+    /// handing their SMEs' reputation over to the risk engine (admin only)
+    ///
+    /// Reputation is only handed over while a risk engine is set; a first
+    /// call with a `limit` of 0 grants the roles of a schema version 0
+    /// admin, so the engine can be set before any invoice is migrated. Call
+    /// repeatedly until it returns 0, the number of IDs left to migrate;
+    /// the schema version is bumped once the last batch is done.
+    pub fn migrate(env: Env, admin: Address, limit: u32) -> Result<u64, AdelantaError> {
+        admin.require_auth();
+
+        let config = Storage::get_config(&env).ok_or(AdelantaError::NotInitialized)?;
+
+        // Before schema versioning the admin was only kept in the config
+        if Storage::get_role_members(&env, Role::Admin) == 0 && admin == config.admin {
+            for role in ALL_ROLES {
+                Storage::grant_role(&env, role, &config.admin);
+            }
+        }
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        let from_version = Storage::get_schema_version(&env);
+        if from_version >= SCHEMA_VERSION {
+            return Ok(0);
+        }
+        Storage::set_config(&env, &config);
+
+        let next_id = Storage::get_next_invoice_id(&env);
+        let mut cursor = Storage::get_migration_cursor(&env);
+        let end = next_id.min(cursor + limit as u64);
+
+        while cursor < end {
+            if let Some(invoice) = Storage::get_invoice(&env, cursor) {
+                let contributions = Storage::get_contributions(&env, cursor);
+                if !contributions.is_empty() {
+                    Storage::set_contributions(&env, cursor, &contributions);
+                }
+                risk::import_legacy_reputation(&env, &invoice.sme);
+                Storage::set_invoice(&env, &invoice);
+                Self::index_invoice(&env, &invoice);
+                // Book the outstanding principal of invoices funded before
+                // exposure totals existed
+                if matches!(invoice.status, InvoiceStatus::Funded | InvoiceStatus::PartiallySettled) {
+                    risk::record(&env, &invoice, CreditEvent::Funded);
+                }
+            }
+            cursor += 1;
+        }
+
+        let remaining = next_id - cursor;
+        if remaining == 0 {
+            Storage::store_legacy_asset(&env);
+            Storage::set_schema_version(&env, SCHEMA_VERSION);
+            Storage::remove_migration_cursor(&env);
+            Events::migrated(&env, from_version, SCHEMA_VERSION);
+        } else {
+            Storage::set_migration_cursor(&env, cursor);
+        }
+        Storage::extend_instance_ttl(&env);
+
+        Ok(remaining)
+    }
+
+    /// Get the storage schema version
+    pub fn schema_version(env: Env) -> u32 {
+        Storage::get_schema_version(&env)
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    /// Validate and store a new invoice; `amount` is the face value in
+    /// `currency` for fiat-denominated invoices
+    #[allow(clippy::too_many_arguments, reason = "shared by both invoice constructors and takes their arguments")]
+    fn new_invoice(
+        env: &Env,
+        sme: Address,
+        payer_id: String,
+        fingerprint: BytesN<32>,
+        asset: Address,
+        currency: Option<Symbol>,
+        amount: i128,
+        due_date: u64,
+        country: String,
+        industry: String,
+        fee_bps: u32,
+        fee_model: FeeModel,
+    ) -> Result<u64, AdelantaError> {
+        Self::require_not_paused(env, Operation::Create)?;
+
+        // Get config
+        let config = Storage::get_config(env).ok_or(AdelantaError::NotInitialized)?;
+
+        // Validate asset and amount against the asset's limits
+        let asset_config = Storage::get_asset(env, &asset)
+            .filter(|asset_config| asset_config.enabled)
+            .ok_or(AdelantaError::AssetNotSupported)?;
+        if amount <= 0 {
+            return Err(AdelantaError::InvalidAmount);
+        }
+
+        // Fiat-denominated invoices are priced in the asset at the current rate
+        let (face_value, funding_fx_rate, amount) = match &currency {
+            Some(currency) => {
+                let rate = Self::fx_rate(env, currency, &asset)?;
+                (amount, Some(rate), fx::to_asset(amount, rate))
+            }
+            None => (0, None, amount),
+        };
+        if amount < asset_config.min_invoice_amount || amount > asset_config.max_invoice_amount {
+            return Err(AdelantaError::InvalidAmount);
+        }
+
+        // Validate fee
+        if fee_bps > 1000 {
+            // Max 10%
+            return Err(AdelantaError::InvalidFeePercentage);
+        }
+
+        compliance::require_eligible(env, &sme, Party::Sme, &country)?;
+
+        // Price the invoice from the SME's and payer's risk scores
+        let (advance_bps, min_fee_bps) = risk::quote(env, &sme, &payer_id, &country, &industry)?
+            .unwrap_or((config.default_advance_bps, 0));
+        if fee_bps < min_fee_bps {
+            return Err(AdelantaError::FeeBelowMinimum);
+        }
+
+        // Validate interest rates
+        if let FeeModel::Accrued(terms) = &fee_model {
+            if terms.annual_rate_bps > 10000 || terms.late_rate_bps > 10000 {
+                return Err(AdelantaError::InvalidInterestRate);
+            }
+        }
+
+        // Reject a real-world invoice that is already registered, so it
+        // cannot be factored twice
+        if Storage::get_fingerprint_invoice(env, &fingerprint).is_some() {
+            return Err(AdelantaError::DuplicateInvoice);
+        }
+
+        // Calculate advance amount at the quoted rate (default 90%)
+        let advance_amount = (amount * advance_bps as i128) / 10000;
+
+        // Get next invoice ID
+        let invoice_id = Storage::get_next_invoice_id(env);
+        if Storage::has_invoice(env, invoice_id) {
+            return Err(AdelantaError::InvoiceAlreadyExists);
+        }
+        Storage::set_next_invoice_id(env, invoice_id + 1);
+
+        // Create invoice
+        let invoice = Invoice {
+            id: invoice_id,
+            sme: sme.clone(),
+            payer_id,
+            asset,
+            currency,
+            face_value,
+            face_value_received: 0,
+            funding_fx_rate,
+            settlement_fx_rate: None,
+            amount,
+            advance_amount,
+            fee_bps,
+            fee_model,
+            lender: None,
+            funded_amount: 0,
+            amount_received: 0,
+            lender_paid: 0,
+            protocol_fee_paid: 0,
+            status: InvoiceStatus::Created,
+            created_at: env.ledger().timestamp(),
+            funded_at: None,
+            settled_at: None,
+            defaulted_at: None,
+            due_date,
+            country,
+            industry,
+        };
+
+        // Store invoice
+        Storage::set_invoice(env, &invoice);
+        Storage::increment_total_invoices(env);
+        Storage::increment_asset_invoices(env, &invoice.asset);
+        Storage::register_fingerprint(env, &fingerprint, invoice_id);
+        Self::index_invoice(env, &invoice);
+
+        risk::record(env, &invoice, CreditEvent::Created);
+
+        Events::invoice_created(env, &invoice);
+
+        Ok(invoice_id)
+    }
+
+    /// Current oracle rate of `currency` in `asset`
+    fn fx_rate(env: &Env, currency: &Symbol, asset: &Address) -> Result<i128, AdelantaError> {
+        let fx_config = Storage::get_fx_config(env).ok_or(AdelantaError::OracleNotConfigured)?;
+        Ok(fx::rate(env, &fx_config, currency, asset)?)
+    }
+
+    /// Price a fiat-denominated invoice at the current rate when its first
+    /// contribution arrives, rejecting rates that moved beyond the slippage
+    /// tolerance since the invoice was quoted
+    fn fix_funding_rate(env: &Env, invoice: &mut Invoice) -> Result<(), AdelantaError> {
+        let Some(currency) = invoice.currency.clone() else {
+            return Ok(());
+        };
+        let fundable = matches!(invoice.status, InvoiceStatus::Created | InvoiceStatus::Acknowledged);
+        if !fundable || invoice.funded_amount > 0 {
+            return Ok(());
+        }
+
+        let fx_config = Storage::get_fx_config(env).ok_or(AdelantaError::OracleNotConfigured)?;
+        let rate = fx::rate(env, &fx_config, &currency, &invoice.asset)?;
+        let quoted = invoice.funding_fx_rate.unwrap_or(rate);
+        if !fx::within_slippage(&fx_config, quoted, rate) {
+            return Err(AdelantaError::FxSlippageExceeded);
+        }
+
+        // Keep the advance rate the invoice was created (or acknowledged) with
+        let amount = fx::to_asset(invoice.face_value, rate);
+        let limits = Storage::get_asset(env, &invoice.asset).ok_or(AdelantaError::AssetNotSupported)?;
+        if amount < limits.min_invoice_amount || amount > limits.max_invoice_amount {
+            return Err(AdelantaError::InvalidAmount);
+        }
+        invoice.advance_amount = (invoice.advance_amount * amount) / invoice.amount;
+        invoice.amount = amount;
+        invoice.funding_fx_rate = Some(rate);
+
+        Ok(())
+    }
+
+    /// Cancel an unfunded invoice, refunding its contributions and
+    /// releasing its fingerprint and reserved exposure
+    fn cancel(env: &Env, invoice: &mut Invoice, cancelled_by: &Address) {
+        let reserved = invoice.funded_amount > 0;
+        Self::refund_contributions(env, invoice);
+
+        Self::transition(env, invoice, InvoiceStatus::Cancelled);
+        Storage::set_invoice(env, invoice);
+        if reserved {
+            risk::record(env, invoice, CreditEvent::Cancelled);
+        }
+
+        // The real-world invoice may be registered again
+        if let Some(fingerprint) = Storage::get_invoice_fingerprint(env, invoice.id) {
+            Storage::release_fingerprint(env, &fingerprint, invoice.id);
+        }
+
+        Events::invoice_cancelled(env, invoice, cancelled_by);
+    }
+
+    /// Default a funded invoice and release its exposure. An invoice found
+    /// invalid in a dispute is not the payer's fault and only counts
+    /// against the SME.
+    fn default_invoice(env: &Env, invoice: &mut Invoice, payer_at_fault: bool) {
+        Self::transition(env, invoice, InvoiceStatus::Defaulted);
+        invoice.defaulted_at = Some(env.ledger().timestamp());
+        Storage::set_invoice(env, invoice);
+
+        let event = if payer_at_fault {
+            CreditEvent::Defaulted
+        } else {
+            CreditEvent::Invalidated
+        };
+        risk::record(env, invoice, event);
+        Storage::extend_instance_ttl(env);
+
+        Events::invoice_defaulted(env, invoice);
+    }
+
+    /// Return the partial contributions an unfunded invoice holds to its lenders
+    fn refund_contributions(env: &Env, invoice: &mut Invoice) {
+        if invoice.funded_amount == 0 {
+            return;
+        }
+
+        let token_client = token::Client::new(env, &invoice.asset);
+        let contract_address = env.current_contract_address();
+        let registry = Storage::get_compliance_registry(env);
+        for contribution in Storage::get_contributions(env, invoice.id).iter() {
+            pay_out(env, &registry, &token_client, &contract_address, &contribution.lender, invoice.id, contribution.amount);
+            Storage::index_remove(env, &InvoiceIndex::Lender(contribution.lender), invoice.id);
+        }
+        invoice.funded_amount = 0;
+    }
+
+    /// Check an invoice can take a regular settlement payment
+    fn require_settleable(invoice: &Invoice) -> Result<(), AdelantaError> {
+        match invoice.status {
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled => Ok(()),
+            InvoiceStatus::Settled => Err(AdelantaError::InvoiceAlreadySettled),
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => {
+                Err(AdelantaError::InvoiceDefaulted)
+            }
+            InvoiceStatus::Disputed => Err(AdelantaError::InvoiceDisputed),
+            _ => Err(AdelantaError::InvoiceNotFunded),
+        }
+    }
+
+    /// Move an invoice to `status`, keeping the status index in sync and
+    /// publishing the change
+    fn transition(env: &Env, invoice: &mut Invoice, status: InvoiceStatus) {
+        if invoice.status == status {
+            return;
+        }
+        Storage::index_remove(env, &InvoiceIndex::Status(invoice.status.clone()), invoice.id);
+        Storage::index_add(env, &InvoiceIndex::Status(status.clone()), invoice.id);
+        let from = core::mem::replace(&mut invoice.status, status);
+
+        Events::status_changed(env, invoice, &from);
+    }
+
+    /// Add an invoice to every index it belongs to
+    fn index_invoice(env: &Env, invoice: &Invoice) {
+        Storage::index_add(env, &InvoiceIndex::Sme(invoice.sme.clone()), invoice.id);
+        Storage::index_add(env, &InvoiceIndex::Payer(invoice.payer_id.clone()), invoice.id);
+        Storage::index_add(env, &InvoiceIndex::Status(invoice.status.clone()), invoice.id);
+        for contribution in Storage::get_contributions(env, invoice.id).iter() {
+            Storage::index_add(env, &InvoiceIndex::Lender(contribution.lender), invoice.id);
+        }
+    }
+
+    /// Load one page of an index, capped at `MAX_PAGE_LIMIT` invoices
+    fn query_index(env: &Env, index: InvoiceIndex, cursor: u32, limit: u32) -> InvoicePage {
+        let ids = Storage::get_index_range(env, &index, cursor, limit.min(MAX_PAGE_LIMIT));
+
+        let mut invoices = Vec::new(env);
+        for id in ids.iter() {
+            if let Some(invoice) = Storage::get_invoice(env, id) {
+                invoices.push_back(invoice);
+            }
+        }
+
+        InvoicePage {
+            invoices,
+            next_cursor: cursor + ids.len(),
+            total: Storage::get_index_len(env, &index),
+        }
+    }
+
+    fn require_not_paused(env: &Env, operation: Operation) -> Result<(), AdelantaError> {
+        if Storage::is_paused(env, operation) {
+            return Err(AdelantaError::Paused);
+        }
+        Ok(())
+    }
+
+    fn require_role(env: &Env, role: Role, account: &Address) -> Result<(), AdelantaError> {
+        if !Storage::has_role(env, role, account) {
+            return Err(AdelantaError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    fn remove_role(
+        env: &Env,
+        role: Role,
+        account: &Address,
+        sender: &Address,
+    ) -> Result<(), AdelantaError> {
+        if !Storage::has_role(env, role, account) {
+            return Ok(());
+        }
+        if role == Role::Admin && Storage::get_role_members(env, role) <= 1 {
+            return Err(AdelantaError::LastAdmin);
+        }
+
+        Storage::revoke_role(env, role, account);
+        Events::role_revoked(env, role, account, sender);
+
+        Ok(())
+    }
+
+    /// Record a lender's contribution and complete funding once the
+    /// advance is fully subscribed
+    fn contribute(
+        env: &Env,
+        lender: Address,
+        mut invoice: Invoice,
+        amount: i128,
+    ) -> Result<FundingEvent, AdelantaError> {
+        let config = Storage::get_config(env).ok_or(AdelantaError::NotInitialized)?;
+        Self::require_not_paused(env, Operation::Fund)?;
+
+        // Check invoice status
+        match invoice.status {
+            InvoiceStatus::Acknowledged => {}
+            InvoiceStatus::Created if !config.require_acknowledgement => {}
+            InvoiceStatus::Created => return Err(AdelantaError::NotAcknowledged),
+            InvoiceStatus::Disputed => return Err(AdelantaError::InvoiceDisputed),
+            _ => return Err(AdelantaError::InvoiceAlreadyFunded),
+        }
+
+        // Check not expired
+        if env.ledger().timestamp() > invoice.due_date {
+            return Err(AdelantaError::InvoiceExpired);
+        }
+
+        if amount <= 0 {
+            return Err(AdelantaError::InvalidAmount);
+        }
+        if invoice.funded_amount + amount > invoice.advance_amount {
+            return Err(AdelantaError::FundingExceedsRemaining);
+        }
+        // The first contribution reserves the whole advance, so later
+        // tranches complete a funding already within the limits
+        let reserving = invoice.funded_amount == 0;
+        if reserving {
+            risk::check_exposure(env, &invoice)?;
+        }
+        compliance::require_eligible(env, &lender, Party::Lender, &invoice.country)?;
+
+        // Merge into the lender's existing share, or add a new one
+        let mut contributions = Storage::get_contributions(env, invoice.id);
+        let existing = contributions.iter().position(|c| c.lender == lender);
+        match existing {
+            Some(index) => {
+                let mut contribution = contributions.get_unchecked(index as u32);
+                contribution.amount += amount;
+                contributions.set(index as u32, contribution);
+            }
+            None => {
+                if contributions.len() >= MAX_LENDERS_PER_INVOICE {
+                    return Err(AdelantaError::TooManyLenders);
+                }
+                contributions.push_back(LenderContribution {
+                    lender: lender.clone(),
+                    amount,
+                });
+            }
+        }
+
+        // Hold the contribution in the contract until fully subscribed
+        let token_client = token::Client::new(env, &invoice.asset);
+        let contract_address = env.current_contract_address();
+        token_client.transfer(&lender, &contract_address, &amount);
+
+        invoice.funded_amount += amount;
+        if invoice.lender.is_none() {
+            invoice.lender = Some(lender.clone());
+        }
+
+        Events::invoice_contribution(env, &invoice, &lender, amount);
+
+        if invoice.funded_amount == invoice.advance_amount {
+            // Release the advance to the SME
+            let registry = Storage::get_compliance_registry(env);
+            pay_out(env, &registry, &token_client, &contract_address, &invoice.sme, invoice.id, invoice.advance_amount);
+
+            Self::transition(env, &mut invoice, InvoiceStatus::Funded);
+            invoice.funded_at = Some(env.ledger().timestamp());
+            Storage::add_volume_funded(env, &invoice.asset, invoice.advance_amount);
+
+            Events::invoice_funded(env, &invoice, &lender);
+        }
+
+        Storage::set_contributions(env, invoice.id, &contributions);
+        Storage::set_invoice(env, &invoice);
+        Storage::index_add(env, &InvoiceIndex::Lender(lender.clone()), invoice.id);
+        if invoice.status == InvoiceStatus::Funded {
+            risk::record(env, &invoice, CreditEvent::Funded);
+        } else if reserving {
+            risk::record(env, &invoice, CreditEvent::Reserved);
+        }
+        Storage::extend_instance_ttl(env);
+
+        Ok(FundingEvent {
+            invoice_id: invoice.id,
+            lender,
+            amount,
+            timestamp: env.ledger().timestamp(),
+        })
+    }
+
+    /// Load an invoice whose lender positions may change hands
+    /// (funded and not yet fully settled)
+    fn get_tradable_invoice(env: &Env, invoice_id: u64) -> Result<Invoice, AdelantaError> {
+        let invoice = Storage::get_invoice(env, invoice_id).ok_or(AdelantaError::InvoiceNotFound)?;
+
+        match invoice.status {
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled => Ok(invoice),
+            InvoiceStatus::Settled => Err(AdelantaError::InvoiceAlreadySettled),
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => {
+                Err(AdelantaError::InvoiceDefaulted)
+            }
+            InvoiceStatus::Disputed => Err(AdelantaError::InvoiceDisputed),
+            _ => Err(AdelantaError::InvoiceNotFunded),
+        }
+    }
+
+    /// Find a lender's contribution index and entry
+    fn find_contribution(
+        env: &Env,
+        invoice_id: u64,
+        lender: &Address,
+    ) -> Result<(u32, LenderContribution), AdelantaError> {
+        let contributions = Storage::get_contributions(env, invoice_id);
+        let index = contributions
+            .iter()
+            .position(|c| c.lender == *lender)
+            .ok_or(AdelantaError::PositionNotFound)? as u32;
+
+        Ok((index, contributions.get_unchecked(index)))
+    }
+
+    /// Reassign `from`'s whole position to `to`, merging into any position
+    /// `to` already holds, and record the transfer in the history
+    fn move_position(
+        env: &Env,
+        mut invoice: Invoice,
+        from: &Address,
+        to: &Address,
+        price: i128,
+    ) -> Result<PositionTransfer, AdelantaError> {
+        if from == to {
+            return Err(AdelantaError::InvalidRecipient);
+        }
+        compliance::require_eligible(env, to, Party::Lender, &invoice.country)?;
+
+        let (index, position) = Self::find_contribution(env, invoice.id, from)?;
+
+        let mut contributions = Storage::get_contributions(env, invoice.id);
+        match contributions.iter().position(|c| c.lender == *to) {
+            Some(to_index) => {
+                let mut existing = contributions.get_unchecked(to_index as u32);
+                existing.amount += position.amount;
+                contributions.set(to_index as u32, existing);
+                contributions.remove(index);
+            }
+            None => {
+                contributions.set(
+                    index,
+                    LenderContribution {
+                        lender: to.clone(),
+                        amount: position.amount,
+                    },
+                );
+            }
+        }
+        Storage::set_contributions(env, invoice.id, &contributions);
+        Storage::index_remove(env, &InvoiceIndex::Lender(from.clone()), invoice.id);
+        Storage::index_add(env, &InvoiceIndex::Lender(to.clone()), invoice.id);
+
+        if invoice.lender.as_ref() == Some(from) {
+            invoice.lender = Some(to.clone());
+            Storage::set_invoice(env, &invoice);
+        }
+
+        let transfer = PositionTransfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount: position.amount,
+            price,
+            timestamp: env.ledger().timestamp(),
+        };
+        Storage::add_position_transfer(env, invoice.id, &transfer);
+
+        Events::position_transferred(env, invoice.id, &transfer);
+
+        Ok(transfer)
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{
+        InvoiceCancelledEvent, InvoiceCreatedEvent, InvoiceFundedEvent, InvoiceStatusEvent, EVENTS_VERSION,
+    };
+    use adelanta_types::fx::{OracleAsset, PriceData, PriceOracle};
+    use crate::types::AssetStats;
+    use adelanta_types::{DayCount, InterestTerms};
+    use settlement::SettlementError;
+
+    /// The contract as built before storage schema versioning
+    #[allow(clippy::too_many_arguments)]
+    mod v0 {
+        soroban_sdk::contractimport!(file = "testdata/adelanta_invoice_v0.wasm");
+    }
+
+    /// The KYC/KYB registry of the `adelanta_compliance` contract
+    mod compliance_registry {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_compliance.wasm"
+        );
+    }
+
+    /// The `adelanta_risk` contract taking over reputation when migrating
+    mod risk_engine {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_risk.wasm"
+        );
+    }
+
+    /// The `adelanta_settlement` contract paying out and recording payments
+    mod settlement {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_settlement.wasm"
+        );
+    }
+    use soroban_sdk::{
+        contract, contractimpl, symbol_short,
+        testutils::{Address as _, EnvTestConfig, Events as _, Ledger},
+        token::{StellarAssetClient, Client as TokenClient},
+        vec, Bytes, Env, IntoVal, TryIntoVal, Val, Vec,
+    };
+
+    fn create_usdc_token<'a>(env: &Env, admin: &Address) -> (Address, TokenClient<'a>, StellarAssetClient<'a>) {
+        let contract_address = env.register_stellar_asset_contract_v2(admin.clone());
+        (
+            contract_address.address(),
+            TokenClient::new(env, &contract_address.address()),
+            StellarAssetClient::new(env, &contract_address.address()),
+        )
+    }
+
+    /// Settlement contract for the invoice contract, granted its
+    /// `Settlement` role
+    fn register_settlement<'a>(env: &Env, contract_id: &Address, admin: &Address) -> settlement::Client<'a> {
+        let settlement_id = env.register_contract_wasm(None, settlement::WASM);
+        let settlement = settlement::Client::new(env, &settlement_id);
+        settlement.initialize(contract_id);
+        AdelantaInvoiceContractClient::new(env, contract_id).grant_role(admin, &Role::Settlement, &settlement_id);
+        settlement
+    }
+
+    struct TestContext {
+        env: Env,
+        contract_id: Address,
+        client: AdelantaInvoiceContractClient<'static>,
+        settlement: settlement::Client<'static>,
+        admin: Address,
+        treasury: Address,
+        sme: Address,
+        usdc: TokenClient<'static>,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    /// Initialized contract (90% advance, 0.5% protocol fee) and its
+    /// settlement contract at timestamp 1000, with 10,000 USDC minted to the
+    /// admin for settlements
+    fn setup() -> TestContext {
+        setup_with_env(Env::default())
+    }
+
+    fn setup_with_env(env: Env) -> TestContext {
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+
+        let (usdc_address, usdc, usdc_admin) = create_usdc_token(&env, &admin);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
+        let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+        client.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let settlement = register_settlement(&env, &contract_id, &admin);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            contract_id,
+            client,
+            settlement,
+            admin,
+            treasury,
+            sme,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Create a 2% fee invoice for the context's SME, due at timestamp 2000
+    fn create_test_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        ctx.client.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    /// Fingerprint unique to the next invoice, stable across test runs
+    fn next_fingerprint(ctx: &TestContext) -> BytesN<32> {
+        let id = ctx.env.as_contract(&ctx.contract_id, || Storage::get_next_invoice_id(&ctx.env));
+        ctx.env
+            .crypto()
+            .sha256(&Bytes::from_array(&ctx.env, &id.to_be_bytes()))
+            .into()
+    }
+
+    /// Generate a lender holding 10,000 USDC
+    fn funded_lender(ctx: &TestContext) -> Address {
+        let lender = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&lender, &10000_0000000);
+        lender
+    }
+
+    /// Last event published in the environment, wrapped for comparison
+    fn last_event(env: &Env) -> Vec<(Address, Vec<Val>, Val)> {
+        vec![env, env.events().all().last().unwrap()]
+    }
+
+    #[test]
+    fn test_full_invoice_lifecycle() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        // Setup accounts
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+        let lender = Address::generate(&env);
+
+        // Setup USDC token
+        let (usdc_address, usdc_client, usdc_admin) = create_usdc_token(&env, &admin);
+
+        // Mint USDC to lender and admin (for settlement)
+        usdc_admin.mint(&lender, &10000_0000000); // 10,000 USDC
+        usdc_admin.mint(&admin, &10000_0000000);  // For settlement
+
+        // Deploy and initialize contract
+        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
+        let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+
+        client.initialize(
+            &admin,
+            &usdc_address,
+            &treasury,
+            &9000, // 90% advance
+            &50,   // 0.5% protocol fee
+        );
+
+        // Deploy the settlement contract paying out settlements
+        let settlement = register_settlement(&env, &contract_id, &admin);
+
+        // Set timestamp
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        // 1. SME creates invoice
+        let invoice_id = client.create_invoice(
+            &sme,
+            &String::from_str(&env, "CORP-001"),
+            &BytesN::from_array(&env, &[1; 32]),
+            &usdc_address,
+            &1000_0000000, // 1000 USDC
+            &2000,         // Due date
+            &String::from_str(&env, "MX"),
+            &String::from_str(&env, "DESIGN"),
+            &200, // 2% fee
+            &FeeModel::Flat,
+        );
+
+        assert_eq!(invoice_id, 1);
+
+        // Check invoice created
+        let invoice = client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Created);
+        assert_eq!(invoice.amount, 1000_0000000);
+        assert_eq!(invoice.advance_amount, 900_0000000); // 90%
 
         // 2. Lender funds invoice
         let funding_event = client.fund_invoice(&lender, &invoice_id);
         assert_eq!(funding_event.amount, 900_0000000);
 
-        // Check SME received advance
-        assert_eq!(usdc_client.balance(&sme), 900_0000000);
+        // Check SME received advance
+        assert_eq!(usdc_client.balance(&sme), 900_0000000);
+
+        // Check invoice is funded
+        let invoice = client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Funded);
+
+        // 3. Settle invoice (simulating Anchor payment)
+        let result = settlement.settle_invoice(
+            &admin,
+            &invoice_id,
+            &1000_0000000, // Full invoice amount received
+            &None,
+        );
+
+        // Verify distribution
+        // Lender: 900 (principal) + 18 (2% fee) = 918 USDC
+        assert_eq!(result.lender_amount, 918_0000000);
+        // Protocol fee: 1000 * 0.5% = 5 USDC
+        assert_eq!(result.protocol_fee, 5_0000000);
+        // SME: 1000 - 918 - 5 = 77 USDC (remainder)
+        assert_eq!(result.sme_amount, 77_0000000);
+
+        // Check final balances
+        // SME: 900 (advance) + 77 (remainder) = 977 USDC
+        assert_eq!(usdc_client.balance(&sme), 977_0000000);
+        // Lender: 10000 - 900 (funded) + 918 (received) = 10018 USDC
+        assert_eq!(usdc_client.balance(&lender), 10018_0000000);
+        // Treasury: 5 USDC
+        assert_eq!(usdc_client.balance(&treasury), 5_0000000);
+
+        // Check invoice is settled
+        let invoice = client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Settled);
+    }
+
+    #[test]
+    fn test_lifecycle_events() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+        let lender = Address::generate(&env);
+
+        let (usdc_address, _, usdc_admin) = create_usdc_token(&env, &admin);
+        usdc_admin.mint(&lender, &10000_0000000);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
+        let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+
+        client.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let config = client.get_config();
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (symbol_short!("init"), EVENTS_VERSION, admin.clone()).into_val(&env),
+                    config.into_val(&env),
+                ),
+            ]
+        );
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        let invoice_id = client.create_invoice(
+            &sme,
+            &String::from_str(&env, "CORP-001"),
+            &BytesN::from_array(&env, &[1; 32]),
+            &usdc_address,
+            &1000_0000000,
+            &2000,
+            &String::from_str(&env, "MX"),
+            &String::from_str(&env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        );
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (symbol_short!("created"), EVENTS_VERSION, invoice_id, sme.clone())
+                        .into_val(&env),
+                    InvoiceCreatedEvent {
+                        payer_id: String::from_str(&env, "CORP-001"),
+                        amount: 1000_0000000,
+                        advance_amount: 900_0000000,
+                        fee_bps: 200,
+                        due_date: 2000,
+                        status: InvoiceStatus::Created,
+                        timestamp: 1000,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1100;
+        });
+
+        client.fund_invoice(&lender, &invoice_id);
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                contract_id.clone(),
+                (symbol_short!("funded"), EVENTS_VERSION, invoice_id, lender.clone())
+                    .into_val(&env),
+                InvoiceFundedEvent {
+                    sme: sme.clone(),
+                    amount: 900_0000000,
+                    status: InvoiceStatus::Funded,
+                    timestamp: 1100,
+                }
+                .into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    /// Payloads of the `status` events published by the invoice contract
+    fn status_events(ctx: &TestContext) -> Vec<InvoiceStatusEvent> {
+        let mut events = Vec::new(&ctx.env);
+        for (contract, topics, payload) in ctx.env.events().all().iter() {
+            let name: Symbol = topics.get(0).unwrap().try_into_val(&ctx.env).unwrap();
+            if contract == ctx.contract_id && name == symbol_short!("status") {
+                events.push_back(payload.try_into_val(&ctx.env).unwrap());
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_status_events_follow_every_transition() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payers = Address::generate(&ctx.env);
+        let arbiter = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Payers, &payers);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.record_acknowledgement(&payers, &invoice_id);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+        ctx.client.open_dispute(&payers, &invoice_id);
+        ctx.client.close_dispute(&payers, &arbiter, &invoice_id, &DisputeResolution::Valid);
+
+        // Payments through the settlement contract are published here too
+        ctx.env.ledger().with_mut(|li| li.timestamp = 1500);
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &400_0000000, &None);
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &600_0000000, &None);
+
+        let change = |from: InvoiceStatus, to: InvoiceStatus, timestamp: u64| InvoiceStatusEvent { from, to, timestamp };
+        assert_eq!(
+            status_events(&ctx),
+            vec![
+                &ctx.env,
+                change(InvoiceStatus::Created, InvoiceStatus::Acknowledged, 1000),
+                change(InvoiceStatus::Acknowledged, InvoiceStatus::Funded, 1000),
+                change(InvoiceStatus::Funded, InvoiceStatus::Disputed, 1000),
+                change(InvoiceStatus::Disputed, InvoiceStatus::Funded, 1000),
+                change(InvoiceStatus::Funded, InvoiceStatus::PartiallySettled, 1500),
+                change(InvoiceStatus::PartiallySettled, InvoiceStatus::Settled, 1500),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_config_events() {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let new_treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+
+        let (usdc_address, _, _) = create_usdc_token(&env, &admin);
+
+        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
+        let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+
+        client.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        let invoice_id = client.create_invoice(
+            &sme,
+            &String::from_str(&env, "CORP-001"),
+            &BytesN::from_array(&env, &[1; 32]),
+            &usdc_address,
+            &1000_0000000,
+            &2000,
+            &String::from_str(&env, "MX"),
+            &String::from_str(&env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        );
+
+        client.cancel_invoice(&sme, &invoice_id);
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (symbol_short!("cancelled"), EVENTS_VERSION, invoice_id, sme.clone())
+                        .into_val(&env),
+                    InvoiceCancelledEvent {
+                        cancelled_by: sme.clone(),
+                        status: InvoiceStatus::Cancelled,
+                        timestamp: 1000,
+                    }
+                    .into_val(&env),
+                ),
+            ]
+        );
+
+        client.update_config(&admin, &Some(new_treasury.clone()), &None, &Some(75), &None, &None, &None);
+        let config = client.get_config();
+        assert_eq!(config.treasury, new_treasury);
+        assert_eq!(config.protocol_fee_bps, 75);
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (symbol_short!("cfg_upd"), EVENTS_VERSION, admin.clone()).into_val(&env),
+                    config.into_val(&env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syndicated_funding_and_pro_rata_settlement() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+        let lender_c = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        // 900 USDC advance subscribed as 450 / 300 / 150
+        ctx.client.fund_invoice_partial(&lender_a, &invoice_id, &450_0000000);
+        ctx.client.fund_invoice_partial(&lender_b, &invoice_id, &300_0000000);
+
+        // Still open, contributions held by the contract, SME not paid yet
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Created);
+        assert_eq!(invoice.funded_amount, 750_0000000);
+        assert_eq!(invoice.lender, Some(lender_a.clone()));
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 750_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 0);
+
+        // Cannot over-subscribe the remainder
+        assert_eq!(
+            ctx.client.try_fund_invoice_partial(&lender_c, &invoice_id, &200_0000000),
+            Err(Ok(AdelantaError::FundingExceedsRemaining))
+        );
+
+        // fund_invoice takes the remaining 150 and completes the syndicate
+        let funding = ctx.client.fund_invoice(&lender_c, &invoice_id);
+        assert_eq!(funding.amount, 150_0000000);
+
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Funded);
+        assert_eq!(invoice.funded_amount, 900_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 900_0000000);
+
+        let lenders = ctx.client.get_invoice_lenders(&invoice_id);
+        assert_eq!(
+            lenders,
+            vec![
+                &ctx.env,
+                LenderContribution { lender: lender_a.clone(), amount: 450_0000000 },
+                LenderContribution { lender: lender_b.clone(), amount: 300_0000000 },
+                LenderContribution { lender: lender_c.clone(), amount: 150_0000000 },
+            ]
+        );
+
+        // Lender portion of 918 USDC split 50% / 33.3% / 16.7%
+        let result = ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000 - 450_0000000 + 459_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_b), 10000_0000000 - 300_0000000 + 306_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_c), 10000_0000000 - 150_0000000 + 153_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 977_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.treasury), 5_0000000);
+    }
+
+    #[test]
+    fn test_repeat_contributions_merge_into_one_share() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        ctx.client.fund_invoice_partial(&lender, &invoice_id, &100_0000000);
+        ctx.client.fund_invoice_partial(&lender, &invoice_id, &200_0000000);
+
+        assert_eq!(
+            ctx.client.get_invoice_lenders(&invoice_id),
+            vec![
+                &ctx.env,
+                LenderContribution { lender: lender.clone(), amount: 300_0000000 },
+            ]
+        );
+        assert_eq!(
+            ctx.client.try_fund_invoice_partial(&lender, &invoice_id, &0),
+            Err(Ok(AdelantaError::InvalidAmount))
+        );
+    }
+
+    #[test]
+    fn test_cancel_refunds_partial_contributions() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        ctx.client.fund_invoice_partial(&lender_a, &invoice_id, &400_0000000);
+        ctx.client.fund_invoice_partial(&lender_b, &invoice_id, &100_0000000);
+
+        ctx.client.cancel_invoice(&ctx.sme, &invoice_id);
+
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Cancelled);
+        assert_eq!(invoice.funded_amount, 0);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_b), 10000_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+    }
+
+    #[test]
+    fn test_transfer_position_redirects_settlement() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let holder = Address::generate(&ctx.env);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        let transfer = ctx.client.transfer_position(&lender, &holder, &invoice_id);
+        assert_eq!(transfer.amount, 900_0000000);
+        assert_eq!(transfer.price, 0);
+
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.lender, Some(holder.clone()));
+        assert_eq!(ctx.client.get_position_history(&invoice_id), vec![&ctx.env, transfer]);
+
+        // The original lender no longer holds anything to transfer
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &holder, &invoice_id),
+            Err(Ok(AdelantaError::PositionNotFound))
+        );
+
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&holder), 918_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 10000_0000000 - 900_0000000);
+
+        // Settled positions can no longer trade
+        assert_eq!(
+            ctx.client.try_transfer_position(&holder, &lender, &invoice_id),
+            Err(Ok(AdelantaError::InvoiceAlreadySettled))
+        );
+    }
+
+    #[test]
+    fn test_trade_position() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+        let marketplace = Address::generate(&ctx.env);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        // Only the marketplace settles sales
+        ctx.client.fund_invoice_partial(&lender_a, &invoice_id, &600_0000000);
+        assert_eq!(
+            ctx.client.try_trade_position(&marketplace, &lender_a, &buyer, &invoice_id, &610_0000000),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+        ctx.client.grant_role(&ctx.admin, &Role::Marketplace, &marketplace);
+
+        // Positions cannot be sold before the invoice is funded
+        assert_eq!(
+            ctx.client.try_trade_position(&marketplace, &lender_a, &buyer, &invoice_id, &610_0000000),
+            Err(Ok(AdelantaError::InvoiceNotFunded))
+        );
+        ctx.client.fund_invoice(&lender_b, &invoice_id);
+
+        let transfer = ctx.client.trade_position(&marketplace, &lender_b, &buyer, &invoice_id, &305_0000000);
+        assert_eq!(transfer.from, lender_b);
+        assert_eq!(transfer.to, buyer);
+        assert_eq!(transfer.amount, 300_0000000);
+        assert_eq!(transfer.price, 305_0000000);
+
+        // Seller paid, lead lender unchanged
+        assert_eq!(ctx.usdc.balance(&lender_b), 10000_0000000 - 300_0000000 + 305_0000000);
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000 - 305_0000000);
+        assert_eq!(
+            ctx.client.try_trade_position(&marketplace, &lender_b, &buyer, &invoice_id, &305_0000000),
+            Err(Ok(AdelantaError::PositionNotFound))
+        );
+        assert_eq!(
+            ctx.client.try_trade_position(&marketplace, &buyer, &buyer, &invoice_id, &305_0000000),
+            Err(Ok(AdelantaError::InvalidRecipient))
+        );
+        assert_eq!(ctx.client.get_invoice(&invoice_id).lender, Some(lender_a.clone()));
+
+        // Settlement pays the buyer the seller's pro rata share
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000 - 305_0000000 + 306_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000 - 600_0000000 + 612_0000000);
+    }
+
+    #[test]
+    fn test_transfer_position_to_holder_rejected() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &lender, &invoice_id),
+            Err(Ok(AdelantaError::InvalidRecipient))
+        );
+    }
 
-        // Check invoice is funded
-        let invoice = client.get_invoice(&invoice_id);
-        assert_eq!(invoice.status, InvoiceStatus::Funded);
+    #[test]
+    fn test_mark_defaulted_after_grace_period() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
 
-        // 3. Settle invoice (simulating Anchor payment)
-        let result = client.settle_invoice(
-            &admin,
-            &invoice_id,
-            &1000_0000000, // Full invoice amount received
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &300_0000000, &None);
+
+        let grace = ctx.client.get_config().grace_period_secs;
+
+        // Due at 2000; still within grace at the boundary
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2000 + grace;
+        });
+        assert_eq!(
+            ctx.client.try_mark_defaulted(&invoice_id),
+            Err(Ok(AdelantaError::GracePeriodActive))
         );
 
-        // Verify distribution
-        // Lender: 900 (principal) + 18 (2% fee) = 918 USDC
-        assert_eq!(result.lender_amount, 918_0000000);
-        // Protocol fee: 1000 * 0.5% = 5 USDC
-        assert_eq!(result.protocol_fee, 5_0000000);
-        // SME: 1000 - 918 - 5 = 77 USDC (remainder)
-        assert_eq!(result.sme_amount, 77_0000000);
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2001 + grace;
+        });
+        ctx.client.mark_defaulted(&invoice_id);
 
-        // Check final balances
-        // SME: 900 (advance) + 77 (remainder) = 977 USDC
-        assert_eq!(usdc_client.balance(&sme), 977_0000000);
-        // Lender: 10000 - 900 (funded) + 918 (received) = 10018 USDC
-        assert_eq!(usdc_client.balance(&lender), 10018_0000000);
-        // Treasury: 5 USDC
-        assert_eq!(usdc_client.balance(&treasury), 5_0000000);
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Defaulted);
+        assert_eq!(invoice.defaulted_at, Some(2001 + grace));
 
-        // Check invoice is settled
-        let invoice = client.get_invoice(&invoice_id);
-        assert_eq!(invoice.status, InvoiceStatus::Settled);
+        // Defaulted invoices can no longer be settled, traded or re-defaulted
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&ctx.admin, &invoice_id, &700_0000000, &None),
+            Err(Ok(SettlementError::InvoiceDefaulted))
+        );
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &ctx.sme, &invoice_id),
+            Err(Ok(AdelantaError::InvoiceDefaulted))
+        );
+        assert_eq!(
+            ctx.client.try_mark_defaulted(&invoice_id),
+            Err(Ok(AdelantaError::InvoiceDefaulted))
+        );
+    }
+
+    #[test]
+    fn test_invalid_interest_rate_rejected() {
+        let ctx = setup();
+
+        let result = ctx.client.try_create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &BytesN::from_array(&ctx.env, &[1; 32]),
+            &ctx.usdc.address,
+            &1000_0000000,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &0,
+            &FeeModel::Accrued(InterestTerms {
+                annual_rate_bps: 10001,
+                late_rate_bps: 0,
+                day_count: DayCount::Act360,
+            }),
+        );
+        assert_eq!(result, Err(Ok(AdelantaError::InvalidInterestRate)));
+    }
+
+    #[test]
+    fn test_settler_role_allowlist() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let anchor = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        assert!(!ctx.client.has_role(&Role::Settler, &anchor));
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&anchor, &invoice_id, &500_0000000, &None),
+            Err(Ok(SettlementError::Unauthorized))
+        );
+
+        ctx.client.grant_role(&ctx.admin, &Role::Settler, &anchor);
+        assert!(ctx.client.has_role(&Role::Settler, &anchor));
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("role_grnt"), EVENTS_VERSION, Role::Settler, anchor.clone())
+                        .into_val(&ctx.env),
+                    ctx.admin.into_val(&ctx.env),
+                ),
+            ]
+        );
+
+        ctx.settlement.settle_invoice(&anchor, &invoice_id, &500_0000000, &None);
+
+        // Payments are only recorded for the settlement contract
+        let payment = Payment {
+            amount: 500_0000000,
+            face_value_paid: 500_0000000,
+            fx_rate: None,
+            lender_amount: 500_0000000,
+            protocol_fee: 0,
+        };
+        assert_eq!(
+            ctx.client.try_record_payment(&anchor, &invoice_id, &payment).err(),
+            Some(Ok(AdelantaError::Unauthorized))
+        );
+
+        // Settler cannot administer roles or the treasury
+        let stranger = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_grant_role(&anchor, &Role::Settler, &stranger),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_update_config(&anchor, &Some(stranger.clone()), &None, &None, &None, &None, &None),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+
+        ctx.client.revoke_role(&ctx.admin, &Role::Settler, &anchor);
+        assert!(!ctx.client.has_role(&Role::Settler, &anchor));
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&anchor, &invoice_id, &500_0000000, &None),
+            Err(Ok(SettlementError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_fee_manager_and_renounce() {
+        let ctx = setup();
+        let fee_manager = Address::generate(&ctx.env);
+
+        ctx.client.grant_role(&ctx.admin, &Role::FeeManager, &fee_manager);
+
+        // Fee parameters need FeeManager, treasury needs Admin
+        ctx.client.update_config(&fee_manager, &None, &Some(8000), &Some(100), &None, &None, &None);
+        let config = ctx.client.get_config();
+        assert_eq!(config.default_advance_bps, 8000);
+        assert_eq!(config.protocol_fee_bps, 100);
+        assert_eq!(
+            ctx.client.try_update_config(&fee_manager, &None, &None, &None, &Some(60), &None, &None),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+
+        ctx.client.renounce_role(&fee_manager, &Role::FeeManager);
+        assert!(!ctx.client.has_role(&Role::FeeManager, &fee_manager));
+        assert_eq!(
+            ctx.client.try_renounce_role(&fee_manager, &Role::FeeManager),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+
+        // The only admin cannot leave the contract without an admin
+        assert_eq!(
+            ctx.client.try_renounce_role(&ctx.admin, &Role::Admin),
+            Err(Ok(AdelantaError::LastAdmin))
+        );
+        let second_admin = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Admin, &second_admin);
+        ctx.client.renounce_role(&ctx.admin, &Role::Admin);
+        assert!(!ctx.client.has_role(&Role::Admin, &ctx.admin));
+        assert_eq!(
+            ctx.client.try_revoke_role(&second_admin, &Role::Admin, &second_admin),
+            Err(Ok(AdelantaError::LastAdmin))
+        );
+    }
+
+    #[test]
+    fn test_pause_requires_guardian_and_unpause_requires_admin() {
+        let ctx = setup();
+        let guardian = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Pauser, &guardian);
+
+        ctx.client.pause(&guardian, &Operation::Fund);
+        assert!(ctx.client.is_paused(&Operation::Fund));
+        assert!(!ctx.client.is_paused(&Operation::Settle));
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("paused"), EVENTS_VERSION, Operation::Fund).into_val(&ctx.env),
+                    guardian.into_val(&ctx.env),
+                ),
+            ]
+        );
+
+        // The guardian can only pause; resuming needs an admin
+        assert_eq!(
+            ctx.client.try_unpause(&guardian, &Operation::Fund),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+        let stranger = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_pause(&stranger, &Operation::Settle),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+
+        ctx.client.unpause(&ctx.admin, &Operation::Fund);
+        assert!(!ctx.client.is_paused(&Operation::Fund));
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("unpaused"), EVENTS_VERSION, Operation::Fund).into_val(&ctx.env),
+                    ctx.admin.into_val(&ctx.env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_create() {
+        let ctx = setup();
+        ctx.client.pause(&ctx.admin, &Operation::Create);
+
+        assert_eq!(
+            ctx.client.try_create_invoice(
+                &ctx.sme,
+                &String::from_str(&ctx.env, "CORP-001"),
+                &BytesN::from_array(&ctx.env, &[1; 32]),
+                &ctx.usdc.address,
+                &1000_0000000,
+                &2000,
+                &String::from_str(&ctx.env, "MX"),
+                &String::from_str(&ctx.env, "DESIGN"),
+                &200,
+                &FeeModel::Flat,
+            ),
+            Err(Ok(AdelantaError::Paused))
+        );
+
+        ctx.client.unpause(&ctx.admin, &Operation::Create);
+        create_test_invoice(&ctx, 1000_0000000);
+    }
+
+    #[test]
+    fn test_pause_fund_keeps_settlement_open() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payer = funded_lender(&ctx);
+
+        let funded_id = create_test_invoice(&ctx, 1000_0000000);
+        let deposit_id = create_test_invoice(&ctx, 1000_0000000);
+        let open_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &funded_id);
+        ctx.client.fund_invoice(&lender, &deposit_id);
+
+        ctx.client.pause(&ctx.admin, &Operation::Fund);
+
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &open_id),
+            Err(Ok(AdelantaError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_fund_invoice_partial(&lender, &open_id, &100_0000000),
+            Err(Ok(AdelantaError::Paused))
+        );
+
+        // Lenders still get repaid and new invoices can still be listed
+        ctx.settlement.settle_invoice(&ctx.admin, &funded_id, &1000_0000000, &None);
+        ctx.settlement.deposit_payment(&payer, &deposit_id, &ctx.usdc.address, &1000_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 10036_0000000);
+        create_test_invoice(&ctx, 1000_0000000);
+    }
+
+    #[test]
+    fn test_pause_trade() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+        let marketplace = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Marketplace, &marketplace);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        ctx.client.pause(&ctx.admin, &Operation::Trade);
+
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &buyer, &invoice_id),
+            Err(Ok(AdelantaError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_trade_position(&marketplace, &lender, &buyer, &invoice_id, &905_0000000),
+            Err(Ok(AdelantaError::Paused))
+        );
+
+        ctx.client.unpause(&ctx.admin, &Operation::Trade);
+        ctx.client.trade_position(&marketplace, &lender, &buyer, &invoice_id, &905_0000000);
+    }
 
-        // Check SME reputation updated
-        let reputation = client.get_sme_reputation(&sme).unwrap();
-        assert_eq!(reputation.total_invoices, 1);
-        assert_eq!(reputation.settled_invoices, 1);
-        assert_eq!(reputation.total_volume, 1000_0000000);
+    #[test]
+    fn test_pause_settle() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+        let settlement = Address::generate(&ctx.env);
+        let payers = Address::generate(&ctx.env);
+        let arbiter = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Settlement, &settlement);
+        ctx.client.grant_role(&ctx.admin, &Role::Payers, &payers);
+
+        let overdue_id = create_test_invoice(&ctx, 1000_0000000);
+        let disputed_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &overdue_id);
+        ctx.client.open_dispute(&payers, &disputed_id);
+
+        ctx.client.pause(&ctx.admin, &Operation::Settle);
+
+        // Creation, funding and trading stay open
+        let open_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &open_id);
+        ctx.client.transfer_position(&lender, &buyer, &open_id);
+
+        let payment = Payment {
+            amount: 500_0000000,
+            face_value_paid: 500_0000000,
+            fx_rate: None,
+            lender_amount: 500_0000000,
+            protocol_fee: 0,
+        };
+        assert_eq!(
+            ctx.client.try_record_payment(&settlement, &overdue_id, &payment).err(),
+            Some(Ok(AdelantaError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_open_dispute(&payers, &open_id).err(),
+            Some(Ok(AdelantaError::Paused))
+        );
+        assert_eq!(
+            ctx.client
+                .try_close_dispute(&payers, &arbiter, &disputed_id, &DisputeResolution::Invalid)
+                .err(),
+            Some(Ok(AdelantaError::Paused))
+        );
+
+        let grace = ctx.client.get_config().grace_period_secs;
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2001 + grace;
+        });
+        assert_eq!(
+            ctx.client.try_mark_defaulted(&overdue_id),
+            Err(Ok(AdelantaError::Paused))
+        );
+
+        ctx.client.unpause(&ctx.admin, &Operation::Settle);
+        ctx.client.close_dispute(&payers, &arbiter, &disputed_id, &DisputeResolution::Invalid);
+        ctx.client.mark_defaulted(&overdue_id);
+        assert_eq!(ctx.client.get_invoice(&overdue_id).status, InvoiceStatus::Defaulted);
+    }
+
+    #[test]
+    fn test_upgrade_swaps_contract_code() {
+        let ctx = setup();
+        let wasm_hash = ctx.env.deployer().upload_contract_wasm(v0::WASM);
+        assert_eq!(ctx.client.schema_version(), SCHEMA_VERSION);
+
+        let stranger = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_upgrade(&stranger, &wasm_hash),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+
+        ctx.client.upgrade(&ctx.admin, &wasm_hash);
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("upgraded"), EVENTS_VERSION, ctx.admin.clone()).into_val(&ctx.env),
+                    wasm_hash.into_val(&ctx.env),
+                ),
+            ]
+        );
+
+        // Calls are now served by the uploaded build, which predates versioning
+        let upgraded = v0::Client::new(&ctx.env, &ctx.contract_id);
+        assert_eq!(upgraded.get_stats(), (0, 0, 0));
+        assert!(ctx.client.try_schema_version().is_err());
     }
 
     #[test]
-    fn test_preview_settlement() {
+    fn test_migrate_from_unversioned_storage() {
         let env = Env::default();
         env.mock_all_auths();
+        env.budget().reset_unlimited();
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
 
         let admin = Address::generate(&env);
         let treasury = Address::generate(&env);
         let sme = Address::generate(&env);
+        let lender = Address::generate(&env);
+        let (usdc_address, usdc, usdc_admin) = create_usdc_token(&env, &admin);
+        usdc_admin.mint(&admin, &10000_0000000);
+        usdc_admin.mint(&lender, &10000_0000000);
+
+        // Populate storage with the old build
+        let contract_id = env.register_contract_wasm(None, v0::WASM);
+        let old = v0::Client::new(&env, &contract_id);
+        old.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let create = || {
+            old.create_invoice(
+                &sme,
+                &String::from_str(&env, "CORP-001"),
+                &1000_0000000,
+                &2000,
+                &String::from_str(&env, "MX"),
+                &String::from_str(&env, "DESIGN"),
+                &200,
+            )
+        };
+        let (open_id, settled_id, funded_id) = (create(), create(), create());
+        old.fund_invoice(&lender, &settled_id);
+        old.fund_invoice(&lender, &funded_id);
+        old.settle_invoice(&admin, &settled_id, &1000_0000000);
 
-        let (usdc_address, _, _) = create_usdc_token(&env, &admin);
-
-        let contract_id = env.register_contract(None, AdelantaInvoiceContract);
+        // The old build has no `upgrade`, so install the new code in place
+        env.register_contract(Some(&contract_id), AdelantaInvoiceContract);
         let client = AdelantaInvoiceContractClient::new(&env, &contract_id);
+        assert_eq!(client.schema_version(), 0);
+
+        // Old records are readable before they are rewritten
+        assert_eq!(client.get_config().grace_period_secs, DEFAULT_GRACE_PERIOD_SECS);
+        let settled = client.get_invoice(&settled_id);
+        assert_eq!(settled.status, InvoiceStatus::Settled);
+        assert_eq!(settled.funded_amount, 900_0000000);
+        assert_eq!(settled.lender_paid, 918_0000000);
+        assert_eq!(settled.protocol_fee_paid, 5_0000000);
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_migrate(&stranger, &10),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
 
-        client.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        // The config admin becomes the holder of every role, and can then
+        // set the risk engine the SMEs' reputation is handed over to
+        assert_eq!(client.migrate(&admin, &0), 3);
+        assert!(client.has_role(&Role::Settler, &admin));
+        let engine_id = env.register_contract_wasm(None, risk_engine::WASM);
+        let engine = risk_engine::Client::new(&env, &engine_id);
+        engine.initialize(&contract_id);
+        client.set_risk_engine(&admin, &Some(engine_id.clone()));
+
+        assert_eq!(client.migrate(&admin, &1), 2);
+        assert_eq!(client.schema_version(), 0);
+
+        // Not yet rewritten, but already settles through the settlement
+        // contract
+        let settlement = register_settlement(&env, &contract_id, &admin);
+        settlement.settle_invoice(&admin, &funded_id, &1000_0000000, &None);
+        assert_eq!(usdc.balance(&lender), 10036_0000000);
+
+        assert_eq!(client.migrate(&admin, &10), 0);
+        assert_eq!(client.schema_version(), SCHEMA_VERSION);
+        assert_eq!(
+            last_event(&env),
+            vec![
+                &env,
+                (
+                    contract_id.clone(),
+                    (symbol_short!("migrated"), EVENTS_VERSION).into_val(&env),
+                    (0u32, SCHEMA_VERSION).into_val(&env),
+                ),
+            ]
+        );
+        assert_eq!(client.migrate(&admin, &10), 0);
+
+        assert_eq!(client.get_invoice(&open_id).status, InvoiceStatus::Created);
+        assert_eq!(client.get_invoice(&funded_id).status, InvoiceStatus::Settled);
+        assert_eq!(client.get_invoice(&open_id).asset, usdc_address);
+        assert_eq!(client.get_invoice(&open_id).currency, None);
+
+        // The global counters carry over as the initial asset's stats
+        let stats = client.get_stats();
+        assert_eq!(stats.total_invoices, 3);
+        assert_eq!(
+            stats.assets,
+            vec![
+                &env,
+                AssetStats {
+                    asset: usdc_address.clone(),
+                    total_invoices: 3,
+                    volume_funded: 1800_0000000,
+                    volume_settled: 2000_0000000,
+                },
+            ]
+        );
+        assert_eq!(client.get_asset(&usdc_address).unwrap().min_invoice_amount, 50_0000000);
+        assert_eq!(
+            client.get_invoice_lenders(&settled_id),
+            vec![
+                &env,
+                LenderContribution {
+                    lender: lender.clone(),
+                    amount: 900_0000000,
+                },
+            ]
+        );
+        let reputation = engine.get_sme_reputation(&sme).unwrap();
+        assert_eq!(reputation.total_invoices, 3);
+        assert_eq!(reputation.settled_invoices, 2);
+        assert_eq!(reputation.defaulted_invoices, 0);
+
+        // Invoices written before the indexes existed are indexed too
+        assert_eq!(client.get_invoices_by_sme(&sme, &0, &10).total, 3);
+        assert_eq!(client.get_invoices_by_lender(&lender, &0, &10).total, 2);
+        assert_eq!(
+            client.get_invoices_by_status(&InvoiceStatus::Settled, &0, &10).total,
+            2
+        );
+    }
 
-        env.ledger().with_mut(|li| {
-            li.timestamp = 1000;
-        });
+    #[test]
+    fn test_invoices_in_approved_assets() {
+        let ctx = setup();
+        let (eurc_address, eurc, eurc_admin) = create_usdc_token(&ctx.env, &ctx.admin);
+        let lender = funded_lender(&ctx);
+        eurc_admin.mint(&lender, &10000_0000000);
+        eurc_admin.mint(&ctx.admin, &10000_0000000);
+        let create = |asset: &Address, amount: i128| {
+            ctx.client.try_create_invoice(
+                &ctx.sme,
+                &String::from_str(&ctx.env, "CORP-001"),
+                &next_fingerprint(&ctx),
+                asset,
+                &amount,
+                &2000,
+                &String::from_str(&ctx.env, "MX"),
+                &String::from_str(&ctx.env, "DESIGN"),
+                &200,
+                &FeeModel::Flat,
+            )
+        };
 
-        let invoice_id = client.create_invoice(
-            &sme,
-            &String::from_str(&env, "CORP-001"),
+        assert_eq!(create(&eurc_address, 1000_0000000), Err(Ok(AdelantaError::AssetNotSupported)));
+        assert_eq!(
+            ctx.client.try_approve_asset(&lender, &eurc_address, &10_0000000, &5000_0000000),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_approve_asset(&ctx.admin, &eurc_address, &5000_0000000, &10_0000000),
+            Err(Ok(AdelantaError::InvalidAmount))
+        );
+
+        ctx.client.approve_asset(&ctx.admin, &eurc_address, &10_0000000, &5000_0000000);
+        let eurc_config = AssetConfig {
+            asset: eurc_address.clone(),
+            min_invoice_amount: 10_0000000,
+            max_invoice_amount: 5000_0000000,
+            enabled: true,
+        };
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("asset"), EVENTS_VERSION, eurc_address.clone()).into_val(&ctx.env),
+                    eurc_config.into_val(&ctx.env),
+                ),
+            ]
+        );
+
+        // Limits are per asset
+        assert_eq!(create(&eurc_address, 6000_0000000), Err(Ok(AdelantaError::InvalidAmount)));
+        assert_eq!(create(&eurc_address, 20_0000000), Ok(Ok(1)));
+        assert_eq!(create(&ctx.usdc.address, 20_0000000), Err(Ok(AdelantaError::InvalidAmount)));
+
+        // Funding and settlement move the invoice's asset
+        let eurc_id = create(&eurc_address, 1000_0000000).unwrap().unwrap();
+        let usdc_id = create_test_invoice(&ctx, 1000_0000000);
+        assert_eq!(ctx.client.get_invoice(&eurc_id).asset, eurc_address);
+        ctx.client.fund_invoice(&lender, &eurc_id);
+        ctx.client.fund_invoice(&lender, &usdc_id);
+        assert_eq!(eurc.balance(&ctx.sme), 900_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 900_0000000);
+        ctx.settlement.settle_invoice(&ctx.admin, &eurc_id, &1000_0000000, &None);
+        assert_eq!(eurc.balance(&lender), 10018_0000000);
+        assert_eq!(eurc.balance(&ctx.treasury), 5_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 9100_0000000);
+
+        let stats = ctx.client.get_stats();
+        assert_eq!(stats.total_invoices, 3);
+        assert_eq!(
+            stats.assets,
+            vec![
+                &ctx.env,
+                AssetStats {
+                    asset: ctx.usdc.address.clone(),
+                    total_invoices: 1,
+                    volume_funded: 900_0000000,
+                    volume_settled: 0,
+                },
+                AssetStats {
+                    asset: eurc_address.clone(),
+                    total_invoices: 2,
+                    volume_funded: 900_0000000,
+                    volume_settled: 1000_0000000,
+                },
+            ]
+        );
+
+        let other_eurc_id = create(&eurc_address, 1000_0000000).unwrap().unwrap();
+        ctx.client.fund_invoice(&lender, &other_eurc_id);
+
+        // Revoking stops new invoices; open ones still settle in the asset
+        ctx.client.revoke_asset(&ctx.admin, &eurc_address);
+        assert_eq!(create(&eurc_address, 1000_0000000), Err(Ok(AdelantaError::AssetNotSupported)));
+        assert!(!ctx.client.get_assets().get(1).unwrap().enabled);
+        ctx.settlement.settle_invoice(&ctx.admin, &other_eurc_id, &1000_0000000, &None);
+        assert_eq!(ctx.client.get_invoice(&other_eurc_id).status, InvoiceStatus::Settled);
+    }
+
+    /// IDs of the invoices on a page
+    fn page_ids(page: &InvoicePage) -> Vec<u64> {
+        let mut ids = Vec::new(&page.invoices.env().clone());
+        for invoice in page.invoices.iter() {
+            ids.push_back(invoice.id);
+        }
+        ids
+    }
+
+    #[test]
+    fn test_indexes_follow_invoice_lifecycle() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+
+        let settled_id = create_test_invoice(&ctx, 1000_0000000);
+        let traded_id = create_test_invoice(&ctx, 1000_0000000);
+        let cancelled_id = create_test_invoice(&ctx, 1000_0000000);
+        let other_payer_id = ctx.client.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-002"),
+            &next_fingerprint(&ctx),
+            &ctx.usdc.address,
             &1000_0000000,
             &2000,
-            &String::from_str(&env, "MX"),
-            &String::from_str(&env, "DESIGN"),
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        );
+
+        ctx.client.fund_invoice_partial(&lender_a, &settled_id, &450_0000000);
+        ctx.client.fund_invoice(&lender_b, &settled_id);
+        ctx.client.fund_invoice(&lender_a, &traded_id);
+        ctx.client.fund_invoice_partial(&lender_b, &cancelled_id, &100_0000000);
+        ctx.settlement.settle_invoice(&ctx.admin, &settled_id, &1000_0000000, &None);
+        ctx.client.transfer_position(&lender_a, &buyer, &traded_id);
+        ctx.client.cancel_invoice(&ctx.sme, &cancelled_id);
+
+        let by_sme = ctx.client.get_invoices_by_sme(&ctx.sme, &0, &10);
+        assert_eq!(by_sme.total, 4);
+        assert_eq!(
+            page_ids(&by_sme),
+            vec![&ctx.env, settled_id, traded_id, cancelled_id, other_payer_id]
+        );
+
+        let corp_001 = String::from_str(&ctx.env, "CORP-001");
+        assert_eq!(
+            page_ids(&ctx.client.get_invoices_by_payer(&corp_001, &0, &10)),
+            vec![&ctx.env, settled_id, traded_id, cancelled_id]
+        );
+
+        // Lenders keep settled invoices, but lose refunded and sold positions
+        assert_eq!(
+            page_ids(&ctx.client.get_invoices_by_lender(&lender_a, &0, &10)),
+            vec![&ctx.env, settled_id]
+        );
+        assert_eq!(
+            page_ids(&ctx.client.get_invoices_by_lender(&lender_b, &0, &10)),
+            vec![&ctx.env, settled_id]
+        );
+        assert_eq!(
+            page_ids(&ctx.client.get_invoices_by_lender(&buyer, &0, &10)),
+            vec![&ctx.env, traded_id]
+        );
+
+        let status_ids = |status: InvoiceStatus| {
+            page_ids(&ctx.client.get_invoices_by_status(&status, &0, &10))
+        };
+        assert_eq!(status_ids(InvoiceStatus::Created), vec![&ctx.env, other_payer_id]);
+        assert_eq!(status_ids(InvoiceStatus::Funded), vec![&ctx.env, traded_id]);
+        assert_eq!(status_ids(InvoiceStatus::Settled), vec![&ctx.env, settled_id]);
+        assert_eq!(status_ids(InvoiceStatus::Cancelled), vec![&ctx.env, cancelled_id]);
+
+        ctx.settlement.settle_invoice(&ctx.admin, &traded_id, &400_0000000, &None);
+        assert_eq!(status_ids(InvoiceStatus::Funded).len(), 0);
+        assert_eq!(status_ids(InvoiceStatus::PartiallySettled), vec![&ctx.env, traded_id]);
+    }
+
+    #[test]
+    fn test_index_pagination_spans_pages() {
+        // Too many entries to keep a useful ledger snapshot
+        let ctx = setup_with_env(Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        }));
+        ctx.env.budget().reset_unlimited();
+        for _ in 0..230 {
+            create_test_invoice(&ctx, 100_0000000);
+        }
+
+        // Walk the SME's invoices; each page is capped at 25 invoices and
+        // fits in the default budget of a single call
+        let mut ids = Vec::new(&ctx.env);
+        let mut cursor = 0;
+        loop {
+            ctx.env.budget().reset_default();
+            let page = ctx.client.get_invoices_by_sme(&ctx.sme, &cursor, &100);
+            assert_eq!(page.total, 230);
+            assert!(page.invoices.len() <= 25);
+            ids.append(&page_ids(&page));
+            cursor = page.next_cursor;
+            if cursor >= page.total {
+                break;
+            }
+        }
+        assert_eq!(ids.len(), 230);
+        for (position, id) in ids.iter().enumerate() {
+            assert_eq!(id, position as u64 + 1);
+        }
+
+        // Leaving an index moves its last entry into the freed position
+        ctx.env.budget().reset_unlimited();
+        ctx.client.cancel_invoice(&ctx.sme, &5);
+        let created = ctx.client.get_invoices_by_status(&InvoiceStatus::Created, &0, &10);
+        assert_eq!(created.total, 229);
+        assert_eq!(page_ids(&created).get_unchecked(4), 230);
+
+        let tail = ctx.client.get_invoices_by_status(&InvoiceStatus::Created, &225, &10);
+        assert_eq!(page_ids(&tail), vec![&ctx.env, 226, 227, 228, 229]);
+        assert_eq!(tail.next_cursor, 229);
+    }
+
+    #[test]
+    fn test_duplicate_fingerprint_rejected_until_cancelled() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let other_sme = Address::generate(&ctx.env);
+        let fingerprint = BytesN::from_array(&ctx.env, &[1; 32]);
+        let create = |sme: &Address, fingerprint: &BytesN<32>| {
+            ctx.client.try_create_invoice(
+                sme,
+                &String::from_str(&ctx.env, "CORP-001"),
+                fingerprint,
+                &ctx.usdc.address,
+                &1000_0000000,
+                &2000,
+                &String::from_str(&ctx.env, "MX"),
+                &String::from_str(&ctx.env, "DESIGN"),
+                &200,
+                &FeeModel::Flat,
+            )
+        };
+
+        let invoice_id = create(&ctx.sme, &fingerprint).unwrap().unwrap();
+        assert_eq!(ctx.client.get_invoice_fingerprint(&invoice_id), Some(fingerprint.clone()));
+        assert_eq!(ctx.client.find_invoice_by_fingerprint(&fingerprint), Some(invoice_id));
+
+        // Neither the same nor another SME can register it again
+        assert_eq!(create(&ctx.sme, &fingerprint), Err(Ok(AdelantaError::DuplicateInvoice)));
+        assert_eq!(create(&other_sme, &fingerprint), Err(Ok(AdelantaError::DuplicateInvoice)));
+
+        // Settlement keeps the fingerprint registered
+        let settled_fingerprint = BytesN::from_array(&ctx.env, &[2; 32]);
+        let settled_id = create(&ctx.sme, &settled_fingerprint).unwrap().unwrap();
+        ctx.client.fund_invoice(&lender, &settled_id);
+        ctx.settlement.settle_invoice(&ctx.admin, &settled_id, &1000_0000000, &None);
+        assert_eq!(
+            create(&ctx.sme, &settled_fingerprint),
+            Err(Ok(AdelantaError::DuplicateInvoice))
+        );
+
+        // Cancellation releases it
+        ctx.client.cancel_invoice(&ctx.sme, &invoice_id);
+        assert_eq!(ctx.client.find_invoice_by_fingerprint(&fingerprint), None);
+        assert_eq!(ctx.client.get_invoice_fingerprint(&invoice_id), None);
+        let reissued_id = create(&ctx.sme, &fingerprint).unwrap().unwrap();
+        assert_eq!(ctx.client.find_invoice_by_fingerprint(&fingerprint), Some(reissued_id));
+    }
+
+    #[test]
+    fn test_acknowledgement_required_for_funding() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payers = Address::generate(&ctx.env);
+
+        ctx.client.update_config(&ctx.admin, &None, &None, &None, &None, &Some(true), &Some(9500));
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &invoice_id),
+            Err(Ok(AdelantaError::NotAcknowledged))
+        );
+
+        // Only the payers contract records acknowledgements
+        assert_eq!(
+            ctx.client.try_record_acknowledgement(&payers, &invoice_id).err(),
+            Some(Ok(AdelantaError::Unauthorized))
+        );
+        ctx.client.grant_role(&ctx.admin, &Role::Payers, &payers);
+
+        let invoice = ctx.client.record_acknowledgement(&payers, &invoice_id);
+        assert_eq!(
+            ctx.client.try_record_acknowledgement(&payers, &invoice_id).err(),
+            Some(Ok(AdelantaError::AlreadyAcknowledged))
+        );
+
+        // The confirmed invoice is advanced at 95% instead of 90%
+        assert_eq!(invoice.status, InvoiceStatus::Acknowledged);
+        assert_eq!(invoice.advance_amount, 950_0000000);
+
+        ctx.client.fund_invoice(&lender, &invoice_id);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 950_0000000);
+        assert_eq!(ctx.client.get_invoice(&invoice_id).status, InvoiceStatus::Funded);
+
+        // Partial funding keeps the acknowledged status
+        let partial_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.record_acknowledgement(&payers, &partial_id);
+        ctx.client.fund_invoice_partial(&lender, &partial_id, &100_0000000);
+        assert_eq!(ctx.client.get_invoice(&partial_id).status, InvoiceStatus::Acknowledged);
+
+        // Acknowledged invoices can still be cancelled before funding
+        let cancelled_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.record_acknowledgement(&payers, &cancelled_id);
+        ctx.client.cancel_invoice(&ctx.sme, &cancelled_id);
+        assert_eq!(ctx.client.get_invoice(&cancelled_id).status, InvoiceStatus::Cancelled);
+
+        // A risk engine that fails to quote fails the acknowledgement
+        let unquoted_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.set_risk_engine(&ctx.admin, &Some(Address::generate(&ctx.env)));
+        assert_eq!(
+            ctx.client.try_record_acknowledgement(&payers, &unquoted_id).err(),
+            Some(Ok(AdelantaError::RiskEngineUnavailable))
+        );
+        assert_eq!(ctx.client.get_invoice(&unquoted_id).status, InvoiceStatus::Created);
+    }
+
+    /// SEP-40 oracle returning prices set by the test
+    #[contract]
+    struct MockOracle;
+
+    #[contractimpl]
+    impl MockOracle {
+        pub fn set_price(env: Env, asset: OracleAsset, price: i128, timestamp: u64) {
+            env.storage().instance().set(&asset, &PriceData { price, timestamp });
+        }
+    }
+
+    #[contractimpl]
+    impl PriceOracle for MockOracle {
+        fn lastprice(env: Env, asset: OracleAsset) -> Option<PriceData> {
+            env.storage().instance().get(&asset)
+        }
+    }
+
+    /// Oracle quoting USDC at $1 and MXN at $0.05, configured with a 1 hour
+    /// maximum price age and 2% slippage tolerance
+    fn setup_oracle(ctx: &TestContext) -> MockOracleClient<'static> {
+        let oracle_id = ctx.env.register_contract(None, MockOracle);
+        let oracle = MockOracleClient::new(&ctx.env, &oracle_id);
+        oracle.set_price(&OracleAsset::Stellar(ctx.usdc.address.clone()), &1_0000000, &1000);
+        oracle.set_price(&OracleAsset::Other(symbol_short!("MXN")), &500000, &1000);
+        ctx.client.set_fx_config(&ctx.admin, &oracle_id, &3600, &200);
+        oracle
+    }
+
+    /// Create a 20,000 MXN invoice settled in USDC, due at timestamp 2000
+    fn create_fiat_test_invoice(ctx: &TestContext) -> Result<u64, AdelantaError> {
+        ctx.client.try_create_fiat_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &FaceValue {
+                currency: symbol_short!("MXN"),
+                amount: 20000_0000000,
+            },
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
             &200,
+            &FeeModel::Flat,
+        )
+        .map(Result::unwrap)
+        .map_err(Result::unwrap)
+    }
+
+    #[test]
+    fn test_fiat_invoice_priced_by_oracle() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let mxn = OracleAsset::Other(symbol_short!("MXN"));
+
+        assert_eq!(create_fiat_test_invoice(&ctx), Err(AdelantaError::OracleNotConfigured));
+        let oracle = setup_oracle(&ctx);
+
+        let invoice_id = create_fiat_test_invoice(&ctx).unwrap();
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.currency, Some(symbol_short!("MXN")));
+        assert_eq!(invoice.face_value, 20000_0000000);
+        assert_eq!(invoice.funding_fx_rate, Some(500000));
+        assert_eq!(invoice.amount, 1000_0000000);
+        assert_eq!(invoice.advance_amount, 900_0000000);
+
+        // Funding needs a fresh price within the slippage tolerance of the quote
+        ctx.env.ledger().with_mut(|li| li.timestamp = 4601);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &invoice_id),
+            Err(Ok(AdelantaError::StalePrice))
+        );
+        ctx.env.ledger().with_mut(|li| li.timestamp = 1500);
+        oracle.set_price(&mxn, &515000, &1500);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &invoice_id),
+            Err(Ok(AdelantaError::FxSlippageExceeded))
+        );
+
+        // The converted amount must still be within the asset's limits
+        oracle.set_price(&mxn, &505000, &1500);
+        ctx.client.approve_asset(&ctx.admin, &ctx.usdc.address, &50_0000000, &1005_0000000);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &invoice_id),
+            Err(Ok(AdelantaError::InvalidAmount))
+        );
+        ctx.client.approve_asset(&ctx.admin, &ctx.usdc.address, &50_0000000, &100000_0000000);
+
+        // The rate at the first contribution fixes the amounts
+        ctx.client.fund_invoice_partial(&lender, &invoice_id, &409_0000000);
+        oracle.set_price(&mxn, &400000, &1500);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.funding_fx_rate, Some(505000));
+        assert_eq!(invoice.amount, 1010_0000000);
+        assert_eq!(invoice.advance_amount, 909_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 909_0000000);
+
+        // Settlers pay in USDC and state the MXN it converted from, at a
+        // rate within the slippage tolerance of the oracle
+        oracle.set_price(&mxn, &500000, &1500);
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None),
+            Err(Ok(SettlementError::InvalidAmount))
+        );
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&ctx.admin, &invoice_id, &900_0000000, &Some(20000_0000000)),
+            Err(Ok(SettlementError::FxSlippageExceeded))
+        );
+
+        // Paying the face value settles the invoice, even though the USDC
+        // it converted to is below the amount priced at funding
+        let result = ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &Some(20000_0000000));
+        assert_eq!(result.lender_amount, 927_1800000);
+        assert_eq!(result.protocol_fee, 5_0500000);
+        assert_eq!(result.sme_amount, 67_7700000);
+        let invoice = ctx.client.get_invoice(&invoice_id);
+        assert_eq!(invoice.settlement_fx_rate, Some(500000));
+        assert_eq!(invoice.amount_received, 1000_0000000);
+        assert_eq!(invoice.face_value_received, 20000_0000000);
+        assert_eq!(invoice.status, InvoiceStatus::Settled);
+    }
+
+    #[test]
+    fn test_compliance_registry_gates_smes_and_lenders() {
+        use compliance_registry::{Client as AdelantaComplianceContractClient, KycPolicy};
+
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let officer = Address::generate(&ctx.env);
+        let mx = String::from_str(&ctx.env, "MX");
+
+        let registry_id = ctx.env.register_contract_wasm(None, compliance_registry::WASM);
+        let registry = AdelantaComplianceContractClient::new(&ctx.env, &registry_id);
+        registry.initialize(&ctx.admin);
+        registry.set_officer(&ctx.admin, &officer, &true);
+        registry.set_policy(
+            &officer,
+            &KycPolicy {
+                sme_min_level: 1,
+                lender_min_level: 1,
+            },
+        );
+        registry.set_country_level(&officer, &mx, &2);
+
+        assert_eq!(
+            ctx.client.try_set_compliance_registry(&officer, &Some(registry_id.clone())),
+            Err(Ok(AdelantaError::Unauthorized))
         );
+        ctx.client.set_compliance_registry(&ctx.admin, &Some(registry_id.clone()));
+        assert_eq!(ctx.client.get_compliance_registry(), Some(registry_id));
+
+        // Unverified SMEs cannot create invoices, nor SMEs below the country's level
+        assert_eq!(
+            ctx.client.try_create_invoice(
+                &ctx.sme,
+                &String::from_str(&ctx.env, "CORP-001"),
+                &next_fingerprint(&ctx),
+                &ctx.usdc.address,
+                &1000_0000000,
+                &2000,
+                &mx,
+                &String::from_str(&ctx.env, "DESIGN"),
+                &200,
+                &FeeModel::Flat,
+            ),
+            Err(Ok(AdelantaError::KycRequired))
+        );
+        registry.set_kyc(&officer, &ctx.sme, &1, &mx, &5000);
+        assert_eq!(
+            ctx.client.try_create_invoice(
+                &ctx.sme,
+                &String::from_str(&ctx.env, "CORP-001"),
+                &next_fingerprint(&ctx),
+                &ctx.usdc.address,
+                &1000_0000000,
+                &2000,
+                &mx,
+                &String::from_str(&ctx.env, "DESIGN"),
+                &200,
+                &FeeModel::Flat,
+            ),
+            Err(Ok(AdelantaError::KycRequired))
+        );
+        registry.set_kyc(&officer, &ctx.sme, &2, &mx, &5000);
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        // Lenders are checked when funding and when acquiring positions
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &invoice_id),
+            Err(Ok(AdelantaError::KycRequired))
+        );
+        registry.set_kyc(&officer, &lender, &2, &mx, &5000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        let buyer = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &buyer, &invoice_id),
+            Err(Ok(AdelantaError::KycRequired))
+        );
+
+        // Removing the registry lifts the checks
+        ctx.client.set_compliance_registry(&ctx.admin, &None);
+        ctx.client.transfer_position(&lender, &buyer, &invoice_id);
+    }
+
+    #[test]
+    fn test_frozen_accounts_payouts_retained_by_registry() {
+        use compliance_registry::Client as AdelantaComplianceContractClient;
+
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let officer = Address::generate(&ctx.env);
+        let sanctions = String::from_str(&ctx.env, "OFAC-SDN");
+
+        let registry_id = ctx.env.register_contract_wasm(None, compliance_registry::WASM);
+        let registry = AdelantaComplianceContractClient::new(&ctx.env, &registry_id);
+        registry.initialize(&ctx.admin);
+        registry.set_officer(&ctx.admin, &officer, &true);
+        ctx.client.set_compliance_registry(&ctx.admin, &Some(registry_id.clone()));
+
+        // The SME is frozen before funding, so its advance is retained
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        registry.freeze_account(&officer, &ctx.sme, &sanctions);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 0);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+        assert_eq!(ctx.usdc.balance(&registry_id), 900_0000000);
+
+        // The lender is frozen after funding, so its settlement share is
+        // retained too, as is the SME's remainder
+        registry.freeze_account(&officer, &lender, &sanctions);
+        let result = ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 9100_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 0);
+        assert_eq!(ctx.usdc.balance(&ctx.treasury), 5_0000000);
+        assert_eq!(ctx.usdc.balance(&registry_id), 1895_0000000);
+        assert_eq!(ctx.client.get_invoice(&invoice_id).status, InvoiceStatus::Settled);
+
+        let sme_payouts = registry.get_retained_payouts(&ctx.sme);
+        assert_eq!(sme_payouts.len(), 2);
+        let advance = sme_payouts.get_unchecked(0);
+        assert_eq!(advance.amount, 900_0000000);
+        assert_eq!(advance.invoice_id, invoice_id);
+        assert_eq!(advance.asset, ctx.usdc.address);
+        let lender_payout = registry.get_retained_payouts(&lender).get_unchecked(0);
+        assert_eq!(lender_payout.amount, 918_0000000);
+
+        // The lender is cleared and its share released to it
+        registry.unfreeze_account(&officer, &lender);
+        registry.release_payout(&officer, &lender_payout.id);
+        assert_eq!(ctx.usdc.balance(&lender), 10018_0000000);
+        assert_eq!(ctx.usdc.balance(&registry_id), 977_0000000);
+    }
+
+    #[test]
+    fn test_dispute_blocks_settlement_until_adjusted() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payers = Address::generate(&ctx.env);
+        let arbiter = Address::generate(&ctx.env);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &invoice_id);
+
+        // Only the payers contract opens and closes disputes
+        assert_eq!(
+            ctx.client.try_open_dispute(&payers, &invoice_id).err(),
+            Some(Ok(AdelantaError::Unauthorized))
+        );
+        ctx.client.grant_role(&ctx.admin, &Role::Payers, &payers);
+        let invoice = ctx.client.open_dispute(&payers, &invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Disputed);
+
+        // The dispute blocks settlement and trading
+        assert_eq!(
+            ctx.settlement.try_settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None),
+            Err(Ok(SettlementError::InvoiceDisputed))
+        );
+        assert_eq!(
+            ctx.client.try_transfer_position(&lender, &ctx.sme, &invoice_id),
+            Err(Ok(AdelantaError::InvoiceDisputed))
+        );
+        assert_eq!(
+            ctx.client.try_open_dispute(&payers, &invoice_id).err(),
+            Some(Ok(AdelantaError::InvoiceDisputed))
+        );
+
+        let close = |resolution: &DisputeResolution| {
+            ctx.client
+                .try_close_dispute(&payers, &arbiter, &invoice_id, resolution)
+                .map(Result::unwrap)
+                .map_err(Result::unwrap)
+        };
+        assert_eq!(
+            close(&DisputeResolution::Adjusted(1000_0000000)).err(),
+            Some(AdelantaError::InvalidAmount)
+        );
+
+        // The cut may not reach into the lender's 900 advance and 18 fee
+        assert_eq!(
+            close(&DisputeResolution::Adjusted(917_9999999)).err(),
+            Some(AdelantaError::InvalidAmount)
+        );
+
+        // The payer owes 950 after the returns; the SME's remainder absorbs the cut
+        let invoice = close(&DisputeResolution::Adjusted(950_0000000)).unwrap();
+        assert_eq!(invoice.status, InvoiceStatus::Funded);
+        assert_eq!(invoice.amount, 950_0000000);
+
+        let result = ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &950_0000000, &None);
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(result.protocol_fee, 4_7500000);
+        assert_eq!(result.sme_amount, 27_2500000);
+        assert_eq!(ctx.client.get_invoice(&invoice_id).status, InvoiceStatus::Settled);
+
+        assert_eq!(close(&DisputeResolution::Valid).err(), Some(AdelantaError::InvoiceNotDisputed));
+    }
 
-        let (lender_amount, sme_amount, protocol_fee) =
-            client.preview_settlement(&invoice_id, &1000_0000000);
+    #[test]
+    fn test_dispute_invalidation_cancels_or_defaults() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payers = Address::generate(&ctx.env);
+        let arbiter = Address::generate(&ctx.env);
+        ctx.client.grant_role(&ctx.admin, &Role::Payers, &payers);
+
+        // Invalidating an unfunded invoice cancels it, refunds its lenders
+        // and releases its fingerprint, as cancelling it would
+        let unfunded_id = create_test_invoice(&ctx, 1000_0000000);
+        let fingerprint = ctx.client.get_invoice_fingerprint(&unfunded_id).unwrap();
+        ctx.client.fund_invoice_partial(&lender, &unfunded_id, &400_0000000);
+        ctx.client.open_dispute(&payers, &unfunded_id);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&lender, &unfunded_id),
+            Err(Ok(AdelantaError::InvoiceDisputed))
+        );
+        ctx.client.close_dispute(&payers, &arbiter, &unfunded_id, &DisputeResolution::Invalid);
+        assert_eq!(ctx.client.get_invoice(&unfunded_id).status, InvoiceStatus::Cancelled);
+        assert_eq!(ctx.usdc.balance(&lender), 10000_0000000);
+        assert_eq!(ctx.client.find_invoice_by_fingerprint(&fingerprint), None);
+        assert_eq!(
+            ctx.client.try_open_dispute(&payers, &unfunded_id).err(),
+            Some(Ok(AdelantaError::InvoiceCancelled))
+        );
 
-        assert_eq!(lender_amount, 918_0000000);
-        assert_eq!(protocol_fee, 5_0000000);
-        assert_eq!(sme_amount, 77_0000000);
+        // An upheld invoice returns to the status it was disputed in
+        let acknowledged_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.record_acknowledgement(&payers, &acknowledged_id);
+        ctx.client.open_dispute(&payers, &acknowledged_id);
+        ctx.client.close_dispute(&payers, &arbiter, &acknowledged_id, &DisputeResolution::Valid);
+        assert_eq!(ctx.client.get_invoice(&acknowledged_id).status, InvoiceStatus::Acknowledged);
+
+        // An invoice upheld as valid carries on as before
+        let funded_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&lender, &funded_id);
+        ctx.client.open_dispute(&payers, &funded_id);
+        ctx.client.close_dispute(&payers, &arbiter, &funded_id, &DisputeResolution::Valid);
+        assert_eq!(ctx.client.get_invoice(&funded_id).status, InvoiceStatus::Funded);
+
+        // Invalidating a funded invoice defaults it against the SME only,
+        // and the lender recovers from the SME
+        ctx.client.open_dispute(&payers, &funded_id);
+        ctx.client.close_dispute(&payers, &arbiter, &funded_id, &DisputeResolution::Invalid);
+        assert_eq!(ctx.client.get_invoice(&funded_id).status, InvoiceStatus::Defaulted);
+
+        ctx.settlement.recover_invoice(&ctx.admin, &funded_id, &918_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 10018_0000000);
     }
 }
@@ -1,5 +1,6 @@
 //! Custom error types for the Adelanta Invoice Contract
 
+use adelanta_types::fx::FxError;
 use soroban_sdk::contracterror;
 
 #[contracterror]
@@ -28,12 +29,75 @@ pub enum AdelantaError {
     InvalidFeePercentage = 10,
     /// Invalid advance percentage (must be 0-100)
     InvalidAdvancePercentage = 11,
-    /// Insufficient settlement amount
-    InsufficientSettlement = 12,
-    /// Transfer failed
-    TransferFailed = 13,
     /// Invoice is expired
     InvoiceExpired = 14,
-    /// Invalid corporate payer
-    InvalidPayer = 15,
+    /// Contribution exceeds the unfunded remainder of the advance
+    FundingExceedsRemaining = 16,
+    /// Invoice already has the maximum number of contributing lenders
+    TooManyLenders = 17,
+    /// Address holds no position in the invoice
+    PositionNotFound = 18,
+    /// Position cannot be transferred to its current holder
+    InvalidRecipient = 20,
+    /// Invoice is defaulted or written off
+    InvoiceDefaulted = 21,
+    /// Invoice is not defaulted
+    InvoiceNotDefaulted = 22,
+    /// Grace period after the due date has not expired yet
+    GracePeriodActive = 23,
+    /// Invalid interest rate (must be 0-100% annualized)
+    InvalidInterestRate = 24,
+    /// The last admin cannot give up the admin role
+    LastAdmin = 26,
+    /// Operation is paused
+    Paused = 27,
+    /// An invoice with this fingerprint is already registered
+    DuplicateInvoice = 28,
+    /// Invoice must be acknowledged by its payer before funding
+    NotAcknowledged = 31,
+    /// Invoice is already acknowledged
+    AlreadyAcknowledged = 32,
+    /// Asset is not approved for new invoices
+    AssetNotSupported = 36,
+    /// No FX oracle is configured for fiat-denominated invoices
+    OracleNotConfigured = 38,
+    /// Oracle has no usable price for the currency or asset
+    PriceUnavailable = 39,
+    /// Oracle price is older than the configured maximum age
+    StalePrice = 40,
+    /// FX rate moved beyond the slippage tolerance
+    FxSlippageExceeded = 41,
+    /// No pricing rule matches the blended risk score, country and industry
+    RiskNotPriced = 42,
+    /// Fee below the minimum of the SME's pricing rule
+    FeeBelowMinimum = 43,
+    /// Funding would exceed the SME's exposure limit
+    SmeExposureExceeded = 45,
+    /// Funding would exceed the payer's exposure limit
+    PayerExposureExceeded = 46,
+    /// Funding would exceed the country's exposure limit
+    CountryExposureExceeded = 47,
+    /// Funding would exceed the industry's exposure limit
+    IndustryExposureExceeded = 48,
+    /// Funding would exceed the global exposure limit
+    GlobalExposureExceeded = 49,
+    /// Account does not meet the compliance registry's KYC/KYB requirements
+    KycRequired = 51,
+    /// Invoice is disputed and waiting for an arbiter
+    InvoiceDisputed = 52,
+    /// Invoice has no open dispute
+    InvoiceNotDisputed = 53,
+    /// Invoice is cancelled
+    InvoiceCancelled = 54,
+    /// Risk engine failed to quote the invoice
+    RiskEngineUnavailable = 56,
+}
+
+impl From<FxError> for AdelantaError {
+    fn from(error: FxError) -> Self {
+        match error {
+            FxError::PriceUnavailable => AdelantaError::PriceUnavailable,
+            FxError::StalePrice => AdelantaError::StalePrice,
+        }
+    }
 }
@@ -0,0 +1,310 @@
+//! Contract events for the Adelanta Invoice Contract
+//!
+//! Every invoice lifecycle transition and config change is published via
+//! `env.events()`. Topics are laid out as `(name, version, ...keys)` so
+//! indexers can filter on event name and schema version without decoding
+//! the payload.
+
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, String, Symbol};
+
+use crate::types::{
+    AssetConfig, ContractConfig, FxConfig, Invoice, InvoiceStatus, Operation,
+    PositionTransfer, Role,
+};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const CONFIG_UPDATED: Symbol = symbol_short!("cfg_upd");
+pub const ASSET_SET: Symbol = symbol_short!("asset");
+pub const FX_CONFIG_UPDATED: Symbol = symbol_short!("fx_cfg");
+pub const COMPLIANCE_REGISTRY_SET: Symbol = symbol_short!("kyc_reg");
+pub const RISK_ENGINE_SET: Symbol = symbol_short!("risk_eng");
+pub const ROLE_GRANTED: Symbol = symbol_short!("role_grnt");
+pub const ROLE_REVOKED: Symbol = symbol_short!("role_rvk");
+pub const PAUSED: Symbol = symbol_short!("paused");
+pub const UNPAUSED: Symbol = symbol_short!("unpaused");
+pub const UPGRADED: Symbol = symbol_short!("upgraded");
+pub const MIGRATED: Symbol = symbol_short!("migrated");
+pub const INVOICE_CREATED: Symbol = symbol_short!("created");
+pub const INVOICE_CONTRIBUTION: Symbol = symbol_short!("contrib");
+pub const INVOICE_FUNDED: Symbol = symbol_short!("funded");
+pub const INVOICE_CANCELLED: Symbol = symbol_short!("cancelled");
+pub const INVOICE_DEFAULTED: Symbol = symbol_short!("defaulted");
+pub const INVOICE_WRITTEN_OFF: Symbol = symbol_short!("wrt_off");
+pub const POSITION_TRANSFERRED: Symbol = symbol_short!("pos_xfer");
+pub const STATUS_CHANGED: Symbol = symbol_short!("status");
+
+/// Payload of the `created` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceCreatedEvent {
+    pub payer_id: String,
+    pub amount: i128,
+    pub advance_amount: i128,
+    pub fee_bps: u32,
+    pub due_date: u64,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Payload of the `contrib` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceContributionEvent {
+    pub amount: i128,
+    pub funded_amount: i128,
+    pub advance_amount: i128,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Payload of the `funded` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceFundedEvent {
+    pub sme: Address,
+    pub amount: i128,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Payload of the `cancelled` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceCancelledEvent {
+    pub cancelled_by: Address,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Payload of the `defaulted` and `wrt_off` events
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceDefaultEvent {
+    pub lender: Option<Address>,
+    pub amount: i128,
+    pub amount_received: i128,
+    pub due_date: u64,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Payload of the `status` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceStatusEvent {
+    pub from: InvoiceStatus,
+    pub to: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    // ========== CONFIG ==========
+
+    /// Topics: `("init", version, admin)`
+    pub fn initialized(env: &Env, config: &ContractConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.admin.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("cfg_upd", version, caller)`
+    pub fn config_updated(env: &Env, caller: &Address, config: &ContractConfig) {
+        env.events().publish(
+            (CONFIG_UPDATED, EVENTS_VERSION, caller.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("asset", version, asset)`
+    pub fn asset_set(env: &Env, asset: &AssetConfig) {
+        env.events().publish(
+            (ASSET_SET, EVENTS_VERSION, asset.asset.clone()),
+            asset.clone(),
+        );
+    }
+
+    /// Topics: `("fx_cfg", version, caller)`
+    pub fn fx_config_updated(env: &Env, caller: &Address, config: &FxConfig) {
+        env.events().publish(
+            (FX_CONFIG_UPDATED, EVENTS_VERSION, caller.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("kyc_reg", version, caller)`; payload is the registry, if any
+    pub fn compliance_registry_set(env: &Env, caller: &Address, registry: &Option<Address>) {
+        env.events().publish(
+            (COMPLIANCE_REGISTRY_SET, EVENTS_VERSION, caller.clone()),
+            registry.clone(),
+        );
+    }
+
+    /// Topics: `("risk_eng", version, caller)`; payload is the engine, if any
+    pub fn risk_engine_set(env: &Env, caller: &Address, engine: &Option<Address>) {
+        env.events().publish(
+            (RISK_ENGINE_SET, EVENTS_VERSION, caller.clone()),
+            engine.clone(),
+        );
+    }
+
+    // ========== ROLES ==========
+
+    /// Topics: `("role_grnt", version, role, account)`; payload is the granting admin
+    pub fn role_granted(env: &Env, role: Role, account: &Address, sender: &Address) {
+        env.events().publish(
+            (ROLE_GRANTED, EVENTS_VERSION, role, account.clone()),
+            sender.clone(),
+        );
+    }
+
+    /// Topics: `("role_rvk", version, role, account)`; payload is the revoking
+    /// admin, or the account itself when renouncing
+    pub fn role_revoked(env: &Env, role: Role, account: &Address, sender: &Address) {
+        env.events().publish(
+            (ROLE_REVOKED, EVENTS_VERSION, role, account.clone()),
+            sender.clone(),
+        );
+    }
+
+    // ========== PAUSE ==========
+
+    /// Topics: `("paused", version, operation)`; payload is the pauser
+    pub fn paused(env: &Env, operation: Operation, caller: &Address) {
+        env.events()
+            .publish((PAUSED, EVENTS_VERSION, operation), caller.clone());
+    }
+
+    /// Topics: `("unpaused", version, operation)`; payload is the admin
+    pub fn unpaused(env: &Env, operation: Operation, caller: &Address) {
+        env.events()
+            .publish((UNPAUSED, EVENTS_VERSION, operation), caller.clone());
+    }
+
+    // ========== UPGRADES ==========
+
+    /// Topics: `("upgraded", version, admin)`; payload is the new Wasm hash
+    pub fn upgraded(env: &Env, admin: &Address, wasm_hash: &BytesN<32>) {
+        env.events()
+            .publish((UPGRADED, EVENTS_VERSION, admin.clone()), wasm_hash.clone());
+    }
+
+    /// Topics: `("migrated", version)`; payload is `(from, to)` schema versions
+    pub fn migrated(env: &Env, from: u32, to: u32) {
+        env.events()
+            .publish((MIGRATED, EVENTS_VERSION), (from, to));
+    }
+
+    // ========== INVOICES ==========
+
+    /// Topics: `("created", version, invoice_id, sme)`
+    pub fn invoice_created(env: &Env, invoice: &Invoice) {
+        env.events().publish(
+            (INVOICE_CREATED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            InvoiceCreatedEvent {
+                payer_id: invoice.payer_id.clone(),
+                amount: invoice.amount,
+                advance_amount: invoice.advance_amount,
+                fee_bps: invoice.fee_bps,
+                due_date: invoice.due_date,
+                status: invoice.status.clone(),
+                timestamp: invoice.created_at,
+            },
+        );
+    }
+
+    /// Topics: `("contrib", version, invoice_id, lender)`
+    pub fn invoice_contribution(env: &Env, invoice: &Invoice, lender: &Address, amount: i128) {
+        env.events().publish(
+            (INVOICE_CONTRIBUTION, EVENTS_VERSION, invoice.id, lender.clone()),
+            InvoiceContributionEvent {
+                amount,
+                funded_amount: invoice.funded_amount,
+                advance_amount: invoice.advance_amount,
+                status: invoice.status.clone(),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("funded", version, invoice_id, lender)`
+    pub fn invoice_funded(env: &Env, invoice: &Invoice, lender: &Address) {
+        env.events().publish(
+            (INVOICE_FUNDED, EVENTS_VERSION, invoice.id, lender.clone()),
+            InvoiceFundedEvent {
+                sme: invoice.sme.clone(),
+                amount: invoice.advance_amount,
+                status: invoice.status.clone(),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("cancelled", version, invoice_id, sme)`
+    pub fn invoice_cancelled(env: &Env, invoice: &Invoice, cancelled_by: &Address) {
+        env.events().publish(
+            (INVOICE_CANCELLED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            InvoiceCancelledEvent {
+                cancelled_by: cancelled_by.clone(),
+                status: invoice.status.clone(),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("defaulted", version, invoice_id, sme)`
+    pub fn invoice_defaulted(env: &Env, invoice: &Invoice) {
+        env.events().publish(
+            (INVOICE_DEFAULTED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            Self::default_event(env, invoice),
+        );
+    }
+
+    /// Topics: `("wrt_off", version, invoice_id, sme)`
+    pub fn invoice_written_off(env: &Env, invoice: &Invoice) {
+        env.events().publish(
+            (INVOICE_WRITTEN_OFF, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            Self::default_event(env, invoice),
+        );
+    }
+
+    /// Topics: `("status", version, invoice_id, sme)`; published on every
+    /// status change, whichever contract caused it
+    pub fn status_changed(env: &Env, invoice: &Invoice, from: &InvoiceStatus) {
+        env.events().publish(
+            (STATUS_CHANGED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            InvoiceStatusEvent {
+                from: from.clone(),
+                to: invoice.status.clone(),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    fn default_event(env: &Env, invoice: &Invoice) -> InvoiceDefaultEvent {
+        InvoiceDefaultEvent {
+            lender: invoice.lender.clone(),
+            amount: invoice.amount,
+            amount_received: invoice.amount_received,
+            due_date: invoice.due_date,
+            status: invoice.status.clone(),
+            timestamp: env.ledger().timestamp(),
+        }
+    }
+
+    // ========== SECONDARY MARKET ==========
+
+    /// Topics: `("pos_xfer", version, invoice_id, to)`
+    pub fn position_transferred(env: &Env, invoice_id: u64, transfer: &PositionTransfer) {
+        env.events().publish(
+            (POSITION_TRANSFERRED, EVENTS_VERSION, invoice_id, transfer.to.clone()),
+            transfer.clone(),
+        );
+    }
+}
@@ -11,7 +11,7 @@
 //! - Invoice tokenization as Stellar assets
 //! - Instant liquidity funding (90% advance)
 //! - Programmable settlement with auto-split
-//! - Reputation tracking for SMEs
+//! - SME and payer reputation kept by the risk engine
 //! 
 //! ## Flow
 //! 1. SME creates an invoice → minted as unique asset
@@ -25,5 +25,9 @@ mod contract;
 mod storage;
 mod types;
 mod errors;
+mod events;
+mod compliance;
+mod risk;
+mod migration;
 
 pub use contract::AdelantaInvoiceContract;
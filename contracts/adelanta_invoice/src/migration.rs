@@ -0,0 +1,166 @@
+//! Storage schema versioning for the Adelanta Invoice Contract
+//!
+//! The schema version is kept in instance storage. After an upgrade that
+//! changes a stored layout, records written by the previous build keep their
+//! old layout until `migrate` rewrites them in batches. Until then storage
+//! reads detect old records and decode them through the legacy layouts below,
+//! so the contract stays usable while a migration is in progress.
+
+use soroban_sdk::{contracttype, Address, Env, Map, String, Symbol, TryFromVal, Val};
+
+use crate::contract::DEFAULT_GRACE_PERIOD_SECS;
+use crate::types::{ContractConfig, FeeModel, Invoice, InvoiceStatus};
+
+/// Current storage schema version
+///
+/// * 0 - layout of the initial deployment, before schema versioning existed
+/// * 1 - current layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `ContractConfig` as stored in schema version 0
+#[contracttype(export = false)]
+#[derive(Clone, Debug)]
+pub struct ContractConfigV0 {
+    pub admin: Address,
+    pub usdc_token: Address,
+    pub treasury: Address,
+    pub default_advance_bps: u32,
+    pub protocol_fee_bps: u32,
+    pub min_invoice_amount: i128,
+    pub max_invoice_amount: i128,
+}
+
+/// `Invoice` as stored in schema version 0
+#[contracttype(export = false)]
+#[derive(Clone, Debug)]
+pub struct InvoiceV0 {
+    pub id: u64,
+    pub sme: Address,
+    pub payer_id: String,
+    pub amount: i128,
+    pub advance_amount: i128,
+    pub fee_bps: u32,
+    pub lender: Option<Address>,
+    pub status: InvoiceStatus,
+    pub created_at: u64,
+    pub funded_at: Option<u64>,
+    pub settled_at: Option<u64>,
+    pub due_date: u64,
+    pub country: String,
+    pub industry: String,
+}
+
+/// SME reputation as stored in schema version 0; reputation is now kept
+/// by the risk engine, which takes these records over when migrating
+#[contracttype(export = false)]
+#[derive(Clone, Debug)]
+pub struct SmeReputationV0 {
+    pub sme: Address,
+    pub total_invoices: u32,
+    pub settled_invoices: u32,
+    pub total_volume: i128,
+    pub avg_settlement_days: u32,
+    pub on_time_rate_bps: u32,
+    pub risk_score: u32,
+}
+
+impl ContractConfigV0 {
+    pub fn upgrade(self) -> ContractConfig {
+        ContractConfig {
+            admin: self.admin,
+            usdc_token: self.usdc_token,
+            treasury: self.treasury,
+            default_advance_bps: self.default_advance_bps,
+            protocol_fee_bps: self.protocol_fee_bps,
+            min_invoice_amount: self.min_invoice_amount,
+            max_invoice_amount: self.max_invoice_amount,
+            grace_period_secs: DEFAULT_GRACE_PERIOD_SECS,
+            require_acknowledgement: false,
+            acknowledged_advance_bps: 0,
+        }
+    }
+}
+
+impl InvoiceV0 {
+    /// Version 0 invoices were in the asset the contract was initialized
+    /// with, had a single lender funding the full advance, and were settled
+    /// in one payment split as advance + flat fee to the lender and the
+    /// protocol fee on the invoice amount to the treasury.
+    pub fn upgrade(self, config: &ContractConfig) -> Invoice {
+        let funded_amount = if self.lender.is_some() {
+            self.advance_amount
+        } else {
+            0
+        };
+        let (amount_received, lender_paid, protocol_fee_paid) =
+            if self.status == InvoiceStatus::Settled {
+                (
+                    self.amount,
+                    self.advance_amount + (self.advance_amount * self.fee_bps as i128) / 10000,
+                    (self.amount * config.protocol_fee_bps as i128) / 10000,
+                )
+            } else {
+                (0, 0, 0)
+            };
+
+        Invoice {
+            id: self.id,
+            sme: self.sme,
+            payer_id: self.payer_id,
+            asset: config.usdc_token.clone(),
+            currency: None,
+            face_value: 0,
+            face_value_received: 0,
+            funding_fx_rate: None,
+            settlement_fx_rate: None,
+            amount: self.amount,
+            advance_amount: self.advance_amount,
+            fee_bps: self.fee_bps,
+            fee_model: FeeModel::Flat,
+            lender: self.lender,
+            funded_amount,
+            amount_received,
+            lender_paid,
+            protocol_fee_paid,
+            status: self.status,
+            created_at: self.created_at,
+            funded_at: self.funded_at,
+            settled_at: self.settled_at,
+            defaulted_at: None,
+            due_date: self.due_date,
+            country: self.country,
+            industry: self.industry,
+        }
+    }
+}
+
+/// Decode a stored config in either layout
+pub fn config(env: &Env, raw: &Val) -> ContractConfig {
+    if has_field(env, raw, "require_acknowledgement") {
+        return decode(env, raw);
+    }
+    decode::<ContractConfigV0>(env, raw).upgrade()
+}
+
+/// Decode a stored invoice in either layout
+pub fn invoice(env: &Env, raw: &Val, config: &ContractConfig) -> Invoice {
+    if has_field(env, raw, "asset") {
+        return decode(env, raw);
+    }
+    decode::<InvoiceV0>(env, raw).upgrade(config)
+}
+
+/// Whether a stored struct has `field`, i.e. was written in a layout that
+/// includes it
+fn has_field(env: &Env, raw: &Val, field: &str) -> bool {
+    Map::<Symbol, Val>::try_from_val(env, raw)
+        .unwrap()
+        .contains_key(Symbol::new(env, field))
+}
+
+fn decode<T: TryFromVal<Env, Val>>(env: &Env, raw: &Val) -> T {
+    match T::try_from_val(env, raw) {
+        Ok(value) => value,
+        Err(_) => panic!("stored record does not match its layout"),
+    }
+}
@@ -0,0 +1,130 @@
+//! Pricing, exposure limits and reputation through the risk engine
+//!
+//! The risk engine is a sibling contract keeping the reputation of SMEs and
+//! payers, the pricing grid and the exposure totals. Every credit event of
+//! an invoice is reported to it. Without an engine, invoices are advanced
+//! at the default rate with no fee floor and no exposure limit.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, String};
+
+use crate::errors::AdelantaError;
+use crate::migration::SmeReputationV0;
+use crate::storage::Storage;
+use crate::types::Invoice;
+
+/// Code of the risk engine's `RiskNotPriced` error
+const RISK_NOT_PRICED: u32 = 10;
+
+/// Mirrors `CreditEvent` of the risk engine
+#[contracttype(export = false)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CreditEvent {
+    Created,
+    Acknowledged,
+    Reserved,
+    Funded,
+    Paid,
+    Defaulted,
+    Invalidated,
+    Disputed,
+    Cancelled,
+}
+
+/// Mirrors `PricingQuote` of the risk engine
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PricingQuote {
+    pub risk_score: u32,
+    pub sme_risk_score: u32,
+    pub payer_risk_score: u32,
+    pub advance_bps: Option<u32>,
+    pub min_fee_bps: u32,
+}
+
+/// Mirrors `ExposureKey` of the risk engine
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExposureKey {
+    Global,
+    Sme(Address),
+    Payer(String),
+    Country(String),
+    Industry(String),
+}
+
+/// Subset of the risk engine interface used by the contract
+#[allow(dead_code)]
+#[contractclient(name = "RiskEngineClient")]
+pub trait RiskEngine {
+    /// Pricing of an invoice by `sme` owed by `payer_id` in `country` and
+    /// `industry`
+    fn get_quote(env: Env, sme: Address, payer_id: String, country: String, industry: String) -> PricingQuote;
+
+    /// First exposure dimension funding the invoice would push over its limit
+    fn get_exposure_breach(env: Env, invoice: Invoice) -> Option<ExposureKey>;
+
+    /// Record a change in an invoice's life
+    fn record_event(env: Env, invoice: Invoice, event: CreditEvent);
+
+    /// Hand over an SME's reputation from schema version 0
+    fn import_sme_reputation(env: Env, legacy: SmeReputationV0);
+}
+
+/// Advance rate and minimum fee of an invoice by `sme` owed by `payer_id`
+/// in `country` and `industry`, or `None` while no engine or pricing grid
+/// is set. Fails with `RiskNotPriced` when no rule of the grid matches, and
+/// with `RiskEngineUnavailable` when the engine fails to quote.
+pub fn quote(
+    env: &Env,
+    sme: &Address,
+    payer_id: &String,
+    country: &String,
+    industry: &String,
+) -> Result<Option<(u32, u32)>, AdelantaError> {
+    let Some(engine) = Storage::get_risk_engine(env) else {
+        return Ok(None);
+    };
+
+    match RiskEngineClient::new(env, &engine).try_get_quote(sme, payer_id, country, industry) {
+        Ok(Ok(quote)) => Ok(quote.advance_bps.map(|advance_bps| (advance_bps, quote.min_fee_bps))),
+        Err(Ok(error)) if error == soroban_sdk::Error::from_contract_error(RISK_NOT_PRICED) => {
+            Err(AdelantaError::RiskNotPriced)
+        }
+        _ => Err(AdelantaError::RiskEngineUnavailable),
+    }
+}
+
+/// Reject funding when the invoice's advance would push any exposure above
+/// its limit. Checked at the first contribution, which reserves the advance.
+pub fn check_exposure(env: &Env, invoice: &Invoice) -> Result<(), AdelantaError> {
+    let Some(engine) = Storage::get_risk_engine(env) else {
+        return Ok(());
+    };
+
+    match RiskEngineClient::new(env, &engine).get_exposure_breach(invoice) {
+        None => Ok(()),
+        Some(ExposureKey::Sme(_)) => Err(AdelantaError::SmeExposureExceeded),
+        Some(ExposureKey::Payer(_)) => Err(AdelantaError::PayerExposureExceeded),
+        Some(ExposureKey::Country(_)) => Err(AdelantaError::CountryExposureExceeded),
+        Some(ExposureKey::Industry(_)) => Err(AdelantaError::IndustryExposureExceeded),
+        Some(ExposureKey::Global) => Err(AdelantaError::GlobalExposureExceeded),
+    }
+}
+
+/// Report a change in an invoice's life to the engine, if one is set
+pub fn record(env: &Env, invoice: &Invoice, event: CreditEvent) {
+    if let Some(engine) = Storage::get_risk_engine(env) {
+        RiskEngineClient::new(env, &engine).record_event(invoice, &event);
+    }
+}
+
+/// Hand an SME's reputation from schema version 0 over to the engine, if
+/// one is set
+pub fn import_legacy_reputation(env: &Env, sme: &Address) {
+    let Some(engine) = Storage::get_risk_engine(env) else {
+        return;
+    };
+    if let Some(legacy) = Storage::take_legacy_sme_reputation(env, sme) {
+        RiskEngineClient::new(env, &engine).import_sme_reputation(&legacy);
+    }
+}
@@ -1,25 +1,71 @@
 //! Storage keys and helpers for the Adelanta Invoice Contract
 
-use soroban_sdk::{contracttype, Address, Env};
+use soroban_sdk::{contracttype, Address, BytesN, Env, IntoVal, Val, Vec};
 
-use crate::types::{ContractConfig, Invoice, SmeReputation};
+use crate::migration::{self, SmeReputationV0, SCHEMA_VERSION};
+use crate::types::{
+    AssetConfig, AssetStats, ContractConfig, FxConfig, Invoice, InvoiceIndex, InvoiceStatus,
+    LenderContribution, PositionTransfer, Operation, Role,
+};
+
+/// Invoice IDs stored per index page
+const INDEX_PAGE_SIZE: u32 = 100;
 
 /// Storage key types
-#[contracttype]
+#[contracttype(export = false)]
 pub enum DataKey {
     /// Contract configuration
     Config,
+    /// Storage schema version
+    SchemaVersion,
+    /// Next invoice ID to rewrite in an ongoing migration
+    MigrationCursor,
+    /// Role membership by (role, account)
+    Role(Role, Address),
+    /// Number of accounts holding a role
+    RoleMembers(Role),
+    /// Pause flag per operation
+    Paused(Operation),
+    /// Approved settlement asset by contract address
+    Asset(Address),
+    /// Addresses of every approved asset, in approval order
+    Assets,
+    /// Statistics per asset
+    AssetStats(Address),
+    /// Price oracle settings for fiat-denominated invoices
+    FxConfig,
+    /// Compliance registry checked on invoice creation and funding
+    ComplianceRegistry,
+    /// Risk engine scoring, pricing and limiting invoices
+    RiskEngine,
     /// Invoice by ID
     Invoice(u64),
     /// Next invoice ID counter
     NextInvoiceId,
-    /// SME reputation data
+    /// Invoice ID registered under a document fingerprint
+    Fingerprint(BytesN<32>),
+    /// Document fingerprint of an invoice
+    InvoiceFingerprint(u64),
+    /// Number of invoices in an index
+    IndexLen(InvoiceIndex),
+    /// Page of invoice IDs in an index, by page number
+    IndexPage(InvoiceIndex, u32),
+    /// Position of an invoice within an index
+    IndexSlot(InvoiceIndex, u64),
+    /// Lender contributions to an invoice's advance
+    InvoiceContributions(u64),
+    /// History of lender position transfers for an invoice
+    PositionHistory(u64),
+    /// Status of a disputed invoice before its dispute
+    DisputedStatus(u64),
+    /// SME reputation (schema version 0), handed to the risk engine when
+    /// migrating
     SmeReputation(Address),
     /// Total invoices count
     TotalInvoices,
-    /// Total volume funded
+    /// Total volume funded (schema version 0)
     TotalVolumeFunded,
-    /// Total volume settled
+    /// Total volume settled (schema version 0)
     TotalVolumeSettled,
 }
 
@@ -30,7 +76,11 @@ impl Storage {
     // ========== CONFIG ==========
     
     pub fn get_config(env: &Env) -> Option<ContractConfig> {
-        env.storage().instance().get(&DataKey::Config)
+        if Self::is_migrated(env) {
+            return env.storage().instance().get(&DataKey::Config);
+        }
+        let raw: Val = env.storage().instance().get(&DataKey::Config)?;
+        Some(migration::config(env, &raw))
     }
 
     pub fn set_config(env: &Env, config: &ContractConfig) {
@@ -41,14 +91,198 @@ impl Storage {
         env.storage().instance().has(&DataKey::Config)
     }
 
+    // ========== SCHEMA VERSION ==========
+
+    pub fn get_schema_version(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0u32)
+    }
+
+    pub fn set_schema_version(env: &Env, version: u32) {
+        env.storage().instance().set(&DataKey::SchemaVersion, &version);
+    }
+
+    /// Whether every stored record has the current layout
+    pub fn is_migrated(env: &Env) -> bool {
+        Self::get_schema_version(env) >= SCHEMA_VERSION
+    }
+
+    pub fn get_migration_cursor(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::MigrationCursor)
+            .unwrap_or(1u64)
+    }
+
+    pub fn set_migration_cursor(env: &Env, id: u64) {
+        env.storage().instance().set(&DataKey::MigrationCursor, &id);
+    }
+
+    pub fn remove_migration_cursor(env: &Env) {
+        env.storage().instance().remove(&DataKey::MigrationCursor);
+    }
+
+    // ========== ROLES ==========
+
+    pub fn has_role(env: &Env, role: Role, account: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Role(role, account.clone()))
+    }
+
+    pub fn get_role_members(env: &Env, role: Role) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::RoleMembers(role))
+            .unwrap_or(0u32)
+    }
+
+    pub fn grant_role(env: &Env, role: Role, account: &Address) {
+        Self::set_persistent(env, &DataKey::Role(role, account.clone()), &true);
+
+        let members = Self::get_role_members(env, role);
+        env.storage().instance().set(&DataKey::RoleMembers(role), &(members + 1));
+    }
+
+    pub fn revoke_role(env: &Env, role: Role, account: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Role(role, account.clone()));
+
+        let members = Self::get_role_members(env, role);
+        env.storage()
+            .instance()
+            .set(&DataKey::RoleMembers(role), &members.saturating_sub(1));
+    }
+
+    // ========== PAUSE ==========
+
+    pub fn is_paused(env: &Env, operation: Operation) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::Paused(operation))
+            .unwrap_or(false)
+    }
+
+    pub fn set_paused(env: &Env, operation: Operation, paused: bool) {
+        env.storage().instance().set(&DataKey::Paused(operation), &paused);
+    }
+
+    // ========== ASSETS ==========
+
+    pub fn get_asset(env: &Env, asset: &Address) -> Option<AssetConfig> {
+        let stored = env.storage().instance().get(&DataKey::Asset(asset.clone()));
+        if stored.is_some() || Self::is_migrated(env) {
+            return stored;
+        }
+        // Before the registry existed, the initial asset was the only one
+        let config = Self::get_config(env)?;
+        if config.usdc_token != *asset {
+            return None;
+        }
+        Some(AssetConfig {
+            asset: config.usdc_token,
+            min_invoice_amount: config.min_invoice_amount,
+            max_invoice_amount: config.max_invoice_amount,
+            enabled: true,
+        })
+    }
+
+    pub fn get_assets(env: &Env) -> Vec<Address> {
+        if let Some(assets) = env.storage().instance().get(&DataKey::Assets) {
+            return assets;
+        }
+        match Self::get_config(env) {
+            Some(config) if !Self::is_migrated(env) => Vec::from_array(env, [config.usdc_token]),
+            _ => Vec::new(env),
+        }
+    }
+
+    /// Approve an asset or update its limits
+    pub fn set_asset(env: &Env, asset: &AssetConfig) {
+        Self::store_legacy_asset(env);
+        let mut assets = Self::get_assets(env);
+        if !assets.contains(&asset.asset) {
+            assets.push_back(asset.asset.clone());
+            env.storage().instance().set(&DataKey::Assets, &assets);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Asset(asset.asset.clone()), asset);
+    }
+
+    /// Write the initial asset of a contract created before the registry
+    /// existed, with its config limits and the global volume counters, into
+    /// the registry
+    pub fn store_legacy_asset(env: &Env) {
+        if env.storage().instance().has(&DataKey::Assets) {
+            return;
+        }
+        let Some(config) = Self::get_config(env) else {
+            return;
+        };
+        let asset = config.usdc_token;
+        if let Some(registered) = Self::get_asset(env, &asset) {
+            let stats = Self::get_asset_stats(env, &asset);
+            env.storage()
+                .instance()
+                .set(&DataKey::Assets, &Vec::from_array(env, [asset.clone()]));
+            env.storage().instance().set(&DataKey::Asset(asset.clone()), &registered);
+            Self::set_asset_stats(env, &stats);
+        }
+    }
+
+    // ========== FX ==========
+
+    pub fn get_fx_config(env: &Env) -> Option<FxConfig> {
+        env.storage().instance().get(&DataKey::FxConfig)
+    }
+
+    pub fn set_fx_config(env: &Env, config: &FxConfig) {
+        env.storage().instance().set(&DataKey::FxConfig, config);
+    }
+
+    // ========== COMPLIANCE ==========
+
+    pub fn get_compliance_registry(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::ComplianceRegistry)
+    }
+
+    pub fn set_compliance_registry(env: &Env, registry: Option<Address>) {
+        match registry {
+            Some(registry) => env.storage().instance().set(&DataKey::ComplianceRegistry, &registry),
+            None => env.storage().instance().remove(&DataKey::ComplianceRegistry),
+        }
+    }
+
+    // ========== RISK ENGINE ==========
+
+    pub fn get_risk_engine(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::RiskEngine)
+    }
+
+    pub fn set_risk_engine(env: &Env, engine: Option<Address>) {
+        match engine {
+            Some(engine) => env.storage().instance().set(&DataKey::RiskEngine, &engine),
+            None => env.storage().instance().remove(&DataKey::RiskEngine),
+        }
+    }
+
     // ========== INVOICES ==========
 
     pub fn get_invoice(env: &Env, id: u64) -> Option<Invoice> {
-        env.storage().persistent().get(&DataKey::Invoice(id))
+        if Self::is_migrated(env) {
+            return env.storage().persistent().get(&DataKey::Invoice(id));
+        }
+        let raw: Val = env.storage().persistent().get(&DataKey::Invoice(id))?;
+        let config = Self::get_config(env)?;
+        Some(migration::invoice(env, &raw, &config))
     }
 
     pub fn set_invoice(env: &Env, invoice: &Invoice) {
-        env.storage().persistent().set(&DataKey::Invoice(invoice.id), invoice);
+        Self::set_persistent(env, &DataKey::Invoice(invoice.id), invoice);
     }
 
     pub fn has_invoice(env: &Env, id: u64) -> bool {
@@ -66,16 +300,197 @@ impl Storage {
         env.storage().instance().set(&DataKey::NextInvoiceId, &id);
     }
 
-    // ========== SME REPUTATION ==========
+    // ========== FINGERPRINTS ==========
+
+    pub fn get_fingerprint_invoice(env: &Env, fingerprint: &BytesN<32>) -> Option<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Fingerprint(fingerprint.clone()))
+    }
+
+    pub fn get_invoice_fingerprint(env: &Env, invoice_id: u64) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::InvoiceFingerprint(invoice_id))
+    }
 
-    pub fn get_sme_reputation(env: &Env, sme: &Address) -> Option<SmeReputation> {
-        env.storage().persistent().get(&DataKey::SmeReputation(sme.clone()))
+    pub fn register_fingerprint(env: &Env, fingerprint: &BytesN<32>, invoice_id: u64) {
+        Self::set_persistent(env, &DataKey::Fingerprint(fingerprint.clone()), &invoice_id);
+        Self::set_persistent(env, &DataKey::InvoiceFingerprint(invoice_id), fingerprint);
     }
 
-    pub fn set_sme_reputation(env: &Env, reputation: &SmeReputation) {
+    pub fn release_fingerprint(env: &Env, fingerprint: &BytesN<32>, invoice_id: u64) {
         env.storage()
             .persistent()
-            .set(&DataKey::SmeReputation(reputation.sme.clone()), reputation);
+            .remove(&DataKey::Fingerprint(fingerprint.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::InvoiceFingerprint(invoice_id));
+    }
+
+    // ========== INVOICE INDEXES ==========
+
+    pub fn get_index_len(env: &Env, index: &InvoiceIndex) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::IndexLen(index.clone()))
+            .unwrap_or(0u32)
+    }
+
+    /// Append an invoice to an index (no-op if already present)
+    pub fn index_add(env: &Env, index: &InvoiceIndex, invoice_id: u64) {
+        let slot_key = DataKey::IndexSlot(index.clone(), invoice_id);
+        if env.storage().persistent().has(&slot_key) {
+            return;
+        }
+
+        let position = Self::get_index_len(env, index);
+        let page_key = DataKey::IndexPage(index.clone(), position / INDEX_PAGE_SIZE);
+        let mut page: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&page_key)
+            .unwrap_or(Vec::new(env));
+        page.push_back(invoice_id);
+
+        Self::set_persistent(env, &page_key, &page);
+        Self::set_persistent(env, &slot_key, &position);
+        Self::set_persistent(env, &DataKey::IndexLen(index.clone()), &(position + 1));
+    }
+
+    /// Remove an invoice from an index; the last entry moves into its place
+    pub fn index_remove(env: &Env, index: &InvoiceIndex, invoice_id: u64) {
+        let slot_key = DataKey::IndexSlot(index.clone(), invoice_id);
+        let position: u32 = match env.storage().persistent().get(&slot_key) {
+            Some(position) => position,
+            None => return,
+        };
+
+        let last = Self::get_index_len(env, index) - 1;
+        let last_page_key = DataKey::IndexPage(index.clone(), last / INDEX_PAGE_SIZE);
+        let mut last_page: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&last_page_key)
+            .expect("an indexed invoice's last page exists");
+        let moved = last_page.pop_back_unchecked();
+
+        if position != last {
+            if position / INDEX_PAGE_SIZE == last / INDEX_PAGE_SIZE {
+                last_page.set(position % INDEX_PAGE_SIZE, moved);
+            } else {
+                let page_key = DataKey::IndexPage(index.clone(), position / INDEX_PAGE_SIZE);
+                let mut page: Vec<u64> = env
+                    .storage()
+                    .persistent()
+                    .get(&page_key)
+                    .expect("the page of an indexed slot exists");
+                page.set(position % INDEX_PAGE_SIZE, moved);
+                Self::set_persistent(env, &page_key, &page);
+            }
+            Self::set_persistent(env, &DataKey::IndexSlot(index.clone(), moved), &position);
+        }
+
+        if last_page.is_empty() {
+            env.storage().persistent().remove(&last_page_key);
+        } else {
+            Self::set_persistent(env, &last_page_key, &last_page);
+        }
+        env.storage().persistent().remove(&slot_key);
+        Self::set_persistent(env, &DataKey::IndexLen(index.clone()), &last);
+    }
+
+    /// Up to `limit` invoice IDs of an index, starting at position `cursor`
+    pub fn get_index_range(env: &Env, index: &InvoiceIndex, cursor: u32, limit: u32) -> Vec<u64> {
+        let end = Self::get_index_len(env, index).min(cursor.saturating_add(limit));
+        let mut ids = Vec::new(env);
+
+        let mut position = cursor;
+        while position < end {
+            let page: Vec<u64> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::IndexPage(index.clone(), position / INDEX_PAGE_SIZE))
+                .expect("every position below the index length is paged");
+            let mut offset = position % INDEX_PAGE_SIZE;
+            while offset < page.len() && position < end {
+                ids.push_back(page.get_unchecked(offset));
+                offset += 1;
+                position += 1;
+            }
+        }
+
+        ids
+    }
+
+    // ========== CONTRIBUTIONS ==========
+
+    pub fn get_contributions(env: &Env, invoice_id: u64) -> Vec<LenderContribution> {
+        let contributions = env
+            .storage()
+            .persistent()
+            .get(&DataKey::InvoiceContributions(invoice_id));
+        match contributions {
+            Some(contributions) => contributions,
+            None if !Self::is_migrated(env) => Self::get_legacy_contributions(env, invoice_id),
+            None => Vec::new(env),
+        }
+    }
+
+    /// Schema version 0 kept no contribution records: a funded invoice's
+    /// single lender provided the whole advance
+    fn get_legacy_contributions(env: &Env, invoice_id: u64) -> Vec<LenderContribution> {
+        let mut contributions = Vec::new(env);
+        if let Some(invoice) = Self::get_invoice(env, invoice_id) {
+            if let (Some(lender), true) = (invoice.lender, invoice.funded_amount > 0) {
+                contributions.push_back(LenderContribution {
+                    lender,
+                    amount: invoice.funded_amount,
+                });
+            }
+        }
+        contributions
+    }
+
+    pub fn set_contributions(env: &Env, invoice_id: u64, contributions: &Vec<LenderContribution>) {
+        Self::set_persistent(env, &DataKey::InvoiceContributions(invoice_id), contributions);
+    }
+
+    // ========== SECONDARY MARKET ==========
+
+    pub fn get_position_history(env: &Env, invoice_id: u64) -> Vec<PositionTransfer> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PositionHistory(invoice_id))
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn add_position_transfer(env: &Env, invoice_id: u64, transfer: &PositionTransfer) {
+        let mut history = Self::get_position_history(env, invoice_id);
+        history.push_back(transfer.clone());
+        Self::set_persistent(env, &DataKey::PositionHistory(invoice_id), &history);
+    }
+
+    // ========== DISPUTES ==========
+
+    pub fn set_disputed_status(env: &Env, invoice_id: u64, status: &InvoiceStatus) {
+        Self::set_persistent(env, &DataKey::DisputedStatus(invoice_id), status);
+    }
+
+    pub fn take_disputed_status(env: &Env, invoice_id: u64) -> Option<InvoiceStatus> {
+        let key = DataKey::DisputedStatus(invoice_id);
+        let status = env.storage().persistent().get(&key)?;
+        env.storage().persistent().remove(&key);
+        Some(status)
+    }
+
+    // ========== LEGACY SME REPUTATION ==========
+
+    pub fn take_legacy_sme_reputation(env: &Env, sme: &Address) -> Option<SmeReputationV0> {
+        let key = DataKey::SmeReputation(sme.clone());
+        let reputation = env.storage().persistent().get(&key)?;
+        env.storage().persistent().remove(&key);
+        Some(reputation)
     }
 
     // ========== STATS ==========
@@ -92,43 +507,66 @@ impl Storage {
         env.storage().instance().set(&DataKey::TotalInvoices, &(current + 1));
     }
 
-    pub fn get_total_volume_funded(env: &Env) -> i128 {
+    pub fn get_asset_stats(env: &Env, asset: &Address) -> AssetStats {
+        let key = DataKey::AssetStats(asset.clone());
+        if let Some(stats) = env.storage().instance().get(&key) {
+            return stats;
+        }
+        let mut stats = AssetStats {
+            asset: asset.clone(),
+            total_invoices: 0,
+            volume_funded: 0,
+            volume_settled: 0,
+        };
+        // Before per-asset stats, the global counters covered the initial asset
+        let legacy = !Self::is_migrated(env)
+            && Self::get_config(env).is_some_and(|config| config.usdc_token == *asset);
+        if legacy {
+            let instance = env.storage().instance();
+            stats.total_invoices = Self::get_total_invoices(env);
+            stats.volume_funded = instance.get(&DataKey::TotalVolumeFunded).unwrap_or(0i128);
+            stats.volume_settled = instance.get(&DataKey::TotalVolumeSettled).unwrap_or(0i128);
+        }
+        stats
+    }
+
+    pub fn set_asset_stats(env: &Env, stats: &AssetStats) {
         env.storage()
             .instance()
-            .get(&DataKey::TotalVolumeFunded)
-            .unwrap_or(0i128)
+            .set(&DataKey::AssetStats(stats.asset.clone()), stats);
     }
 
-    pub fn add_volume_funded(env: &Env, amount: i128) {
-        let current = Self::get_total_volume_funded(env);
-        env.storage().instance().set(&DataKey::TotalVolumeFunded, &(current + amount));
+    pub fn increment_asset_invoices(env: &Env, asset: &Address) {
+        let mut stats = Self::get_asset_stats(env, asset);
+        stats.total_invoices += 1;
+        Self::set_asset_stats(env, &stats);
     }
 
-    pub fn get_total_volume_settled(env: &Env) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::TotalVolumeSettled)
-            .unwrap_or(0i128)
+    pub fn add_volume_funded(env: &Env, asset: &Address, amount: i128) {
+        let mut stats = Self::get_asset_stats(env, asset);
+        stats.volume_funded += amount;
+        Self::set_asset_stats(env, &stats);
     }
 
-    pub fn add_volume_settled(env: &Env, amount: i128) {
-        let current = Self::get_total_volume_settled(env);
-        env.storage().instance().set(&DataKey::TotalVolumeSettled, &(current + amount));
+    pub fn add_volume_settled(env: &Env, asset: &Address, amount: i128) {
+        let mut stats = Self::get_asset_stats(env, asset);
+        stats.volume_settled += amount;
+        Self::set_asset_stats(env, &stats);
     }
 
     // ========== TTL MANAGEMENT ==========
 
-    pub fn extend_instance_ttl(env: &Env) {
+    /// Write a persistent record, keeping it alive for the maximum TTL
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
         let max_ttl = env.storage().max_ttl();
-        env.storage()
-            .instance()
-            .extend_ttl(max_ttl - 1000, max_ttl);
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
     }
 
-    pub fn extend_invoice_ttl(env: &Env, id: u64) {
+    pub fn extend_instance_ttl(env: &Env) {
         let max_ttl = env.storage().max_ttl();
         env.storage()
-            .persistent()
-            .extend_ttl(&DataKey::Invoice(id), max_ttl - 1000, max_ttl);
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
     }
 }
@@ -0,0 +1,473 @@
+//! Main contract implementation for the Adelanta Marketplace
+//!
+//! Listings are kept here; positions only change hands in the invoice
+//! contract, which the marketplace calls with its `Marketplace` role once a
+//! buyer takes a listing. A listing is not withdrawn when its seller parts
+//! with the position in another way, or when the position grows, but it can
+//! only be bought while the seller holds exactly the position listed.
+
+use soroban_sdk::{contract, contractimpl, Address, Env};
+
+use crate::errors::MarketplaceError;
+use crate::events::Events;
+use crate::invoice::{InvoiceClient, InvoiceStatus, LenderPosition, Operation, PositionTransfer, Role};
+use crate::storage::Storage;
+use crate::types::{MarketplaceConfig, PositionListing};
+
+#[contract]
+pub struct AdelantaMarketplaceContract;
+
+#[contractimpl]
+impl AdelantaMarketplaceContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the marketplace
+    ///
+    /// # Arguments
+    /// * `invoice_contract` - Invoice contract whose positions are traded
+    pub fn initialize(env: Env, invoice_contract: Address) -> Result<(), MarketplaceError> {
+        if Storage::has_config(&env) {
+            return Err(MarketplaceError::AlreadyInitialized);
+        }
+
+        let config = MarketplaceConfig { invoice_contract };
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // LISTINGS
+    // ============================================================
+
+    /// List a lender position for sale at a price in the invoice's asset
+    ///
+    /// Listing the same position again replaces its price.
+    pub fn list_position(
+        env: Env,
+        seller: Address,
+        invoice_id: u64,
+        price: i128,
+    ) -> Result<PositionListing, MarketplaceError> {
+        seller.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        Self::require_not_paused(&invoices)?;
+        Self::require_marketplace_role(&env, &invoices)?;
+
+        if price <= 0 {
+            return Err(MarketplaceError::InvalidAmount);
+        }
+
+        let position = Self::require_tradable(&invoices, invoice_id, &seller)?;
+
+        let listing = PositionListing {
+            invoice_id,
+            seller,
+            amount: position.amount,
+            price,
+            listed_at: env.ledger().timestamp(),
+        };
+        Storage::set_listing(&env, &listing);
+        Storage::extend_instance_ttl(&env);
+
+        Events::position_listed(&env, &listing);
+
+        Ok(listing)
+    }
+
+    /// Withdraw a position listing
+    pub fn cancel_listing(env: Env, seller: Address, invoice_id: u64) -> Result<(), MarketplaceError> {
+        seller.require_auth();
+
+        if Storage::get_listing(&env, invoice_id, &seller).is_none() {
+            return Err(MarketplaceError::ListingNotFound);
+        }
+        Storage::remove_listing(&env, invoice_id, &seller);
+
+        Events::position_delisted(&env, invoice_id, &seller);
+
+        Ok(())
+    }
+
+    /// Buy a listed position, paying the asking price to the seller in the
+    /// invoice's asset
+    ///
+    /// The invoice contract moves the position and publishes the transfer.
+    pub fn buy_position(
+        env: Env,
+        buyer: Address,
+        invoice_id: u64,
+        seller: Address,
+    ) -> Result<PositionTransfer, MarketplaceError> {
+        buyer.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        Self::require_not_paused(&invoices)?;
+        Self::require_marketplace_role(&env, &invoices)?;
+
+        let listing = Storage::get_listing(&env, invoice_id, &seller)
+            .ok_or(MarketplaceError::ListingNotFound)?;
+        if buyer == seller {
+            return Err(MarketplaceError::InvalidRecipient);
+        }
+        let position = Self::require_tradable(&invoices, invoice_id, &seller)?;
+        if position.amount != listing.amount {
+            return Err(MarketplaceError::ListingStale);
+        }
+
+        Storage::remove_listing(&env, invoice_id, &seller);
+
+        Ok(invoices.trade_position(
+            &env.current_contract_address(),
+            &seller,
+            &buyer,
+            &invoice_id,
+            &listing.price,
+        ))
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get an open position listing
+    pub fn get_listing(env: Env, invoice_id: u64, seller: Address) -> Option<PositionListing> {
+        Storage::get_listing(&env, invoice_id, &seller)
+    }
+
+    /// Get the marketplace configuration
+    pub fn get_config(env: Env) -> Result<MarketplaceConfig, MarketplaceError> {
+        Storage::get_config(&env).ok_or(MarketplaceError::NotInitialized)
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    fn invoice_client(env: &Env) -> Result<InvoiceClient<'_>, MarketplaceError> {
+        let config = Storage::get_config(env).ok_or(MarketplaceError::NotInitialized)?;
+        Ok(InvoiceClient::new(env, &config.invoice_contract))
+    }
+
+    fn require_not_paused(invoices: &InvoiceClient) -> Result<(), MarketplaceError> {
+        if invoices.is_paused(&Operation::Trade) {
+            return Err(MarketplaceError::Paused);
+        }
+        Ok(())
+    }
+
+    /// Positions can only be traded once the invoice contract granted the
+    /// marketplace its `Marketplace` role
+    fn require_marketplace_role(env: &Env, invoices: &InvoiceClient) -> Result<(), MarketplaceError> {
+        if !invoices.has_role(&Role::Marketplace, &env.current_contract_address()) {
+            return Err(MarketplaceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Get the position `seller` holds in an invoice that is funded and
+    /// not yet fully settled, defaulted or disputed
+    fn require_tradable(
+        invoices: &InvoiceClient,
+        invoice_id: u64,
+        seller: &Address,
+    ) -> Result<LenderPosition, MarketplaceError> {
+        let position = invoices
+            .get_lender_position(&invoice_id, seller)
+            .filter(|position| !position.closed)
+            .ok_or(MarketplaceError::PositionNotFound)?;
+
+        match invoices.get_invoice(&invoice_id).status {
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled => Ok(position),
+            _ => Err(MarketplaceError::NotTradable),
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::{Client as TokenClient, StellarAssetClient},
+        BytesN, String,
+    };
+
+    /// The invoice contract whose positions are traded
+    #[allow(clippy::too_many_arguments)]
+    mod adelanta_invoice {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_invoice.wasm"
+        );
+    }
+
+    /// The settlement contract paying out the invoices
+    mod adelanta_settlement {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_settlement.wasm"
+        );
+    }
+    use adelanta_invoice::{Client as AdelantaInvoiceContractClient, FeeModel, Role};
+
+    struct TestContext {
+        env: Env,
+        client: AdelantaMarketplaceContractClient<'static>,
+        invoices: AdelantaInvoiceContractClient<'static>,
+        settlement: adelanta_settlement::Client<'static>,
+        admin: Address,
+        usdc: TokenClient<'static>,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    /// Marketplace over an invoice contract (90% advance, 0.5% protocol
+    /// fee) at timestamp 1000, with 10,000 USDC minted to the admin for
+    /// settlements
+    fn setup() -> TestContext {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+
+        let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc = TokenClient::new(&env, &usdc_address);
+        let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let invoice_id = env.register_contract_wasm(None, adelanta_invoice::WASM);
+        let invoices = AdelantaInvoiceContractClient::new(&env, &invoice_id);
+        invoices.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let settlement_id = env.register_contract_wasm(None, adelanta_settlement::WASM);
+        let settlement = adelanta_settlement::Client::new(&env, &settlement_id);
+        settlement.initialize(&invoice_id);
+        invoices.grant_role(&admin, &Role::Settlement, &settlement_id);
+
+        let contract_id = env.register_contract(None, AdelantaMarketplaceContract);
+        let client = AdelantaMarketplaceContractClient::new(&env, &contract_id);
+        client.initialize(&invoice_id);
+        invoices.grant_role(&admin, &Role::Marketplace, &contract_id);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            client,
+            invoices,
+            settlement,
+            admin,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Generate a lender holding 10,000 USDC
+    fn funded_lender(ctx: &TestContext) -> Address {
+        let lender = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&lender, &10000_0000000);
+        lender
+    }
+
+    /// Create a 2% fee invoice due at timestamp 2000, with a fingerprint
+    /// unique to the invoice
+    fn create_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        let fingerprint = BytesN::from_array(&ctx.env, &[ctx.invoices.get_stats().total_invoices as u8; 32]);
+        ctx.invoices.create_invoice(
+            &Address::generate(&ctx.env),
+            &String::from_str(&ctx.env, "CORP-001"),
+            &fingerprint,
+            &ctx.usdc.address,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    #[test]
+    fn test_list_and_buy_position() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+
+        // Positions cannot be listed before the invoice is funded
+        ctx.invoices.fund_invoice_partial(&lender_a, &invoice_id, &600_0000000);
+        assert_eq!(
+            ctx.client.try_list_position(&lender_a, &invoice_id, &610_0000000),
+            Err(Ok(MarketplaceError::NotTradable))
+        );
+        ctx.invoices.fund_invoice(&lender_b, &invoice_id);
+
+        assert_eq!(
+            ctx.client.try_list_position(&buyer, &invoice_id, &305_0000000),
+            Err(Ok(MarketplaceError::PositionNotFound))
+        );
+        assert_eq!(
+            ctx.client.try_list_position(&lender_b, &invoice_id, &0),
+            Err(Ok(MarketplaceError::InvalidAmount))
+        );
+        let listing = ctx.client.list_position(&lender_b, &invoice_id, &305_0000000);
+        assert_eq!(ctx.client.get_listing(&invoice_id, &lender_b), Some(listing));
+
+        assert_eq!(
+            ctx.client.try_buy_position(&lender_b, &invoice_id, &lender_b),
+            Err(Ok(MarketplaceError::InvalidRecipient))
+        );
+        let transfer = ctx.client.buy_position(&buyer, &invoice_id, &lender_b);
+        assert_eq!(transfer.from, lender_b);
+        assert_eq!(transfer.to, buyer);
+        assert_eq!(transfer.amount, 300_0000000);
+        assert_eq!(transfer.price, 305_0000000);
+
+        // Seller paid, listing consumed, lead lender unchanged
+        assert_eq!(ctx.usdc.balance(&lender_b), 10000_0000000 - 300_0000000 + 305_0000000);
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000 - 305_0000000);
+        assert_eq!(ctx.client.get_listing(&invoice_id, &lender_b), None);
+        assert_eq!(
+            ctx.client.try_buy_position(&buyer, &invoice_id, &lender_b),
+            Err(Ok(MarketplaceError::ListingNotFound))
+        );
+        assert_eq!(ctx.invoices.get_invoice(&invoice_id).lender, Some(lender_a.clone()));
+
+        // Settlement pays the buyer the seller's pro rata share
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000 - 305_0000000 + 306_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000 - 600_0000000 + 612_0000000);
+
+        // Settled positions can no longer be listed
+        assert_eq!(
+            ctx.client.try_list_position(&buyer, &invoice_id, &306_0000000),
+            Err(Ok(MarketplaceError::PositionNotFound))
+        );
+    }
+
+    #[test]
+    fn test_listing_lapses_with_direct_transfer() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let holder = Address::generate(&ctx.env);
+        let buyer = funded_lender(&ctx);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        ctx.client.list_position(&lender, &invoice_id, &920_0000000);
+        ctx.invoices.transfer_position(&lender, &holder, &invoice_id);
+
+        assert_eq!(
+            ctx.client.try_buy_position(&buyer, &invoice_id, &lender),
+            Err(Ok(MarketplaceError::PositionNotFound))
+        );
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000);
+
+        // Nor is it revived by the seller taking on a larger position
+        let other = funded_lender(&ctx);
+        let other_id = create_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice_partial(&other, &other_id, &400_0000000);
+        ctx.invoices.fund_invoice(&lender, &other_id);
+        ctx.client.list_position(&lender, &other_id, &510_0000000);
+        ctx.invoices.transfer_position(&other, &lender, &other_id);
+        assert_eq!(ctx.invoices.get_lender_position(&other_id, &lender).unwrap().amount, 900_0000000);
+        assert_eq!(
+            ctx.client.try_buy_position(&buyer, &other_id, &lender),
+            Err(Ok(MarketplaceError::ListingStale))
+        );
+        assert_eq!(ctx.usdc.balance(&buyer), 10000_0000000);
+
+        ctx.client.cancel_listing(&lender, &invoice_id);
+        assert_eq!(ctx.client.get_listing(&invoice_id, &lender), None);
+        assert_eq!(
+            ctx.client.try_cancel_listing(&lender, &invoice_id),
+            Err(Ok(MarketplaceError::ListingNotFound))
+        );
+    }
+
+    #[test]
+    fn test_pause_trade() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        ctx.client.list_position(&lender, &invoice_id, &905_0000000);
+
+        ctx.invoices.pause(&ctx.admin, &adelanta_invoice::Operation::Trade);
+
+        assert_eq!(
+            ctx.client.try_list_position(&lender, &invoice_id, &910_0000000),
+            Err(Ok(MarketplaceError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_buy_position(&buyer, &invoice_id, &lender),
+            Err(Ok(MarketplaceError::Paused))
+        );
+
+        // Sellers can still withdraw their listing
+        ctx.client.cancel_listing(&lender, &invoice_id);
+        assert_eq!(ctx.client.get_listing(&invoice_id, &lender), None);
+    }
+
+    #[test]
+    fn test_trading_needs_marketplace_role() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let buyer = funded_lender(&ctx);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        // A newly deployed marketplace trades nothing until it is granted
+        // the role on the invoice contract
+        let marketplace_id = ctx.env.register_contract(None, AdelantaMarketplaceContract);
+        let marketplace = AdelantaMarketplaceContractClient::new(&ctx.env, &marketplace_id);
+        marketplace.initialize(&ctx.invoices.address);
+        assert_eq!(
+            marketplace.try_list_position(&lender, &invoice_id, &905_0000000),
+            Err(Ok(MarketplaceError::Unauthorized))
+        );
+
+        ctx.invoices.grant_role(&ctx.admin, &Role::Marketplace, &marketplace_id);
+        marketplace.list_position(&lender, &invoice_id, &905_0000000);
+
+        // Revoking the role stops sales of listings already open
+        ctx.invoices.revoke_role(&ctx.admin, &Role::Marketplace, &marketplace_id);
+        assert_eq!(
+            marketplace.try_buy_position(&buyer, &invoice_id, &lender),
+            Err(Ok(MarketplaceError::Unauthorized))
+        );
+
+        ctx.invoices.grant_role(&ctx.admin, &Role::Marketplace, &marketplace_id);
+        let transfer = marketplace.buy_position(&buyer, &invoice_id, &lender);
+        assert_eq!(transfer.to, buyer);
+        assert_eq!(ctx.invoices.get_lender_position(&invoice_id, &buyer).unwrap().amount, 900_0000000);
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let ctx = setup();
+        assert_eq!(
+            ctx.client.try_initialize(&ctx.invoices.address),
+            Err(Ok(MarketplaceError::AlreadyInitialized))
+        );
+        assert_eq!(ctx.client.get_config().invoice_contract, ctx.invoices.address);
+    }
+}
@@ -0,0 +1,30 @@
+//! Custom error types for the Adelanta Marketplace Contract
+
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MarketplaceError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Invalid price (zero or negative)
+    InvalidAmount = 3,
+    /// Trading is paused on the invoice contract
+    Paused = 4,
+    /// Seller holds no open position in the invoice
+    PositionNotFound = 5,
+    /// Invoice is not funded, or already settled, defaulted or disputed
+    NotTradable = 6,
+    /// No listing for this position
+    ListingNotFound = 7,
+    /// Seller cannot buy its own position
+    InvalidRecipient = 8,
+    /// Marketplace has not been granted the `Marketplace` role on the
+    /// invoice contract
+    Unauthorized = 9,
+    /// Seller's position changed since it was listed
+    ListingStale = 10,
+}
@@ -0,0 +1,45 @@
+//! Contract events for the Adelanta Marketplace Contract
+//!
+//! Events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. A sale is published by the invoice contract
+//! as the position transfer, with its price.
+
+use soroban_sdk::{symbol_short, Address, Env, Symbol};
+
+use crate::types::{MarketplaceConfig, PositionListing};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const POSITION_LISTED: Symbol = symbol_short!("pos_list");
+pub const POSITION_DELISTED: Symbol = symbol_short!("pos_dlist");
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    /// Topics: `("init", version, invoice_contract)`
+    pub fn initialized(env: &Env, config: &MarketplaceConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.invoice_contract.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("pos_list", version, invoice_id, seller)`
+    pub fn position_listed(env: &Env, listing: &PositionListing) {
+        env.events().publish(
+            (POSITION_LISTED, EVENTS_VERSION, listing.invoice_id, listing.seller.clone()),
+            listing.clone(),
+        );
+    }
+
+    /// Topics: `("pos_dlist", version, invoice_id, seller)`
+    pub fn position_delisted(env: &Env, invoice_id: u64, seller: &Address) {
+        env.events().publish(
+            (POSITION_DELISTED, EVENTS_VERSION, invoice_id, seller.clone()),
+            env.ledger().timestamp(),
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Client for the Adelanta invoice contract
+//!
+//! Declares only the entry points the marketplace calls. Types come from
+//! `adelanta_types`, shared with the invoice contract.
+
+use soroban_sdk::{contractclient, Address, Env};
+
+pub use adelanta_types::{Invoice, InvoiceStatus, LenderPosition, Operation, PositionTransfer, Role};
+
+/// Subset of the `AdelantaInvoiceContract` interface used by the marketplace
+#[allow(dead_code)]
+#[contractclient(name = "InvoiceClient")]
+pub trait InvoiceContract {
+    /// Invoice details
+    fn get_invoice(env: Env, invoice_id: u64) -> Invoice;
+
+    /// A lender's position in an invoice
+    fn get_lender_position(env: Env, invoice_id: u64, lender: Address) -> Option<LenderPosition>;
+
+    /// Whether an operation is paused
+    fn is_paused(env: Env, operation: Operation) -> bool;
+
+    /// Whether an account holds a role
+    fn has_role(env: Env, role: Role, account: Address) -> bool;
+
+    /// Have `buyer` pay `price` to `seller` for `seller`'s whole position
+    fn trade_position(
+        env: Env,
+        marketplace: Address,
+        seller: Address,
+        buyer: Address,
+        invoice_id: u64,
+        price: i128,
+    ) -> PositionTransfer;
+}
@@ -0,0 +1,27 @@
+//! # Adelanta Marketplace Contract
+//!
+//! A Soroban smart contract running the secondary market for lender
+//! positions of the Adelanta protocol.
+//!
+//! ## Overview
+//! Lenders list their position in a funded invoice at an asking price in
+//! the invoice's asset. A buyer pays the seller through the invoice
+//! contract, which hands the whole position to the buyer, so settlement
+//! then pays the buyer.
+//!
+//! ## Key Features
+//! - Listings per invoice and seller, replaced by listing again
+//! - Purchases settled by the invoice contract, which checks the buyer
+//!   against its compliance registry
+//! - Paused together with the invoice contract's `Trade` operation
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+mod invoice;
+
+pub use contract::AdelantaMarketplaceContract;
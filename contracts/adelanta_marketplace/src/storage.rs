@@ -0,0 +1,67 @@
+//! Storage keys and helpers for the Adelanta Marketplace Contract
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Val};
+
+use crate::types::{MarketplaceConfig, PositionListing};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Marketplace configuration
+    Config,
+    /// Listing by (invoice ID, seller)
+    Listing(u64, Address),
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<MarketplaceConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &MarketplaceConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== LISTINGS ==========
+
+    pub fn get_listing(env: &Env, invoice_id: u64, seller: &Address) -> Option<PositionListing> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Listing(invoice_id, seller.clone()))
+    }
+
+    pub fn set_listing(env: &Env, listing: &PositionListing) {
+        let key = DataKey::Listing(listing.invoice_id, listing.seller.clone());
+        Self::set_persistent(env, &key, listing);
+    }
+
+    pub fn remove_listing(env: &Env, invoice_id: u64, seller: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Listing(invoice_id, seller.clone()));
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
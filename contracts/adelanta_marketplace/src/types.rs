@@ -0,0 +1,29 @@
+//! Data types for the Adelanta Marketplace Contract
+
+use soroban_sdk::{contracttype, Address};
+
+/// Marketplace configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketplaceConfig {
+    /// Invoice contract whose positions are traded; it must grant the
+    /// marketplace its `Marketplace` role
+    pub invoice_contract: Address,
+}
+
+/// A lender position offered for sale
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionListing {
+    /// Invoice the position belongs to
+    pub invoice_id: u64,
+    /// Current holder selling the position
+    pub seller: Address,
+    /// Amount of the advance the position held when listed; the listing
+    /// lapses once the seller's position no longer matches it
+    pub amount: i128,
+    /// Asking price in the invoice asset
+    pub price: i128,
+    /// Listing timestamp
+    pub listed_at: u64,
+}
@@ -0,0 +1,681 @@
+//! Main contract implementation for the Adelanta Payers Contract
+//!
+//! Payer accounts, acknowledgements and disputes are kept here. The invoice
+//! contract applies each acknowledgement and dispute to the invoice when
+//! the payers contract calls it with its `Payers` role. Payers are managed
+//! by the holders of the invoice contract's `Admin` role, and disputes are
+//! resolved by its `Arbiter` role.
+
+use soroban_sdk::{contract, contractimpl, Address, Env, String};
+
+use crate::errors::PayersError;
+use crate::events::Events;
+use crate::invoice::{DisputeResolution, Invoice, InvoiceClient, InvoiceStatus, Operation, Role, INVALID_AMOUNT};
+use crate::storage::Storage;
+use crate::types::{Acknowledgement, Dispute, DisputeReason, DisputeState, PayersConfig};
+
+#[contract]
+pub struct AdelantaPayersContract;
+
+#[contractimpl]
+impl AdelantaPayersContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the payers contract
+    ///
+    /// # Arguments
+    /// * `invoice_contract` - Invoice contract whose invoices are
+    ///   acknowledged and disputed
+    pub fn initialize(env: Env, invoice_contract: Address) -> Result<(), PayersError> {
+        if Storage::has_config(&env) {
+            return Err(PayersError::AlreadyInitialized);
+        }
+
+        let config = PayersConfig { invoice_contract };
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // PAYERS
+    // ============================================================
+
+    /// Register the account of a corporate payer (admin only)
+    pub fn register_payer(
+        env: Env,
+        admin: Address,
+        payer_id: String,
+        account: Address,
+    ) -> Result<(), PayersError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        Storage::set_payer_account(&env, &payer_id, &account);
+        Storage::extend_instance_ttl(&env);
+
+        Events::payer_registered(&env, &payer_id, &account);
+
+        Ok(())
+    }
+
+    /// Allow or disallow an attester to acknowledge invoices on behalf of a
+    /// registered payer (admin only)
+    pub fn set_payer_attester(
+        env: Env,
+        admin: Address,
+        payer_id: String,
+        attester: Address,
+        authorized: bool,
+    ) -> Result<(), PayersError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if Storage::get_payer_account(&env, &payer_id).is_none() {
+            return Err(PayersError::PayerNotRegistered);
+        }
+        Storage::set_payer_attester(&env, &payer_id, &attester, authorized);
+
+        Events::attester_set(&env, &payer_id, &attester, authorized);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // ACKNOWLEDGEMENT
+    // ============================================================
+
+    /// Confirm an invoice's amount and due date (payer or its attester)
+    ///
+    /// The invoice becomes `Acknowledged`. If funding has not started, the
+    /// invoice contract raises its advance to `acknowledged_advance_bps`
+    /// when that is above the invoice's rate, but never above the pricing
+    /// grid's advance for the invoice's risk.
+    ///
+    /// # Arguments
+    /// * `signer` - Registered payer account, or one of its attesters
+    /// * `invoice_id` - Invoice to acknowledge
+    /// * `amount` - Invoice amount, or face value of a fiat-denominated invoice
+    /// * `due_date` - Invoice due date
+    pub fn acknowledge_invoice(
+        env: Env,
+        signer: Address,
+        invoice_id: u64,
+        amount: i128,
+        due_date: u64,
+    ) -> Result<Acknowledgement, PayersError> {
+        signer.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+
+        match invoice.status {
+            InvoiceStatus::Created => {}
+            InvoiceStatus::Acknowledged => return Err(PayersError::AlreadyAcknowledged),
+            InvoiceStatus::Disputed => return Err(PayersError::InvoiceDisputed),
+            _ => return Err(PayersError::InvoiceAlreadyFunded),
+        }
+
+        let payer = Storage::get_payer_account(&env, &invoice.payer_id)
+            .ok_or(PayersError::PayerNotRegistered)?;
+        if signer != payer && !Storage::is_payer_attester(&env, &invoice.payer_id, &signer) {
+            return Err(PayersError::Unauthorized);
+        }
+
+        // Fiat-denominated invoices are confirmed at their face value
+        let expected_amount = match invoice.currency {
+            Some(_) => invoice.face_value,
+            None => invoice.amount,
+        };
+        if amount != expected_amount || due_date != invoice.due_date {
+            return Err(PayersError::AcknowledgementMismatch);
+        }
+
+        invoices.record_acknowledgement(&env.current_contract_address(), &invoice_id);
+
+        let acknowledgement = Acknowledgement {
+            invoice_id,
+            signer,
+            amount,
+            due_date,
+            acknowledged_at: env.ledger().timestamp(),
+        };
+        Storage::set_acknowledgement(&env, &acknowledgement);
+        Storage::extend_instance_ttl(&env);
+
+        Events::invoice_acknowledged(&env, &acknowledgement);
+
+        Ok(acknowledgement)
+    }
+
+    // ============================================================
+    // DISPUTES
+    // ============================================================
+
+    /// Dispute an invoice (payer or its attester, a lender in the invoice,
+    /// or admin)
+    ///
+    /// The invoice is `Disputed` until an arbiter resolves it. Meanwhile it
+    /// cannot be funded, settled, traded, cancelled or marked defaulted,
+    /// and payer deposits are escrowed. The dispute counts in the payer's
+    /// reputation.
+    ///
+    /// # Arguments
+    /// * `caller` - Payer account or attester, contributing lender, or admin
+    /// * `invoice_id` - Invoice to dispute (unsettled and not defaulted)
+    /// * `reason` - Grounds of the dispute
+    pub fn raise_dispute(
+        env: Env,
+        caller: Address,
+        invoice_id: u64,
+        reason: DisputeReason,
+    ) -> Result<Dispute, PayersError> {
+        caller.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        Self::require_not_paused(&invoices)?;
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+
+        match invoice.status {
+            InvoiceStatus::Created
+            | InvoiceStatus::Acknowledged
+            | InvoiceStatus::Funded
+            | InvoiceStatus::PartiallySettled => {}
+            InvoiceStatus::Disputed => return Err(PayersError::InvoiceDisputed),
+            InvoiceStatus::Settled => return Err(PayersError::InvoiceAlreadySettled),
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => {
+                return Err(PayersError::InvoiceDefaulted)
+            }
+            InvoiceStatus::Cancelled => return Err(PayersError::InvoiceCancelled),
+        }
+
+        let is_payer = Storage::get_payer_account(&env, &invoice.payer_id) == Some(caller.clone())
+            || Storage::is_payer_attester(&env, &invoice.payer_id, &caller);
+        let is_lender = invoices
+            .get_invoice_lenders(&invoice_id)
+            .iter()
+            .any(|contribution| contribution.lender == caller);
+        if !is_payer && !is_lender && !invoices.has_role(&Role::Admin, &caller) {
+            return Err(PayersError::Unauthorized);
+        }
+
+        invoices.open_dispute(&env.current_contract_address(), &invoice_id);
+
+        let dispute = Dispute {
+            invoice_id,
+            raised_by: caller,
+            reason,
+            raised_at: env.ledger().timestamp(),
+            previous_status: invoice.status,
+            state: DisputeState::Open,
+            resolved_by: None,
+            resolved_at: None,
+        };
+        Storage::set_dispute(&env, &dispute);
+        Storage::extend_instance_ttl(&env);
+
+        Events::invoice_disputed(&env, &dispute);
+
+        Ok(dispute)
+    }
+
+    /// Resolve a disputed invoice (arbiter only)
+    ///
+    /// - `Valid`: the invoice returns to its status before the dispute.
+    /// - `Adjusted(amount)`: likewise, with `amount` now due from the payer.
+    ///   It must be below the invoice amount, above what was already
+    ///   received and, once funded, cover the lenders' advance and the fee
+    ///   accrued so far. Settlement runs through the usual waterfall, so the cut
+    ///   comes out of the SME's remainder first. An unfunded invoice has
+    ///   its advance scaled down with the amount, which must stay above
+    ///   what lenders already contributed.
+    /// - `Invalid`: an unfunded invoice is cancelled and contributions are
+    ///   refunded to its lenders. A funded invoice defaults against the
+    ///   SME's reputation only, and the lenders' recourse against the SME
+    ///   is collected through the settlement contract's `recover_invoice`.
+    pub fn resolve_dispute(
+        env: Env,
+        arbiter: Address,
+        invoice_id: u64,
+        resolution: DisputeResolution,
+    ) -> Result<Dispute, PayersError> {
+        arbiter.require_auth();
+
+        let invoices = Self::require_role(&env, Role::Arbiter, &arbiter)?;
+        Self::require_not_paused(&invoices)?;
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+        if invoice.status != InvoiceStatus::Disputed {
+            return Err(PayersError::InvoiceNotDisputed);
+        }
+        let mut dispute = Storage::get_dispute(&env, invoice_id).ok_or(PayersError::InvoiceNotDisputed)?;
+
+        // The invoice contract is the one to judge an adjusted amount
+        match invoices.try_close_dispute(&env.current_contract_address(), &arbiter, &invoice_id, &resolution) {
+            Ok(Ok(_)) => {}
+            Err(Ok(error)) if error == soroban_sdk::Error::from_contract_error(INVALID_AMOUNT) => {
+                return Err(PayersError::InvalidAmount);
+            }
+            Err(Ok(error)) => env.panic_with_error(error),
+            _ => panic!("invoice contract failed to close the dispute"),
+        }
+
+        dispute.state = DisputeState::Resolved(resolution);
+        dispute.resolved_by = Some(arbiter.clone());
+        dispute.resolved_at = Some(env.ledger().timestamp());
+        Storage::set_dispute(&env, &dispute);
+
+        Events::dispute_resolved(&env, &dispute, &arbiter);
+
+        Ok(dispute)
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get the payer acknowledgement of an invoice
+    pub fn get_acknowledgement(env: Env, invoice_id: u64) -> Option<Acknowledgement> {
+        Storage::get_acknowledgement(&env, invoice_id)
+    }
+
+    /// Get the account registered for a corporate payer ID
+    pub fn get_payer_account(env: Env, payer_id: String) -> Option<Address> {
+        Storage::get_payer_account(&env, &payer_id)
+    }
+
+    /// Get the latest dispute raised against an invoice
+    pub fn get_dispute(env: Env, invoice_id: u64) -> Option<Dispute> {
+        Storage::get_dispute(&env, invoice_id)
+    }
+
+    /// Get the payers contract configuration
+    pub fn get_config(env: Env) -> Result<PayersConfig, PayersError> {
+        Storage::get_config(&env).ok_or(PayersError::NotInitialized)
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    fn invoice_client(env: &Env) -> Result<InvoiceClient<'_>, PayersError> {
+        let config = Storage::get_config(env).ok_or(PayersError::NotInitialized)?;
+        Ok(InvoiceClient::new(env, &config.invoice_contract))
+    }
+
+    /// Require `account` to hold `role` on the invoice contract
+    fn require_role<'a>(env: &'a Env, role: Role, account: &Address) -> Result<InvoiceClient<'a>, PayersError> {
+        let invoices = Self::invoice_client(env)?;
+        if !invoices.has_role(&role, account) {
+            return Err(PayersError::Unauthorized);
+        }
+        Ok(invoices)
+    }
+
+    /// Disputes are held while the invoice contract's settlement is paused
+    fn require_not_paused(invoices: &InvoiceClient) -> Result<(), PayersError> {
+        if invoices.is_paused(&Operation::Settle) {
+            return Err(PayersError::Paused);
+        }
+        Ok(())
+    }
+
+    fn get_invoice(invoices: &InvoiceClient, invoice_id: u64) -> Result<Invoice, PayersError> {
+        match invoices.try_get_invoice(&invoice_id) {
+            Ok(Ok(invoice)) => Ok(invoice),
+            _ => Err(PayersError::InvoiceNotFound),
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::EVENTS_VERSION;
+    use soroban_sdk::{
+        symbol_short,
+        testutils::{Address as _, Events as _, Ledger},
+        token::StellarAssetClient,
+        vec, BytesN, IntoVal, Val, Vec,
+    };
+
+    /// The invoice contract whose invoices are acknowledged and disputed
+    #[allow(clippy::too_many_arguments)]
+    mod adelanta_invoice {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_invoice.wasm"
+        );
+    }
+    use adelanta_invoice::{
+        AdelantaError, Client as AdelantaInvoiceContractClient, FeeModel, InvoiceStatus as Status,
+        Operation as InvoiceOperation, Role as InvoiceRole,
+    };
+
+    struct TestContext {
+        env: Env,
+        contract_id: Address,
+        client: AdelantaPayersContractClient<'static>,
+        invoices: AdelantaInvoiceContractClient<'static>,
+        admin: Address,
+        sme: Address,
+        payer: Address,
+        arbiter: Address,
+        usdc: Address,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    /// Payers contract of an invoice contract (90% advance, 0.5% protocol
+    /// fee) at timestamp 1000, with an arbiter
+    fn setup() -> TestContext {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+        let payer = Address::generate(&env);
+        let arbiter = Address::generate(&env);
+
+        let usdc = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc_admin = StellarAssetClient::new(&env, &usdc);
+
+        let invoice_id = env.register_contract_wasm(None, adelanta_invoice::WASM);
+        let invoices = AdelantaInvoiceContractClient::new(&env, &invoice_id);
+        invoices.initialize(&admin, &usdc, &treasury, &9000, &50);
+        invoices.grant_role(&admin, &InvoiceRole::Arbiter, &arbiter);
+
+        let contract_id = env.register_contract(None, AdelantaPayersContract);
+        let client = AdelantaPayersContractClient::new(&env, &contract_id);
+        client.initialize(&invoice_id);
+        invoices.grant_role(&admin, &InvoiceRole::Payers, &contract_id);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            contract_id,
+            client,
+            invoices,
+            admin,
+            sme,
+            payer,
+            arbiter,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Generate a lender holding 10,000 USDC
+    fn funded_lender(ctx: &TestContext) -> Address {
+        let lender = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&lender, &10000_0000000);
+        lender
+    }
+
+    /// Create a 2% fee invoice owed by "CORP-001" to the context's SME, due
+    /// at timestamp 2000
+    fn create_test_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        let fingerprint = [ctx.invoices.get_stats().total_invoices as u8; 32];
+        ctx.invoices.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &BytesN::from_array(&ctx.env, &fingerprint),
+            &ctx.usdc,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    /// Last event published in the environment, wrapped for comparison
+    fn last_event(env: &Env) -> Vec<(Address, Vec<Val>, Val)> {
+        vec![env, env.events().all().last().unwrap()]
+    }
+
+    #[test]
+    fn test_acknowledgement_required_for_funding() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payer_id = String::from_str(&ctx.env, "CORP-001");
+
+        ctx.invoices.update_config(&ctx.admin, &None, &None, &None, &None, &Some(true), &Some(9500));
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&ctx.payer, &invoice_id, &1000_0000000, &2000),
+            Err(Ok(PayersError::PayerNotRegistered))
+        );
+
+        assert_eq!(
+            ctx.client.try_register_payer(&ctx.sme, &payer_id, &ctx.payer),
+            Err(Ok(PayersError::Unauthorized))
+        );
+        ctx.client.register_payer(&ctx.admin, &payer_id, &ctx.payer);
+        assert_eq!(ctx.client.get_payer_account(&payer_id), Some(ctx.payer.clone()));
+
+        // Only the payer can confirm, and only the invoice's own terms
+        let stranger = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&stranger, &invoice_id, &1000_0000000, &2000),
+            Err(Ok(PayersError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&ctx.payer, &invoice_id, &900_0000000, &2000),
+            Err(Ok(PayersError::AcknowledgementMismatch))
+        );
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&ctx.payer, &invoice_id, &1000_0000000, &3000),
+            Err(Ok(PayersError::AcknowledgementMismatch))
+        );
+
+        let acknowledgement = ctx.client.acknowledge_invoice(&ctx.payer, &invoice_id, &1000_0000000, &2000);
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("acked"), EVENTS_VERSION, invoice_id, ctx.payer.clone()).into_val(&ctx.env),
+                    acknowledgement.into_val(&ctx.env),
+                ),
+            ]
+        );
+        assert_eq!(ctx.client.get_acknowledgement(&invoice_id), Some(acknowledgement));
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&ctx.payer, &invoice_id, &1000_0000000, &2000),
+            Err(Ok(PayersError::AlreadyAcknowledged))
+        );
+
+        // The confirmed invoice is advanced at 95% instead of 90%
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, Status::Acknowledged);
+        assert_eq!(invoice.advance_amount, 950_0000000);
+
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&ctx.payer, &invoice_id, &1000_0000000, &2000),
+            Err(Ok(PayersError::InvoiceAlreadyFunded))
+        );
+
+        // Acknowledgements are only recorded through the payers contract
+        assert_eq!(
+            ctx.invoices.try_record_acknowledgement(&stranger, &invoice_id).err(),
+            Some(Ok(AdelantaError::Unauthorized))
+        );
+    }
+
+    #[test]
+    fn test_attester_acknowledges_for_payer() {
+        let ctx = setup();
+        let attester = Address::generate(&ctx.env);
+        let payer_id = String::from_str(&ctx.env, "CORP-001");
+
+        assert_eq!(
+            ctx.client.try_set_payer_attester(&ctx.admin, &payer_id, &attester, &true),
+            Err(Ok(PayersError::PayerNotRegistered))
+        );
+        ctx.client.register_payer(&ctx.admin, &payer_id, &ctx.payer);
+        ctx.client.set_payer_attester(&ctx.admin, &payer_id, &attester, &true);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        let acknowledgement = ctx.client.acknowledge_invoice(&attester, &invoice_id, &1000_0000000, &2000);
+        assert_eq!(acknowledgement.signer, attester);
+        assert_eq!(ctx.invoices.get_invoice(&invoice_id).status, Status::Acknowledged);
+
+        let revoked_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.client.set_payer_attester(&ctx.admin, &payer_id, &attester, &false);
+        assert_eq!(
+            ctx.client.try_acknowledge_invoice(&attester, &revoked_id, &1000_0000000, &2000),
+            Err(Ok(PayersError::Unauthorized))
+        );
+        ctx.client.acknowledge_invoice(&ctx.payer, &revoked_id, &1000_0000000, &2000);
+    }
+
+    #[test]
+    fn test_dispute_adjusts_invoice_amount() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        ctx.client.register_payer(&ctx.admin, &String::from_str(&ctx.env, "CORP-001"), &ctx.payer);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        assert_eq!(
+            ctx.client.try_raise_dispute(&ctx.sme, &invoice_id, &DisputeReason::GoodsReturned),
+            Err(Ok(PayersError::Unauthorized))
+        );
+        let dispute = ctx.client.raise_dispute(&ctx.payer, &invoice_id, &DisputeReason::GoodsReturned);
+        assert_eq!(dispute.previous_status, InvoiceStatus::Funded);
+        assert_eq!(dispute.state, DisputeState::Open);
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("disputed"), EVENTS_VERSION, invoice_id, ctx.payer.clone()).into_val(&ctx.env),
+                    dispute.into_val(&ctx.env),
+                ),
+            ]
+        );
+        assert_eq!(ctx.invoices.get_invoice(&invoice_id).status, Status::Disputed);
+        assert_eq!(
+            ctx.client.try_raise_dispute(&lender, &invoice_id, &DisputeReason::Other),
+            Err(Ok(PayersError::InvoiceDisputed))
+        );
+
+        assert_eq!(
+            ctx.client.try_resolve_dispute(&ctx.payer, &invoice_id, &DisputeResolution::Valid),
+            Err(Ok(PayersError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_resolve_dispute(&ctx.arbiter, &invoice_id, &DisputeResolution::Adjusted(1000_0000000)),
+            Err(Ok(PayersError::InvalidAmount))
+        );
+
+        // The payer owes 950 after the returns
+        let dispute = ctx.client.resolve_dispute(&ctx.arbiter, &invoice_id, &DisputeResolution::Adjusted(950_0000000));
+        assert_eq!(dispute.resolved_by, Some(ctx.arbiter.clone()));
+        assert_eq!(dispute.resolved_at, Some(1000));
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("resolved"), EVENTS_VERSION, invoice_id, ctx.arbiter.clone()).into_val(&ctx.env),
+                    dispute.into_val(&ctx.env),
+                ),
+            ]
+        );
+        assert_eq!(ctx.client.get_dispute(&invoice_id), Some(dispute));
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, Status::Funded);
+        assert_eq!(invoice.amount, 950_0000000);
+
+        assert_eq!(
+            ctx.client.try_resolve_dispute(&ctx.arbiter, &invoice_id, &DisputeResolution::Valid),
+            Err(Ok(PayersError::InvoiceNotDisputed))
+        );
+    }
+
+    #[test]
+    fn test_pause_settle_holds_disputes() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        ctx.client.register_payer(&ctx.admin, &String::from_str(&ctx.env, "CORP-001"), &ctx.payer);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        ctx.invoices.pause(&ctx.admin, &InvoiceOperation::Settle);
+        assert_eq!(
+            ctx.client.try_raise_dispute(&ctx.payer, &invoice_id, &DisputeReason::GoodsReturned),
+            Err(Ok(PayersError::Paused))
+        );
+
+        ctx.invoices.unpause(&ctx.admin, &InvoiceOperation::Settle);
+        ctx.client.raise_dispute(&ctx.payer, &invoice_id, &DisputeReason::GoodsReturned);
+
+        ctx.invoices.pause(&ctx.admin, &InvoiceOperation::Settle);
+        assert_eq!(
+            ctx.client.try_resolve_dispute(&ctx.arbiter, &invoice_id, &DisputeResolution::Valid),
+            Err(Ok(PayersError::Paused))
+        );
+
+        ctx.invoices.unpause(&ctx.admin, &InvoiceOperation::Settle);
+        ctx.client.resolve_dispute(&ctx.arbiter, &invoice_id, &DisputeResolution::Valid);
+        assert_eq!(ctx.invoices.get_invoice(&invoice_id).status, Status::Funded);
+    }
+
+    #[test]
+    fn test_dispute_invalidation_cancels_or_defaults() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        // Invalidating an unfunded invoice cancels it and refunds its lenders
+        let unfunded_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice_partial(&lender, &unfunded_id, &400_0000000);
+        ctx.client.raise_dispute(&lender, &unfunded_id, &DisputeReason::ShortDelivery);
+        ctx.client.resolve_dispute(&ctx.arbiter, &unfunded_id, &DisputeResolution::Invalid);
+        assert_eq!(ctx.invoices.get_invoice(&unfunded_id).status, Status::Cancelled);
+        assert_eq!(
+            ctx.client.try_raise_dispute(&lender, &unfunded_id, &DisputeReason::Other),
+            Err(Ok(PayersError::InvoiceCancelled))
+        );
+
+        // An invoice upheld as valid carries on as before
+        let funded_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &funded_id);
+        ctx.client.raise_dispute(&ctx.admin, &funded_id, &DisputeReason::CreditNote);
+        ctx.client.resolve_dispute(&ctx.arbiter, &funded_id, &DisputeResolution::Valid);
+        assert_eq!(ctx.invoices.get_invoice(&funded_id).status, Status::Funded);
+
+        // Invalidating a funded invoice defaults it
+        ctx.client.raise_dispute(&ctx.admin, &funded_id, &DisputeReason::CreditNote);
+        let dispute = ctx.client.resolve_dispute(&ctx.arbiter, &funded_id, &DisputeResolution::Invalid);
+        assert_eq!(dispute.state, DisputeState::Resolved(DisputeResolution::Invalid));
+        assert_eq!(ctx.invoices.get_invoice(&funded_id).status, Status::Defaulted);
+        assert_eq!(
+            ctx.client.try_raise_dispute(&ctx.admin, &funded_id, &DisputeReason::Other),
+            Err(Ok(PayersError::InvoiceDefaulted))
+        );
+    }
+}
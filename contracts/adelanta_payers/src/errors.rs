@@ -0,0 +1,39 @@
+//! Custom error types for the Adelanta Payers Contract
+
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum PayersError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Caller lacks the required role on the invoice contract
+    Unauthorized = 3,
+    /// Invoice not found
+    InvoiceNotFound = 4,
+    /// No payer account is registered for the payer ID
+    PayerNotRegistered = 5,
+    /// Acknowledged amount or due date does not match the invoice
+    AcknowledgementMismatch = 6,
+    /// Invoice is already acknowledged
+    AlreadyAcknowledged = 7,
+    /// Invoice already funded
+    InvoiceAlreadyFunded = 8,
+    /// Invoice already settled
+    InvoiceAlreadySettled = 9,
+    /// Invoice is defaulted or written off
+    InvoiceDefaulted = 10,
+    /// Invoice is cancelled
+    InvoiceCancelled = 11,
+    /// Invoice is disputed and waiting for an arbiter
+    InvoiceDisputed = 12,
+    /// Invoice has no open dispute
+    InvoiceNotDisputed = 13,
+    /// Invalid amount (zero or negative)
+    InvalidAmount = 14,
+    /// Disputes are paused with the invoice contract's settlement
+    Paused = 16,
+}
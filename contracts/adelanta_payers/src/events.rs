@@ -0,0 +1,83 @@
+//! Contract events for the Adelanta Payers Contract
+//!
+//! Events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. The invoice contract publishes no event of
+//! its own for acknowledgements and disputes, except the `cancelled` or
+//! `defaulted` event of an invalidated invoice.
+
+use soroban_sdk::{symbol_short, Address, Env, String, Symbol};
+
+use crate::types::{Acknowledgement, Dispute, PayersConfig};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const PAYER_REGISTERED: Symbol = symbol_short!("payer_reg");
+pub const ATTESTER_SET: Symbol = symbol_short!("attester");
+pub const INVOICE_ACKNOWLEDGED: Symbol = symbol_short!("acked");
+pub const INVOICE_DISPUTED: Symbol = symbol_short!("disputed");
+pub const DISPUTE_RESOLVED: Symbol = symbol_short!("resolved");
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    /// Topics: `("init", version, invoice_contract)`
+    pub fn initialized(env: &Env, config: &PayersConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.invoice_contract.clone()),
+            config.clone(),
+        );
+    }
+
+    // ========== PAYERS ==========
+
+    /// Topics: `("payer_reg", version, payer_id)`; payload is the payer account
+    pub fn payer_registered(env: &Env, payer_id: &String, account: &Address) {
+        env.events().publish(
+            (PAYER_REGISTERED, EVENTS_VERSION, payer_id.clone()),
+            account.clone(),
+        );
+    }
+
+    /// Topics: `("attester", version, payer_id, attester)`; payload is whether
+    /// the attester is authorized
+    pub fn attester_set(env: &Env, payer_id: &String, attester: &Address, authorized: bool) {
+        env.events().publish(
+            (ATTESTER_SET, EVENTS_VERSION, payer_id.clone(), attester.clone()),
+            authorized,
+        );
+    }
+
+    /// Topics: `("acked", version, invoice_id, signer)`
+    pub fn invoice_acknowledged(env: &Env, acknowledgement: &Acknowledgement) {
+        env.events().publish(
+            (
+                INVOICE_ACKNOWLEDGED,
+                EVENTS_VERSION,
+                acknowledgement.invoice_id,
+                acknowledgement.signer.clone(),
+            ),
+            acknowledgement.clone(),
+        );
+    }
+
+    // ========== DISPUTES ==========
+
+    /// Topics: `("disputed", version, invoice_id, raised_by)`
+    pub fn invoice_disputed(env: &Env, dispute: &Dispute) {
+        env.events().publish(
+            (INVOICE_DISPUTED, EVENTS_VERSION, dispute.invoice_id, dispute.raised_by.clone()),
+            dispute.clone(),
+        );
+    }
+
+    /// Topics: `("resolved", version, invoice_id, arbiter)`
+    pub fn dispute_resolved(env: &Env, dispute: &Dispute, arbiter: &Address) {
+        env.events().publish(
+            (DISPUTE_RESOLVED, EVENTS_VERSION, dispute.invoice_id, arbiter.clone()),
+            dispute.clone(),
+        );
+    }
+}
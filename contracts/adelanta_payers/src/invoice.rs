@@ -0,0 +1,46 @@
+//! Client for the Adelanta invoice contract
+//!
+//! Declares only the entry points the payers contract calls. Types come
+//! from `adelanta_types`, shared with the invoice contract.
+
+use soroban_sdk::{contractclient, Address, Env, Vec};
+
+/// Code of the invoice contract's `InvalidAmount` error
+pub const INVALID_AMOUNT: u32 = 9;
+
+pub use adelanta_types::{DisputeResolution, Invoice, InvoiceStatus, LenderContribution, Operation, Role};
+
+/// Subset of the `AdelantaInvoiceContract` interface used by the payers
+/// contract
+#[allow(dead_code)]
+#[contractclient(name = "InvoiceClient")]
+pub trait InvoiceContract {
+    /// Invoice details
+    fn get_invoice(env: Env, invoice_id: u64) -> Invoice;
+
+    /// Lenders that funded an invoice and their contributions
+    fn get_invoice_lenders(env: Env, invoice_id: u64) -> Vec<LenderContribution>;
+
+    /// Whether `account` holds `role`
+    fn has_role(env: Env, role: Role, account: Address) -> bool;
+
+    /// Whether an operation is paused
+    fn is_paused(env: Env, operation: Operation) -> bool;
+
+    /// Mark an invoice acknowledged by its payer, returning the invoice as
+    /// updated
+    fn record_acknowledgement(env: Env, payers: Address, invoice_id: u64) -> Invoice;
+
+    /// Mark an invoice disputed, returning the invoice as updated
+    fn open_dispute(env: Env, payers: Address, invoice_id: u64) -> Invoice;
+
+    /// Apply an arbiter's decision on a disputed invoice, returning the
+    /// invoice as updated
+    fn close_dispute(
+        env: Env,
+        payers: Address,
+        arbiter: Address,
+        invoice_id: u64,
+        resolution: DisputeResolution,
+    ) -> Invoice;
+}
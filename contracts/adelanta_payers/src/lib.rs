@@ -0,0 +1,27 @@
+//! # Adelanta Payers Contract
+//!
+//! A Soroban smart contract for the corporate payers owing invoices of the
+//! Adelanta protocol.
+//!
+//! ## Overview
+//! The admin registers the account of each corporate payer ID and the
+//! attesters allowed to sign on its behalf. Payers confirm invoices before
+//! funding, and payers, lenders or the admin dispute them until an arbiter
+//! decides. The invoice contract applies each acknowledgement and dispute
+//! to the invoice.
+//!
+//! ## Key Features
+//! - Payer account registry with delegated attesters
+//! - Payer acknowledgement of an invoice's amount and due date
+//! - Disputes with reason codes, resolved by an arbiter
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+mod invoice;
+
+pub use contract::AdelantaPayersContract;
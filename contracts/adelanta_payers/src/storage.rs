@@ -0,0 +1,106 @@
+//! Storage keys and helpers for the Adelanta Payers Contract
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, String, Val};
+
+use crate::types::{Acknowledgement, Dispute, PayersConfig};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Payers contract configuration
+    Config,
+    /// Account registered for a corporate payer ID
+    PayerAccount(String),
+    /// Attester allowed to acknowledge invoices for a payer ID
+    PayerAttester(String, Address),
+    /// Payer acknowledgement of an invoice
+    Acknowledgement(u64),
+    /// Latest dispute raised against an invoice
+    Dispute(u64),
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<PayersConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &PayersConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== PAYERS ==========
+
+    pub fn get_payer_account(env: &Env, payer_id: &String) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PayerAccount(payer_id.clone()))
+    }
+
+    pub fn set_payer_account(env: &Env, payer_id: &String, account: &Address) {
+        Self::set_persistent(env, &DataKey::PayerAccount(payer_id.clone()), account);
+    }
+
+    pub fn is_payer_attester(env: &Env, payer_id: &String, attester: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::PayerAttester(payer_id.clone(), attester.clone()))
+    }
+
+    pub fn set_payer_attester(env: &Env, payer_id: &String, attester: &Address, authorized: bool) {
+        let key = DataKey::PayerAttester(payer_id.clone(), attester.clone());
+        if authorized {
+            Self::set_persistent(env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    pub fn get_acknowledgement(env: &Env, invoice_id: u64) -> Option<Acknowledgement> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Acknowledgement(invoice_id))
+    }
+
+    pub fn set_acknowledgement(env: &Env, acknowledgement: &Acknowledgement) {
+        Self::set_persistent(
+            env,
+            &DataKey::Acknowledgement(acknowledgement.invoice_id),
+            acknowledgement,
+        );
+    }
+
+    // ========== DISPUTES ==========
+
+    pub fn get_dispute(env: &Env, invoice_id: u64) -> Option<Dispute> {
+        env.storage().persistent().get(&DataKey::Dispute(invoice_id))
+    }
+
+    pub fn set_dispute(env: &Env, dispute: &Dispute) {
+        Self::set_persistent(env, &DataKey::Dispute(dispute.invoice_id), dispute);
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    /// Write a persistent record, keeping it alive for the maximum TTL
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
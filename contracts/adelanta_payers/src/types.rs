@@ -0,0 +1,79 @@
+//! Data types for the Adelanta Payers Contract
+
+use soroban_sdk::{contracttype, Address};
+
+use crate::invoice::{DisputeResolution, InvoiceStatus};
+
+/// Payers contract configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayersConfig {
+    /// Invoice contract whose invoices are acknowledged and disputed; it
+    /// must grant the payers contract its `Payers` role, and its roles
+    /// manage payers and resolve disputes
+    pub invoice_contract: Address,
+}
+
+/// A payer's confirmation of an invoice's amount and due date
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Acknowledgement {
+    /// Invoice ID
+    pub invoice_id: u64,
+    /// Registered payer account, or an attester signing on its behalf
+    pub signer: Address,
+    /// Confirmed invoice amount
+    pub amount: i128,
+    /// Confirmed due date
+    pub due_date: u64,
+    /// Acknowledgement timestamp
+    pub acknowledged_at: u64,
+}
+
+/// Grounds on which an invoice is disputed
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DisputeReason {
+    /// Goods were returned to the SME
+    GoodsReturned,
+    /// Fewer goods or services were delivered than invoiced
+    ShortDelivery,
+    /// The SME issued a credit note against the invoice
+    CreditNote,
+    /// Goods or services did not meet the agreed quality
+    Quality,
+    /// Any other ground
+    Other,
+}
+
+/// Progress of a dispute
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DisputeState {
+    /// Waiting for an arbiter
+    Open,
+    /// Closed with the arbiter's decision
+    Resolved(DisputeResolution),
+}
+
+/// Dispute raised against an invoice
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dispute {
+    /// Disputed invoice
+    pub invoice_id: u64,
+    /// Payer, lender or admin who raised the dispute
+    pub raised_by: Address,
+    /// Grounds of the dispute
+    pub reason: DisputeReason,
+    /// Timestamp the dispute was raised
+    pub raised_at: u64,
+    /// Invoice status before the dispute, restored unless it is invalidated
+    pub previous_status: InvoiceStatus,
+    /// Whether the dispute is open, or the arbiter's decision
+    pub state: DisputeState,
+    /// Arbiter who resolved the dispute
+    pub resolved_by: Option<Address>,
+    /// Resolution timestamp
+    pub resolved_at: Option<u64>,
+}
@@ -0,0 +1,1336 @@
+//! Main contract implementation for the Adelanta Pool
+//!
+//! The pool keeps its own books rather than reading its token balance:
+//! `cash` is idle USDC and `deployed` is principal out in invoices at cost.
+//! Settlement proceeds reach the pool as plain transfers from the invoice
+//! contract and only count once `collect` books them, so USDC sent to the
+//! pool directly cannot move the share price.
+//!
+//! Capital is split into a senior and a junior tranche, each with its own
+//! book value and shares. `collect` runs the income and losses of every
+//! invoice through the tranche waterfalls.
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractimpl, panic_with_error,
+    token::{self, TokenInterface},
+    vec, Address, Env, IntoVal, String, Symbol,
+};
+
+use crate::errors::PoolError;
+use crate::events::Events;
+use crate::invoice::InvoiceClient;
+use crate::storage::Storage;
+use crate::types::{
+    AllowanceValue, PoolConfig, PoolPosition, PoolState, Tranche, TrancheConfig, TrancheState,
+    Waterfall, WithdrawalRequest, WithdrawalResult,
+};
+use crate::waterfall;
+
+/// Share token decimals, matching USDC
+const SHARE_DECIMALS: u32 = 7;
+
+/// Highest target return a tranche can be given (basis points)
+const MAX_TARGET_APR_BPS: u32 = 10000;
+
+#[contract]
+pub struct AdelantaPoolContract;
+
+#[contractimpl]
+impl AdelantaPoolContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the pool
+    ///
+    /// Both tranches start without a target return or lock-up, and without
+    /// a minimum junior share, until the admin sets their terms.
+    ///
+    /// # Arguments
+    /// * `admin` - Admin address, appoints the manager and sets tranche terms
+    /// * `manager` - Manager address, funds invoices with pool liquidity
+    /// * `asset` - USDC token contract address
+    /// * `invoice_contract` - `AdelantaInvoiceContract` address
+    /// * `name` - Senior share token name
+    /// * `symbol` - Senior share token symbol
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        manager: Address,
+        asset: Address,
+        invoice_contract: Address,
+        name: String,
+        symbol: String,
+    ) -> Result<(), PoolError> {
+        if Storage::has_config(&env) {
+            return Err(PoolError::AlreadyInitialized);
+        }
+
+        let config = PoolConfig {
+            admin,
+            manager,
+            asset,
+            invoice_contract,
+            name,
+            symbol,
+            min_junior_bps: 0,
+        };
+
+        Storage::set_config(&env, &config);
+        for tranche in [Tranche::Senior, Tranche::Junior] {
+            Storage::set_tranche(&env, &Storage::get_tranche(&env, tranche));
+        }
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    /// Appoint a new pool manager
+    pub fn set_manager(env: Env, admin: Address, manager: Address) -> Result<(), PoolError> {
+        admin.require_auth();
+
+        let mut config = Self::config(&env)?;
+        if admin != config.admin {
+            return Err(PoolError::Unauthorized);
+        }
+
+        config.manager = manager.clone();
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::manager_set(&env, &admin, &manager);
+
+        Ok(())
+    }
+
+    /// Set a tranche's target return and lock-up (admin only)
+    ///
+    /// The target return accrued so far is kept; the new rate applies from
+    /// now on.
+    ///
+    /// # Arguments
+    /// * `target_apr_bps` - Annual return paid out of yield before the excess is shared
+    /// * `lockup_secs` - Time after a deposit before the depositor may withdraw
+    pub fn set_tranche(
+        env: Env,
+        admin: Address,
+        tranche: Tranche,
+        target_apr_bps: u32,
+        lockup_secs: u64,
+    ) -> Result<(), PoolError> {
+        admin.require_auth();
+
+        let config = Self::config(&env)?;
+        if admin != config.admin {
+            return Err(PoolError::Unauthorized);
+        }
+        if target_apr_bps > MAX_TARGET_APR_BPS {
+            return Err(PoolError::InvalidConfig);
+        }
+
+        Self::accrue(&env);
+
+        let tranche_config = TrancheConfig {
+            target_apr_bps,
+            lockup_secs,
+        };
+        Storage::set_tranche_config(&env, tranche, &tranche_config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::tranche_set(&env, tranche, &tranche_config);
+
+        Ok(())
+    }
+
+    /// Set the minimum junior share of tranche assets (admin only)
+    ///
+    /// Senior deposits and junior withdrawals that would take the junior
+    /// tranche below it are refused, keeping a first-loss cushion under the
+    /// senior tranche.
+    pub fn set_min_junior(env: Env, admin: Address, min_junior_bps: u32) -> Result<(), PoolError> {
+        admin.require_auth();
+
+        let mut config = Self::config(&env)?;
+        if admin != config.admin {
+            return Err(PoolError::Unauthorized);
+        }
+        if min_junior_bps > 10000 {
+            return Err(PoolError::InvalidConfig);
+        }
+
+        config.min_junior_bps = min_junior_bps;
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::min_junior_set(&env, &admin, min_junior_bps);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // DEPOSITS & WITHDRAWALS
+    // ============================================================
+
+    /// Deposit USDC into a tranche and receive its shares at the current
+    /// share price
+    ///
+    /// The first deposit into a tranche mints shares one to one. A tranche
+    /// whose assets were wiped out takes no deposits until recoveries give
+    /// its shares a price again. Starts or extends the depositor's lock-up
+    /// in the tranche.
+    ///
+    /// # Returns
+    /// * Shares minted
+    pub fn deposit(env: Env, depositor: Address, tranche: Tranche, amount: i128) -> Result<i128, PoolError> {
+        depositor.require_auth();
+
+        let config = Self::config(&env)?;
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        Self::accrue(&env);
+
+        if tranche == Tranche::Senior {
+            let senior = Storage::get_tranche(&env, Tranche::Senior);
+            Self::require_subordination(&config, senior.assets + amount, Self::junior_cover(&env))?;
+        }
+
+        let mut state = Storage::get_tranche(&env, tranche);
+        if state.shares > 0 && state.assets == 0 {
+            return Err(PoolError::TrancheInsolvent);
+        }
+        let shares = Self::to_shares(&state, amount);
+        if shares <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+
+        token::Client::new(&env, &config.asset).transfer(
+            &depositor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        state.assets += amount;
+        Storage::set_tranche(&env, &state);
+        Storage::set_cash(&env, Storage::get_cash(&env) + amount);
+        Self::mint_shares(&env, tranche, &depositor, shares);
+
+        let lockup = Storage::get_tranche_config(&env, tranche).lockup_secs;
+        let locked_until = env.ledger().timestamp().saturating_add(lockup);
+        if locked_until > Storage::get_locked_until(&env, tranche, &depositor) {
+            Storage::set_locked_until(&env, tranche, &depositor, locked_until);
+        }
+        Storage::extend_instance_ttl(&env);
+
+        Events::deposited(&env, &depositor, tranche, amount, shares);
+
+        Ok(shares)
+    }
+
+    /// Redeem tranche shares for USDC
+    ///
+    /// Pays out immediately when the pool holds enough idle USDC and no
+    /// earlier withdrawal is waiting. Otherwise the shares are locked in the
+    /// pool and the withdrawal is queued; it is paid at the tranche's share
+    /// price when processed, so queued shares keep earning, and absorbing
+    /// losses, until then.
+    pub fn withdraw(
+        env: Env,
+        owner: Address,
+        tranche: Tranche,
+        shares: i128,
+    ) -> Result<WithdrawalResult, PoolError> {
+        owner.require_auth();
+
+        let config = Self::config(&env)?;
+        if shares <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        if Storage::get_balance(&env, tranche, &owner) < shares {
+            return Err(PoolError::InsufficientBalance);
+        }
+        if env.ledger().timestamp() < Storage::get_locked_until(&env, tranche, &owner) {
+            return Err(PoolError::WithdrawalLocked);
+        }
+
+        Self::accrue(&env);
+
+        let state = Storage::get_tranche(&env, tranche);
+        let assets = Self::to_assets(&state, shares);
+        if tranche == Tranche::Junior {
+            let senior = Storage::get_tranche(&env, Tranche::Senior);
+            Self::require_subordination(&config, senior.assets, Self::junior_cover(&env) - assets)?;
+        }
+
+        let result = if !Self::has_queued_withdrawals(&env) && assets <= Storage::get_cash(&env) {
+            Self::pay_withdrawal(&env, &config, tranche, &owner, &owner, shares, assets);
+
+            WithdrawalResult {
+                assets,
+                request_id: None,
+            }
+        } else {
+            let request = WithdrawalRequest {
+                id: Storage::get_queue_tail(&env),
+                owner: owner.clone(),
+                tranche,
+                shares,
+                requested_at: env.ledger().timestamp(),
+            };
+
+            Self::move_shares(&env, tranche, &owner, &env.current_contract_address(), shares);
+            Self::add_queued_shares(&env, tranche, shares);
+            Storage::set_withdrawal(&env, &request);
+            Storage::set_queue_tail(&env, request.id + 1);
+
+            Events::withdrawal_queued(&env, &request);
+
+            WithdrawalResult {
+                assets: 0,
+                request_id: Some(request.id),
+            }
+        };
+
+        Storage::extend_instance_ttl(&env);
+
+        Ok(result)
+    }
+
+    /// Cancel a queued withdrawal and return its shares to the owner
+    pub fn cancel_withdrawal(env: Env, owner: Address, request_id: u64) -> Result<(), PoolError> {
+        owner.require_auth();
+
+        let request = Storage::get_withdrawal(&env, request_id).ok_or(PoolError::WithdrawalNotFound)?;
+        if request.owner != owner {
+            return Err(PoolError::Unauthorized);
+        }
+
+        Self::move_shares(&env, request.tranche, &env.current_contract_address(), &owner, request.shares);
+        Self::add_queued_shares(&env, request.tranche, -request.shares);
+        Storage::remove_withdrawal(&env, request_id);
+        Storage::extend_instance_ttl(&env);
+
+        Events::withdrawal_cancelled(&env, &request);
+
+        Ok(())
+    }
+
+    /// Pay queued withdrawals in request order while idle liquidity lasts
+    ///
+    /// Callable by anyone, typically right after `collect`. Stops at the
+    /// first request the pool cannot pay in full.
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of queue entries to look at
+    ///
+    /// # Returns
+    /// * Number of withdrawals paid
+    pub fn process_withdrawals(env: Env, limit: u32) -> Result<u32, PoolError> {
+        let config = Self::config(&env)?;
+        let pool = env.current_contract_address();
+
+        Self::accrue(&env);
+
+        let tail = Storage::get_queue_tail(&env);
+        let mut head = Storage::get_queue_head(&env);
+        let mut examined = 0u32;
+        let mut paid = 0u32;
+
+        while head < tail && examined < limit {
+            examined += 1;
+
+            // Cancelled requests leave a gap in the queue
+            let Some(request) = Storage::get_withdrawal(&env, head) else {
+                head += 1;
+                continue;
+            };
+
+            let state = Storage::get_tranche(&env, request.tranche);
+            let assets = Self::to_assets(&state, request.shares);
+            if assets > Storage::get_cash(&env) {
+                break;
+            }
+
+            Self::pay_withdrawal(&env, &config, request.tranche, &pool, &request.owner, request.shares, assets);
+            Self::add_queued_shares(&env, request.tranche, -request.shares);
+            Storage::remove_withdrawal(&env, head);
+
+            head += 1;
+            paid += 1;
+        }
+
+        Storage::set_queue_head(&env, head);
+        Storage::extend_instance_ttl(&env);
+
+        Ok(paid)
+    }
+
+    // ============================================================
+    // INVOICE POSITIONS
+    // ============================================================
+
+    /// Fund an invoice with idle pool liquidity (called by the manager)
+    ///
+    /// Contributes `amount` of the invoice's advance through
+    /// `fund_invoice_partial`, with the pool as lender. Queued withdrawals
+    /// have first call on idle liquidity, so funding is refused while any
+    /// are waiting.
+    ///
+    /// # Arguments
+    /// * `manager` - Pool manager (must be the caller)
+    /// * `invoice_id` - Invoice to fund
+    /// * `amount` - Portion of the advance to contribute, in USDC
+    pub fn fund_invoice(env: Env, manager: Address, invoice_id: u64, amount: i128) -> Result<(), PoolError> {
+        manager.require_auth();
+
+        let config = Self::config(&env)?;
+        if manager != config.manager {
+            return Err(PoolError::Unauthorized);
+        }
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        if Self::has_queued_withdrawals(&env) {
+            return Err(PoolError::WithdrawalsPending);
+        }
+        let cash = Storage::get_cash(&env);
+        if amount > cash {
+            return Err(PoolError::InsufficientLiquidity);
+        }
+
+        // The invoice contract pulls the contribution from the pool
+        let pool = env.current_contract_address();
+        env.authorize_as_current_contract(vec![
+            &env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: config.asset.clone(),
+                    fn_name: Symbol::new(&env, "transfer"),
+                    args: (pool.clone(), config.invoice_contract.clone(), amount).into_val(&env),
+                },
+                sub_invocations: vec![&env],
+            }),
+        ]);
+        InvoiceClient::new(&env, &config.invoice_contract).fund_invoice_partial(
+            &pool,
+            &invoice_id,
+            &amount,
+        );
+
+        let mut position = Storage::get_position(&env, invoice_id).unwrap_or(PoolPosition {
+            invoice_id,
+            principal: 0,
+            collected: 0,
+            closed: false,
+        });
+        position.principal += amount;
+
+        Storage::set_position(&env, &position);
+        Storage::set_cash(&env, cash - amount);
+        Storage::set_deployed(&env, Storage::get_deployed(&env) + amount);
+        Storage::extend_instance_ttl(&env);
+
+        Events::invoice_funded(&env, invoice_id, amount, position.principal, cash - amount);
+
+        Ok(())
+    }
+
+    /// Book the proceeds the invoice contract has paid the pool for an
+    /// invoice (callable by anyone)
+    ///
+    /// Proceeds repay principal first; anything above it is income. Once
+    /// the invoice is settled, cancelled or written off, principal that was
+    /// not repaid is a loss. Income and losses go through the tranche
+    /// waterfalls. Late recoveries on written-off invoices can still be
+    /// collected, as income.
+    ///
+    /// # Returns
+    /// * Proceeds booked by this call
+    pub fn collect(env: Env, invoice_id: u64) -> Result<i128, PoolError> {
+        let config = Self::config(&env)?;
+        let pool = env.current_contract_address();
+
+        let mut position = Storage::get_position(&env, invoice_id).ok_or(PoolError::PositionNotFound)?;
+        let lender_position = InvoiceClient::new(&env, &config.invoice_contract)
+            .get_lender_position(&invoice_id, &pool)
+            .ok_or(PoolError::PositionNotFound)?;
+
+        // Pro rata shares can overstate the pool's cut by rounding dust, so
+        // never book more than the USDC actually received
+        let cash = Storage::get_cash(&env);
+        let unbooked = token::Client::new(&env, &config.asset).balance(&pool) - cash;
+        let proceeds = (lender_position.paid - position.collected).min(unbooked).max(0);
+
+        let mut principal_repaid = 0;
+        let mut loss = 0;
+        if !position.closed {
+            let repaid_before = position.collected.min(position.principal);
+            let repaid_after = (position.collected + proceeds).min(position.principal);
+            principal_repaid = repaid_after - repaid_before;
+
+            if lender_position.closed {
+                loss = position.principal - repaid_after;
+                position.closed = true;
+            }
+
+            Storage::set_deployed(&env, Storage::get_deployed(&env) - principal_repaid - loss);
+        }
+        position.collected += proceeds;
+
+        // Run the outcome through the tranche waterfalls
+        Self::accrue(&env);
+        let mut senior = Storage::get_tranche(&env, Tranche::Senior);
+        let mut junior = Storage::get_tranche(&env, Tranche::Junior);
+        let mut split = Waterfall {
+            senior_income: 0,
+            junior_income: 0,
+            senior_loss: 0,
+            junior_loss: 0,
+        };
+        waterfall::absorb_loss(&mut senior, &mut junior, loss, &mut split);
+        waterfall::distribute_income(&mut senior, &mut junior, proceeds - principal_repaid, &mut split);
+        Storage::set_tranche(&env, &senior);
+        Storage::set_tranche(&env, &junior);
+
+        Storage::set_position(&env, &position);
+        Storage::set_cash(&env, cash + proceeds);
+        Storage::extend_instance_ttl(&env);
+
+        Events::position_collected(&env, invoice_id, proceeds, principal_repaid, loss, &split, position.closed);
+
+        Ok(proceeds)
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get the pool configuration
+    pub fn get_config(env: Env) -> Result<PoolConfig, PoolError> {
+        Self::config(&env)
+    }
+
+    /// Get the pool's balance sheet
+    pub fn get_state(env: Env) -> PoolState {
+        PoolState {
+            cash: Storage::get_cash(&env),
+            deployed: Storage::get_deployed(&env),
+            senior: Storage::get_tranche(&env, Tranche::Senior),
+            junior: Storage::get_tranche(&env, Tranche::Junior),
+        }
+    }
+
+    /// Get the terms of a tranche
+    pub fn get_tranche_config(env: Env, tranche: Tranche) -> TrancheConfig {
+        Storage::get_tranche_config(&env, tranche)
+    }
+
+    /// Total USDC value of the pool: idle cash plus deployed principal
+    pub fn total_assets(env: Env) -> i128 {
+        Storage::get_cash(&env) + Storage::get_deployed(&env)
+    }
+
+    /// Shares of `tranche` a deposit of `assets` would mint now
+    pub fn convert_to_shares(env: Env, tranche: Tranche, assets: i128) -> i128 {
+        Self::to_shares(&Storage::get_tranche(&env, tranche), assets)
+    }
+
+    /// USDC value of `shares` of `tranche` at its current share price
+    pub fn convert_to_assets(env: Env, tranche: Tranche, shares: i128) -> i128 {
+        Self::to_assets(&Storage::get_tranche(&env, tranche), shares)
+    }
+
+    /// Get an owner's share balance in a tranche
+    pub fn tranche_balance(env: Env, tranche: Tranche, owner: Address) -> i128 {
+        Storage::get_balance(&env, tranche, &owner)
+    }
+
+    /// Get the time an owner's lock-up in a tranche ends
+    pub fn locked_until(env: Env, tranche: Tranche, owner: Address) -> u64 {
+        Storage::get_locked_until(&env, tranche, &owner)
+    }
+
+    /// Get the pool's position in an invoice
+    pub fn get_position(env: Env, invoice_id: u64) -> Option<PoolPosition> {
+        Storage::get_position(&env, invoice_id)
+    }
+
+    /// Get a queued withdrawal
+    pub fn get_withdrawal(env: Env, request_id: u64) -> Option<WithdrawalRequest> {
+        Storage::get_withdrawal(&env, request_id)
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    fn config(env: &Env) -> Result<PoolConfig, PoolError> {
+        Storage::get_config(env).ok_or(PoolError::NotInitialized)
+    }
+
+    /// Accrue both tranches' target returns up to now
+    fn accrue(env: &Env) {
+        let now = env.ledger().timestamp();
+        for tranche in [Tranche::Senior, Tranche::Junior] {
+            let mut state = Storage::get_tranche(env, tranche);
+            waterfall::accrue(&mut state, &Storage::get_tranche_config(env, tranche), now);
+            Storage::set_tranche(env, &state);
+        }
+    }
+
+    /// Shares `assets` buy; none while outstanding shares are worth nothing
+    fn to_shares(state: &TrancheState, assets: i128) -> i128 {
+        if state.shares == 0 {
+            return assets;
+        }
+        if state.assets == 0 {
+            return 0;
+        }
+
+        (assets * state.shares) / state.assets
+    }
+
+    fn to_assets(state: &TrancheState, shares: i128) -> i128 {
+        if state.shares == 0 {
+            return 0;
+        }
+
+        (shares * state.assets) / state.shares
+    }
+
+    /// Junior assets not already queued for withdrawal
+    fn junior_cover(env: &Env) -> i128 {
+        let junior = Storage::get_tranche(env, Tranche::Junior);
+        junior.assets - Self::to_assets(&junior, junior.queued_shares)
+    }
+
+    /// Check the junior tranche keeps its minimum share of tranche assets.
+    /// Without senior capital there is nothing to protect.
+    fn require_subordination(
+        config: &PoolConfig,
+        senior_assets: i128,
+        junior_assets: i128,
+    ) -> Result<(), PoolError> {
+        if senior_assets == 0 {
+            return Ok(());
+        }
+        if junior_assets * 10000 < (senior_assets + junior_assets) * config.min_junior_bps as i128 {
+            return Err(PoolError::SubordinationBreached);
+        }
+
+        Ok(())
+    }
+
+    fn has_queued_withdrawals(env: &Env) -> bool {
+        Storage::get_tranche(env, Tranche::Senior).queued_shares > 0
+            || Storage::get_tranche(env, Tranche::Junior).queued_shares > 0
+    }
+
+    fn add_queued_shares(env: &Env, tranche: Tranche, shares: i128) {
+        let mut state = Storage::get_tranche(env, tranche);
+        state.queued_shares += shares;
+        Storage::set_tranche(env, &state);
+    }
+
+    /// Burn `shares` of `tranche` held by `holder` and pay their value to
+    /// `owner`
+    fn pay_withdrawal(
+        env: &Env,
+        config: &PoolConfig,
+        tranche: Tranche,
+        holder: &Address,
+        owner: &Address,
+        shares: i128,
+        assets: i128,
+    ) {
+        let mut state = Storage::get_tranche(env, tranche);
+        state.assets -= assets;
+        Storage::set_tranche(env, &state);
+        Self::burn_shares(env, tranche, holder, shares);
+        Storage::set_cash(env, Storage::get_cash(env) - assets);
+
+        token::Client::new(env, &config.asset).transfer(
+            &env.current_contract_address(),
+            owner,
+            &assets,
+        );
+
+        Events::withdrawn(env, owner, tranche, assets, shares);
+    }
+
+    fn mint_shares(env: &Env, tranche: Tranche, to: &Address, amount: i128) {
+        Storage::set_balance(env, tranche, to, Storage::get_balance(env, tranche, to) + amount);
+
+        let mut state = Storage::get_tranche(env, tranche);
+        state.shares += amount;
+        Storage::set_tranche(env, &state);
+
+        if tranche == Tranche::Senior {
+            Events::mint(env, to, amount);
+        }
+    }
+
+    fn burn_shares(env: &Env, tranche: Tranche, from: &Address, amount: i128) {
+        let balance = Storage::get_balance(env, tranche, from);
+        if balance < amount {
+            panic_with_error!(env, PoolError::InsufficientBalance);
+        }
+
+        Storage::set_balance(env, tranche, from, balance - amount);
+
+        // Once the last shares are gone, unpaid target returns and
+        // unrecovered losses go with them rather than to the next depositor.
+        // The assets must have been paid out first.
+        let mut state = Storage::get_tranche(env, tranche);
+        state.shares -= amount;
+        if state.shares == 0 {
+            if state.assets > 0 {
+                panic_with_error!(env, PoolError::LastSharesBurned);
+            }
+            state.yield_due = 0;
+            state.losses = 0;
+        }
+        Storage::set_tranche(env, &state);
+
+        if tranche == Tranche::Senior {
+            Events::burn(env, from, amount);
+        }
+    }
+
+    fn move_shares(env: &Env, tranche: Tranche, from: &Address, to: &Address, amount: i128) {
+        let balance = Storage::get_balance(env, tranche, from);
+        if balance < amount {
+            panic_with_error!(env, PoolError::InsufficientBalance);
+        }
+
+        Storage::set_balance(env, tranche, from, balance - amount);
+        Storage::set_balance(env, tranche, to, Storage::get_balance(env, tranche, to) + amount);
+
+        if tranche == Tranche::Senior {
+            Events::transfer(env, from, to, amount);
+        }
+    }
+
+    /// Move senior shares between holders, carrying the sender's lock-up
+    /// over so transfers cannot be used to skip it
+    fn transfer_senior(env: &Env, from: &Address, to: &Address, amount: i128) {
+        Self::move_shares(env, Tranche::Senior, from, to, amount);
+
+        let locked_until = Storage::get_locked_until(env, Tranche::Senior, from);
+        if locked_until > Storage::get_locked_until(env, Tranche::Senior, to) {
+            Storage::set_locked_until(env, Tranche::Senior, to, locked_until);
+        }
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Storage::get_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic_with_error!(env, PoolError::InsufficientAllowance);
+        }
+
+        if amount > 0 {
+            Storage::set_allowance(
+                env,
+                from,
+                spender,
+                &AllowanceValue {
+                    amount: allowance.amount - amount,
+                    expiration_ledger: allowance.expiration_ledger,
+                },
+            );
+        }
+    }
+
+    fn require_non_negative(env: &Env, amount: i128) {
+        if amount < 0 {
+            panic_with_error!(env, PoolError::InvalidAmount);
+        }
+    }
+}
+
+// ============================================================
+// SHARE TOKEN (SEP-41)
+// ============================================================
+// Senior tranche shares. Junior shares are not transferable.
+
+#[contractimpl]
+impl TokenInterface for AdelantaPoolContract {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        Storage::get_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+
+        Self::require_non_negative(&env, amount);
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            panic_with_error!(&env, PoolError::InvalidExpiration);
+        }
+
+        Storage::set_allowance(
+            &env,
+            &from,
+            &spender,
+            &AllowanceValue {
+                amount,
+                expiration_ledger,
+            },
+        );
+        Storage::extend_instance_ttl(&env);
+
+        Events::approve(&env, &from, &spender, amount, expiration_ledger);
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        Storage::get_balance(&env, Tranche::Senior, &id)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+
+        Self::require_non_negative(&env, amount);
+        Self::transfer_senior(&env, &from, &to, amount);
+        Storage::extend_instance_ttl(&env);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+
+        Self::require_non_negative(&env, amount);
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::transfer_senior(&env, &from, &to, amount);
+        Storage::extend_instance_ttl(&env);
+    }
+
+    /// Burning shares without redeeming them leaves their value to the
+    /// remaining senior holders. The last shares cannot be burned while the
+    /// tranche holds assets.
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+
+        Self::require_non_negative(&env, amount);
+        Self::burn_shares(&env, Tranche::Senior, &from, amount);
+        Storage::extend_instance_ttl(&env);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+
+        Self::require_non_negative(&env, amount);
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::burn_shares(&env, Tranche::Senior, &from, amount);
+        Storage::extend_instance_ttl(&env);
+    }
+
+    fn decimals(_env: Env) -> u32 {
+        SHARE_DECIMALS
+    }
+
+    fn name(env: Env) -> String {
+        Self::config(&env)
+            .unwrap_or_else(|error| panic_with_error!(&env, error))
+            .name
+    }
+
+    fn symbol(env: Env) -> String {
+        Self::config(&env)
+            .unwrap_or_else(|error| panic_with_error!(&env, error))
+            .symbol
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token::{Client as TokenClient, StellarAssetClient},
+        BytesN,
+    };
+
+    /// The invoice contract the pool lends through
+    #[allow(clippy::too_many_arguments)]
+    mod adelanta_invoice {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_invoice.wasm"
+        );
+    }
+
+    /// The settlement contract paying out the invoices
+    mod adelanta_settlement {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_settlement.wasm"
+        );
+    }
+    use adelanta_invoice::{Client as AdelantaInvoiceContractClient, FeeModel, InvoiceStatus, Role};
+
+    struct TestContext {
+        env: Env,
+        client: AdelantaPoolContractClient<'static>,
+        invoices: AdelantaInvoiceContractClient<'static>,
+        settlement: adelanta_settlement::Client<'static>,
+        admin: Address,
+        manager: Address,
+        sme: Address,
+        usdc: TokenClient<'static>,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    /// Pool over an invoice contract (90% advance, 0.5% protocol fee) at
+    /// timestamp 1000, with 10,000 USDC minted to the admin for settlements
+    fn setup() -> TestContext {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let manager = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+
+        let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc = TokenClient::new(&env, &usdc_address);
+        let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let invoice_id = env.register_contract_wasm(None, adelanta_invoice::WASM);
+        let invoices = AdelantaInvoiceContractClient::new(&env, &invoice_id);
+        invoices.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let settlement_id = env.register_contract_wasm(None, adelanta_settlement::WASM);
+        let settlement = adelanta_settlement::Client::new(&env, &settlement_id);
+        settlement.initialize(&invoice_id);
+        invoices.grant_role(&admin, &Role::Settlement, &settlement_id);
+
+        let pool_id = env.register_contract(None, AdelantaPoolContract);
+        let client = AdelantaPoolContractClient::new(&env, &pool_id);
+        client.initialize(
+            &admin,
+            &manager,
+            &usdc_address,
+            &invoice_id,
+            &String::from_str(&env, "Adelanta Pool Share"),
+            &String::from_str(&env, "ADLP"),
+        );
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            client,
+            invoices,
+            settlement,
+            admin,
+            manager,
+            sme,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Generate a depositor holding 10,000 USDC
+    fn depositor(ctx: &TestContext) -> Address {
+        let depositor = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&depositor, &10000_0000000);
+        depositor
+    }
+
+    /// Create a 2% fee invoice for the context's SME, due at timestamp 2000,
+    /// with a fingerprint unique to the invoice
+    fn create_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        let fingerprint = BytesN::from_array(&ctx.env, &[ctx.invoices.get_stats().total_invoices as u8; 32]);
+        ctx.invoices.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &fingerprint,
+            &ctx.usdc.address,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let ctx = setup();
+        let alice = depositor(&ctx);
+
+        assert_eq!(ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000), 1000_0000000);
+        assert_eq!(ctx.client.balance(&alice), 1000_0000000);
+        assert_eq!(ctx.client.total_assets(), 1000_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.client.address), 1000_0000000);
+
+        let result = ctx.client.withdraw(&alice, &Tranche::Senior, &400_0000000);
+        assert_eq!(result.assets, 400_0000000);
+        assert_eq!(result.request_id, None);
+        assert_eq!(ctx.client.balance(&alice), 600_0000000);
+        assert_eq!(ctx.usdc.balance(&alice), 9400_0000000);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.cash, 600_0000000);
+        assert_eq!(state.senior.shares, 600_0000000);
+
+        assert_eq!(
+            ctx.client.try_withdraw(&alice, &Tranche::Senior, &700_0000000),
+            Err(Ok(PoolError::InsufficientBalance))
+        );
+        assert_eq!(
+            ctx.client.try_deposit(&alice, &Tranche::Senior, &0),
+            Err(Ok(PoolError::InvalidAmount))
+        );
+    }
+
+    #[test]
+    fn test_settlement_raises_share_price() {
+        let ctx = setup();
+        let alice = depositor(&ctx);
+        let bob = depositor(&ctx);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+        ctx.client.deposit(&bob, &Tranche::Senior, &1000_0000000);
+
+        // Only the manager deploys liquidity
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&alice, &invoice_id, &900_0000000),
+            Err(Ok(PoolError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_fund_invoice(&ctx.manager, &invoice_id, &2500_0000000),
+            Err(Ok(PoolError::InsufficientLiquidity))
+        );
+
+        ctx.client.fund_invoice(&ctx.manager, &invoice_id, &900_0000000);
+
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, InvoiceStatus::Funded);
+        assert_eq!(invoice.lender, Some(ctx.client.address.clone()));
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 900_0000000);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.cash, 1100_0000000);
+        assert_eq!(state.deployed, 900_0000000);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Senior, &1000_0000000), 1000_0000000);
+
+        // Proceeds reach the pool but only count once collected
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&ctx.client.address), 2018_0000000);
+        assert_eq!(ctx.client.total_assets(), 2000_0000000);
+
+        // Principal 900 + 2% fee 18
+        assert_eq!(ctx.client.collect(&invoice_id), 918_0000000);
+        let position = ctx.client.get_position(&invoice_id).unwrap();
+        assert_eq!(position.principal, 900_0000000);
+        assert_eq!(position.collected, 918_0000000);
+        assert!(position.closed);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.cash, 2018_0000000);
+        assert_eq!(state.deployed, 0);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Senior, &1000_0000000), 1009_0000000);
+
+        // Collecting again books nothing new
+        assert_eq!(ctx.client.collect(&invoice_id), 0);
+
+        // New deposits buy in at the higher share price
+        let carol = depositor(&ctx);
+        assert_eq!(ctx.client.deposit(&carol, &Tranche::Senior, &1009_0000000), 1000_0000000);
+
+        let result = ctx.client.withdraw(&alice, &Tranche::Senior, &1000_0000000);
+        assert_eq!(result.assets, 1009_0000000);
+        assert_eq!(ctx.usdc.balance(&alice), 10009_0000000);
+    }
+
+    #[test]
+    fn test_withdrawal_queue() {
+        let ctx = setup();
+        let alice = depositor(&ctx);
+        let bob = depositor(&ctx);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&ctx.manager, &invoice_id, &900_0000000);
+
+        // Only 100 USDC idle, so the withdrawal waits for liquidity
+        let result = ctx.client.withdraw(&alice, &Tranche::Senior, &1000_0000000);
+        assert_eq!(result.assets, 0);
+        assert_eq!(result.request_id, Some(1));
+        assert_eq!(ctx.client.balance(&alice), 0);
+        assert_eq!(ctx.client.balance(&ctx.client.address), 1000_0000000);
+        assert_eq!(ctx.client.get_state().senior.queued_shares, 1000_0000000);
+
+        // Later deposits neither jump the queue nor get deployed ahead of it
+        ctx.client.deposit(&bob, &Tranche::Senior, &50_0000000);
+        let result = ctx.client.withdraw(&bob, &Tranche::Senior, &50_0000000);
+        assert_eq!(result.request_id, Some(2));
+
+        let other_invoice = create_invoice(&ctx, 100_0000000);
+        assert_eq!(
+            ctx.client.try_fund_invoice(&ctx.manager, &other_invoice, &90_0000000),
+            Err(Ok(PoolError::WithdrawalsPending))
+        );
+        assert_eq!(ctx.client.process_withdrawals(&10), 0);
+
+        // Bob changes his mind and gets his shares back
+        assert_eq!(
+            ctx.client.try_cancel_withdrawal(&alice, &2),
+            Err(Ok(PoolError::Unauthorized))
+        );
+        ctx.client.cancel_withdrawal(&bob, &2);
+        assert_eq!(ctx.client.balance(&bob), 50_0000000);
+        assert_eq!(ctx.client.get_withdrawal(&2), None);
+
+        // Settlement proceeds pay the queue, with the yield shared by every
+        // share outstanding, Bob's included: 1000 * 1068 / 1050
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        ctx.client.collect(&invoice_id);
+        assert_eq!(ctx.client.process_withdrawals(&10), 1);
+
+        assert_eq!(ctx.usdc.balance(&alice), 10017_1428571);
+        assert_eq!(ctx.client.get_withdrawal(&1), None);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.cash, 50_8571429);
+        assert_eq!(state.senior.shares, 50_0000000);
+        assert_eq!(state.senior.queued_shares, 0);
+
+        // With the queue drained the manager may deploy again
+        ctx.client.fund_invoice(&ctx.manager, &other_invoice, &50_0000000);
+    }
+
+    #[test]
+    fn test_collect_losses_and_recoveries() {
+        let ctx = setup();
+        let alice = depositor(&ctx);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+
+        // A cancelled invoice refunds the contribution in full
+        let cancelled = create_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&ctx.manager, &cancelled, &500_0000000);
+        ctx.invoices.cancel_invoice(&ctx.sme, &cancelled);
+        assert_eq!(ctx.client.collect(&cancelled), 500_0000000);
+        assert_eq!(ctx.client.get_state().deployed, 0);
+        assert_eq!(ctx.client.total_assets(), 1000_0000000);
+
+        // A partial payment repays principal first
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&ctx.manager, &invoice_id, &900_0000000);
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &500_0000000, &None);
+        assert_eq!(ctx.client.collect(&invoice_id), 500_0000000);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.cash, 600_0000000);
+        assert_eq!(state.deployed, 400_0000000);
+
+        // Writing the invoice off writes the rest of the principal off
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2000 + 31 * 24 * 60 * 60;
+        });
+        ctx.invoices.mark_defaulted(&invoice_id);
+        ctx.invoices.write_off(&ctx.admin, &invoice_id);
+        assert_eq!(ctx.client.collect(&invoice_id), 0);
+
+        let position = ctx.client.get_position(&invoice_id).unwrap();
+        assert!(position.closed);
+        assert_eq!(ctx.client.get_state().deployed, 0);
+        assert_eq!(ctx.client.total_assets(), 600_0000000);
+
+        // Late recoveries are still booked
+        ctx.settlement.recover_invoice(&ctx.admin, &invoice_id, &300_0000000);
+        assert_eq!(ctx.client.collect(&invoice_id), 300_0000000);
+        assert_eq!(ctx.client.total_assets(), 900_0000000);
+
+        assert_eq!(
+            ctx.client.try_collect(&99),
+            Err(Ok(PoolError::PositionNotFound))
+        );
+    }
+
+    #[test]
+    fn test_share_token() {
+        let ctx = setup();
+        let alice = depositor(&ctx);
+        let bob = Address::generate(&ctx.env);
+        let spender = Address::generate(&ctx.env);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+
+        assert_eq!(ctx.client.name(), String::from_str(&ctx.env, "Adelanta Pool Share"));
+        assert_eq!(ctx.client.symbol(), String::from_str(&ctx.env, "ADLP"));
+        assert_eq!(ctx.client.decimals(), 7);
+
+        ctx.client.transfer(&alice, &bob, &300_0000000);
+        assert_eq!(ctx.client.balance(&alice), 700_0000000);
+        assert_eq!(ctx.client.balance(&bob), 300_0000000);
+
+        ctx.client.approve(&alice, &spender, &200_0000000, &1000);
+        assert_eq!(ctx.client.allowance(&alice, &spender), 200_0000000);
+        ctx.client.transfer_from(&spender, &alice, &bob, &150_0000000);
+        assert_eq!(ctx.client.allowance(&alice, &spender), 50_0000000);
+        assert_eq!(ctx.client.balance(&bob), 450_0000000);
+
+        // Nobody would be left to own the assets behind the last shares
+        ctx.client.transfer(&bob, &alice, &450_0000000);
+        assert!(ctx.client.try_burn(&alice, &1000_0000000).is_err());
+        ctx.client.transfer(&alice, &bob, &450_0000000);
+
+        assert!(ctx.client.try_transfer_from(&spender, &alice, &bob, &100_0000000).is_err());
+        assert!(ctx.client.try_transfer(&bob, &alice, &500_0000000).is_err());
+
+        // Allowances lapse after their expiration ledger
+        ctx.env.ledger().with_mut(|li| {
+            li.sequence_number = 1001;
+        });
+        assert_eq!(ctx.client.allowance(&alice, &spender), 0);
+
+        // Transferred shares are redeemable by their new holder
+        let result = ctx.client.withdraw(&bob, &Tranche::Senior, &450_0000000);
+        assert_eq!(result.assets, 450_0000000);
+        assert_eq!(ctx.client.get_state().senior.shares, 550_0000000);
+    }
+
+    #[test]
+    fn test_tranche_yield_waterfall() {
+        let ctx = setup();
+        let sponsor = depositor(&ctx);
+        let alice = depositor(&ctx);
+
+        assert_eq!(
+            ctx.client.try_set_tranche(&ctx.admin, &Tranche::Senior, &10001, &0),
+            Err(Ok(PoolError::InvalidConfig))
+        );
+        assert_eq!(
+            ctx.client.try_set_tranche(&alice, &Tranche::Senior, &1000, &0),
+            Err(Ok(PoolError::Unauthorized))
+        );
+
+        // Senior targets 10%, junior 20% with a 90-day lock-up and at
+        // least a 20% share of the pool
+        ctx.client.set_tranche(&ctx.admin, &Tranche::Senior, &1000, &0);
+        ctx.client.set_tranche(&ctx.admin, &Tranche::Junior, &2000, &(90 * 86_400));
+        ctx.client.set_min_junior(&ctx.admin, &2000);
+
+        assert_eq!(
+            ctx.client.try_deposit(&alice, &Tranche::Senior, &1000_0000000),
+            Err(Ok(PoolError::SubordinationBreached))
+        );
+        ctx.client.deposit(&sponsor, &Tranche::Junior, &250_0000000);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+        assert_eq!(
+            ctx.client.try_deposit(&alice, &Tranche::Senior, &100_0000000),
+            Err(Ok(PoolError::SubordinationBreached))
+        );
+
+        // Junior shares are not part of the SEP-41 token
+        assert_eq!(ctx.client.balance(&sponsor), 0);
+        assert_eq!(ctx.client.tranche_balance(&Tranche::Junior, &sponsor), 250_0000000);
+        assert_eq!(ctx.client.locked_until(&Tranche::Junior, &sponsor), 1000 + 90 * 86_400);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&ctx.manager, &invoice_id, &900_0000000);
+
+        // A tenth of a year later the invoice pays 18 USDC of fees: 10 to
+        // cover the senior target, 5 the junior target, and the last 3 to
+        // the junior tranche
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 3_153_600;
+        });
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(ctx.client.collect(&invoice_id), 918_0000000);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.senior.assets, 1010_0000000);
+        assert_eq!(state.junior.assets, 258_0000000);
+        assert_eq!(state.senior.yield_due, 0);
+        assert_eq!(state.junior.yield_due, 0);
+        assert_eq!(ctx.client.total_assets(), 1268_0000000);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Senior, &1000_0000000), 1010_0000000);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Junior, &250_0000000), 258_0000000);
+
+        // Senior earns its 1% target for the period; junior, bearing the
+        // first loss, realizes 3.2%
+        let senior_yield_bps = (state.senior.assets - 1000_0000000) * 10000 / 1000_0000000;
+        let junior_yield_bps = (state.junior.assets - 250_0000000) * 10000 / 250_0000000;
+        assert_eq!(senior_yield_bps, 100);
+        assert_eq!(junior_yield_bps, 320);
+
+        // The junior tranche is locked up, then held to its minimum share
+        assert_eq!(
+            ctx.client.try_withdraw(&sponsor, &Tranche::Junior, &100_0000000),
+            Err(Ok(PoolError::WithdrawalLocked))
+        );
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 90 * 86_400;
+        });
+        assert_eq!(
+            ctx.client.try_withdraw(&sponsor, &Tranche::Junior, &100_0000000),
+            Err(Ok(PoolError::SubordinationBreached))
+        );
+
+        let result = ctx.client.withdraw(&alice, &Tranche::Senior, &500_0000000);
+        assert_eq!(result.assets, 505_0000000);
+        let result = ctx.client.withdraw(&sponsor, &Tranche::Junior, &100_0000000);
+        assert_eq!(result.assets, 103_2000000);
+    }
+
+    #[test]
+    fn test_junior_absorbs_losses_first() {
+        let ctx = setup();
+        let sponsor = depositor(&ctx);
+        let alice = depositor(&ctx);
+        ctx.client.deposit(&sponsor, &Tranche::Junior, &250_0000000);
+        ctx.client.deposit(&alice, &Tranche::Senior, &1000_0000000);
+
+        let invoice_id = create_invoice(&ctx, 1000_0000000);
+        ctx.client.fund_invoice(&ctx.manager, &invoice_id, &900_0000000);
+
+        // The payer never pays and the invoice is written off: the junior
+        // tranche is wiped out before the senior tranche loses 650
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2000 + 31 * 24 * 60 * 60;
+        });
+        ctx.invoices.mark_defaulted(&invoice_id);
+        ctx.invoices.write_off(&ctx.admin, &invoice_id);
+        ctx.client.collect(&invoice_id);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.junior.assets, 0);
+        assert_eq!(state.junior.losses, 250_0000000);
+        assert_eq!(state.senior.assets, 350_0000000);
+        assert_eq!(state.senior.losses, 650_0000000);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Senior, &1000_0000000), 350_0000000);
+
+        // The wiped-out junior shares cannot price new deposits
+        assert_eq!(ctx.client.convert_to_shares(&Tranche::Junior, &100_0000000), 0);
+        assert_eq!(
+            ctx.client.try_deposit(&sponsor, &Tranche::Junior, &100_0000000),
+            Err(Ok(PoolError::TrancheInsolvent))
+        );
+
+        // Recoveries make the senior tranche whole first
+        ctx.settlement.recover_invoice(&ctx.admin, &invoice_id, &700_0000000);
+        assert_eq!(ctx.client.collect(&invoice_id), 700_0000000);
+
+        let state = ctx.client.get_state();
+        assert_eq!(state.senior.assets, 1000_0000000);
+        assert_eq!(state.senior.losses, 0);
+        assert_eq!(state.junior.assets, 50_0000000);
+        assert_eq!(state.junior.losses, 200_0000000);
+        assert_eq!(ctx.client.total_assets(), 1050_0000000);
+
+        // Once recoveries price the junior shares again, deposits buy in at
+        // that price instead of diluting the existing holders
+        assert_eq!(ctx.client.deposit(&sponsor, &Tranche::Junior, &50_0000000), 250_0000000);
+        assert_eq!(ctx.client.convert_to_assets(&Tranche::Junior, &250_0000000), 50_0000000);
+    }
+}
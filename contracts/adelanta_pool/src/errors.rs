@@ -0,0 +1,41 @@
+//! Custom error types for the Adelanta Pool Contract
+
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum PoolError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Unauthorized caller
+    Unauthorized = 3,
+    /// Invalid amount (zero or negative)
+    InvalidAmount = 4,
+    /// Share balance too low
+    InsufficientBalance = 5,
+    /// Share allowance too low
+    InsufficientAllowance = 6,
+    /// Allowance expiration ledger already passed
+    InvalidExpiration = 7,
+    /// Not enough idle liquidity in the pool
+    InsufficientLiquidity = 8,
+    /// Queued withdrawals must be paid before liquidity is deployed
+    WithdrawalsPending = 9,
+    /// Pool has no position in the invoice
+    PositionNotFound = 10,
+    /// Withdrawal request not found
+    WithdrawalNotFound = 11,
+    /// Shares still within the tranche's lock-up
+    WithdrawalLocked = 12,
+    /// Junior tranche would fall below its minimum share of the pool
+    SubordinationBreached = 13,
+    /// Tranche terms out of range
+    InvalidConfig = 14,
+    /// Tranche shares are outstanding but its assets were wiped out
+    TrancheInsolvent = 15,
+    /// Burning the last shares of a tranche would strand its assets
+    LastSharesBurned = 16,
+}
@@ -0,0 +1,205 @@
+//! Contract events for the Adelanta Pool Contract
+//!
+//! Pool events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. Senior share token events use the fixed
+//! SEP-41 topics instead so wallets and indexers pick up the shares like
+//! any other token.
+
+use soroban_sdk::{contracttype, symbol_short, Address, Env, Symbol};
+
+use crate::types::{PoolConfig, Tranche, TrancheConfig, Waterfall, WithdrawalRequest};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const MANAGER_SET: Symbol = symbol_short!("manager");
+pub const TRANCHE_SET: Symbol = symbol_short!("tranche");
+pub const MIN_JUNIOR_SET: Symbol = symbol_short!("min_jr");
+pub const DEPOSITED: Symbol = symbol_short!("deposit");
+pub const WITHDRAWN: Symbol = symbol_short!("withdraw");
+pub const WITHDRAWAL_QUEUED: Symbol = symbol_short!("wd_queued");
+pub const WITHDRAWAL_CANCELLED: Symbol = symbol_short!("wd_cancel");
+pub const INVOICE_FUNDED: Symbol = symbol_short!("funded");
+pub const POSITION_COLLECTED: Symbol = symbol_short!("collected");
+
+/// Payload of the `deposit` and `withdraw` events
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SharesEvent {
+    pub assets: i128,
+    pub shares: i128,
+    pub timestamp: u64,
+}
+
+/// Payload of the `funded` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolFundedEvent {
+    pub amount: i128,
+    pub principal: i128,
+    pub cash: i128,
+    pub timestamp: u64,
+}
+
+/// Payload of the `collected` event
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionCollectedEvent {
+    pub proceeds: i128,
+    pub principal_repaid: i128,
+    pub loss: i128,
+    pub waterfall: Waterfall,
+    pub closed: bool,
+    pub timestamp: u64,
+}
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    // ========== CONFIG ==========
+
+    /// Topics: `("init", version, admin)`
+    pub fn initialized(env: &Env, config: &PoolConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.admin.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("manager", version, admin)`
+    pub fn manager_set(env: &Env, admin: &Address, manager: &Address) {
+        env.events().publish(
+            (MANAGER_SET, EVENTS_VERSION, admin.clone()),
+            manager.clone(),
+        );
+    }
+
+    /// Topics: `("tranche", version, tranche)`
+    pub fn tranche_set(env: &Env, tranche: Tranche, config: &TrancheConfig) {
+        env.events().publish(
+            (TRANCHE_SET, EVENTS_VERSION, tranche),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("min_jr", version, admin)`
+    pub fn min_junior_set(env: &Env, admin: &Address, min_junior_bps: u32) {
+        env.events().publish(
+            (MIN_JUNIOR_SET, EVENTS_VERSION, admin.clone()),
+            min_junior_bps,
+        );
+    }
+
+    // ========== DEPOSITS & WITHDRAWALS ==========
+
+    /// Topics: `("deposit", version, depositor, tranche)`
+    pub fn deposited(env: &Env, depositor: &Address, tranche: Tranche, assets: i128, shares: i128) {
+        env.events().publish(
+            (DEPOSITED, EVENTS_VERSION, depositor.clone(), tranche),
+            SharesEvent {
+                assets,
+                shares,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("withdraw", version, owner, tranche)`
+    pub fn withdrawn(env: &Env, owner: &Address, tranche: Tranche, assets: i128, shares: i128) {
+        env.events().publish(
+            (WITHDRAWN, EVENTS_VERSION, owner.clone(), tranche),
+            SharesEvent {
+                assets,
+                shares,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("wd_queued", version, owner, request_id)`
+    pub fn withdrawal_queued(env: &Env, request: &WithdrawalRequest) {
+        env.events().publish(
+            (WITHDRAWAL_QUEUED, EVENTS_VERSION, request.owner.clone(), request.id),
+            request.clone(),
+        );
+    }
+
+    /// Topics: `("wd_cancel", version, owner, request_id)`
+    pub fn withdrawal_cancelled(env: &Env, request: &WithdrawalRequest) {
+        env.events().publish(
+            (WITHDRAWAL_CANCELLED, EVENTS_VERSION, request.owner.clone(), request.id),
+            request.clone(),
+        );
+    }
+
+    // ========== INVOICE POSITIONS ==========
+
+    /// Topics: `("funded", version, invoice_id)`
+    pub fn invoice_funded(env: &Env, invoice_id: u64, amount: i128, principal: i128, cash: i128) {
+        env.events().publish(
+            (INVOICE_FUNDED, EVENTS_VERSION, invoice_id),
+            PoolFundedEvent {
+                amount,
+                principal,
+                cash,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    /// Topics: `("collected", version, invoice_id)`
+    pub fn position_collected(
+        env: &Env,
+        invoice_id: u64,
+        proceeds: i128,
+        principal_repaid: i128,
+        loss: i128,
+        waterfall: &Waterfall,
+        closed: bool,
+    ) {
+        env.events().publish(
+            (POSITION_COLLECTED, EVENTS_VERSION, invoice_id),
+            PositionCollectedEvent {
+                proceeds,
+                principal_repaid,
+                loss,
+                waterfall: waterfall.clone(),
+                closed,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+
+    // ========== SHARE TOKEN (SEP-41) ==========
+
+    /// Topics: `("approve", from, spender)`
+    pub fn approve(env: &Env, from: &Address, spender: &Address, amount: i128, expiration_ledger: u32) {
+        env.events().publish(
+            (Symbol::new(env, "approve"), from.clone(), spender.clone()),
+            (amount, expiration_ledger),
+        );
+    }
+
+    /// Topics: `("transfer", from, to)`
+    pub fn transfer(env: &Env, from: &Address, to: &Address, amount: i128) {
+        env.events().publish(
+            (Symbol::new(env, "transfer"), from.clone(), to.clone()),
+            amount,
+        );
+    }
+
+    /// Topics: `("mint", pool, to)`
+    pub fn mint(env: &Env, to: &Address, amount: i128) {
+        env.events().publish(
+            (Symbol::new(env, "mint"), env.current_contract_address(), to.clone()),
+            amount,
+        );
+    }
+
+    /// Topics: `("burn", from)`
+    pub fn burn(env: &Env, from: &Address, amount: i128) {
+        env.events().publish((Symbol::new(env, "burn"), from.clone()), amount);
+    }
+}
@@ -0,0 +1,19 @@
+//! Client for the Adelanta invoice contract
+//!
+//! Declares only the entry points the pool calls. Types come from
+//! `adelanta_types`, shared with the invoice contract.
+
+use soroban_sdk::{contractclient, Address, Env};
+
+pub use adelanta_types::{FundingEvent, LenderPosition};
+
+/// Subset of the `AdelantaInvoiceContract` interface used by the pool
+#[allow(dead_code)]
+#[contractclient(name = "InvoiceClient")]
+pub trait Invoice {
+    /// Contribute `amount` of the invoice's advance on behalf of `lender`
+    fn fund_invoice_partial(env: Env, lender: Address, invoice_id: u64, amount: i128) -> FundingEvent;
+
+    /// A lender's position in an invoice and what it has returned so far
+    fn get_lender_position(env: Env, invoice_id: u64, lender: Address) -> Option<LenderPosition>;
+}
@@ -0,0 +1,37 @@
+//! # Adelanta Pool Contract
+//!
+//! A Soroban smart contract pooling lender liquidity for the Adelanta
+//! invoice contract.
+//!
+//! ## Overview
+//! Depositors put USDC into a senior or a junior tranche of the pool and
+//! receive shares of the tranche; senior shares are a SEP-41 token. A pool
+//! manager funds invoices out of the pool on the depositors' behalf;
+//! settlement proceeds flow back to the pool and raise share prices.
+//!
+//! ## Key Features
+//! - SEP-41 senior share token, transferable like any Stellar token
+//! - Junior first-loss tranche with target returns per tranche
+//! - Invoice funding through `AdelantaInvoiceContract`
+//! - Share price growth as invoices settle
+//! - Withdrawal queue while liquidity is deployed
+//!
+//! ## Flow
+//! 1. Depositor deposits USDC → pool mints tranche shares at the share price
+//! 2. Manager funds invoices with idle pool USDC
+//! 3. Payer settles the invoice → lender proceeds are paid to the pool
+//! 4. Anyone collects the position → income and losses run through the
+//!    tranche waterfalls
+//! 5. Depositor redeems shares, immediately or through the queue
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+mod invoice;
+mod waterfall;
+
+pub use contract::AdelantaPoolContract;
@@ -0,0 +1,214 @@
+//! Storage keys and helpers for the Adelanta Pool Contract
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Val};
+
+use crate::types::{
+    AllowanceValue, PoolConfig, PoolPosition, Tranche, TrancheConfig, TrancheState, WithdrawalRequest,
+};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Pool configuration
+    Config,
+    /// Idle USDC booked by the pool
+    Cash,
+    /// Principal deployed in open invoice positions
+    Deployed,
+    /// Book value and shares of a tranche
+    Tranche(Tranche),
+    /// Terms of a tranche
+    TrancheConfig(Tranche),
+    /// Share balance by (tranche, owner)
+    Balance(Tranche, Address),
+    /// End of an owner's lock-up by (tranche, owner)
+    LockedUntil(Tranche, Address),
+    /// Senior share allowance by (owner, spender)
+    Allowance(Address, Address),
+    /// Pool position by invoice ID
+    Position(u64),
+    /// Queued withdrawal by request ID
+    Withdrawal(u64),
+    /// ID of the oldest request that may still be queued
+    QueueHead,
+    /// Next withdrawal request ID
+    QueueTail,
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<PoolConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &PoolConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== BOOKS ==========
+
+    pub fn get_cash(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::Cash).unwrap_or(0)
+    }
+
+    pub fn set_cash(env: &Env, amount: i128) {
+        env.storage().instance().set(&DataKey::Cash, &amount);
+    }
+
+    pub fn get_deployed(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::Deployed).unwrap_or(0)
+    }
+
+    pub fn set_deployed(env: &Env, amount: i128) {
+        env.storage().instance().set(&DataKey::Deployed, &amount);
+    }
+
+    // ========== TRANCHES ==========
+
+    pub fn get_tranche(env: &Env, tranche: Tranche) -> TrancheState {
+        env.storage()
+            .instance()
+            .get(&DataKey::Tranche(tranche))
+            .unwrap_or(TrancheState {
+                tranche,
+                assets: 0,
+                shares: 0,
+                queued_shares: 0,
+                yield_due: 0,
+                losses: 0,
+                accrued_at: env.ledger().timestamp(),
+            })
+    }
+
+    pub fn set_tranche(env: &Env, state: &TrancheState) {
+        env.storage().instance().set(&DataKey::Tranche(state.tranche), state);
+    }
+
+    pub fn get_tranche_config(env: &Env, tranche: Tranche) -> TrancheConfig {
+        env.storage()
+            .instance()
+            .get(&DataKey::TrancheConfig(tranche))
+            .unwrap_or(TrancheConfig {
+                target_apr_bps: 0,
+                lockup_secs: 0,
+            })
+    }
+
+    pub fn set_tranche_config(env: &Env, tranche: Tranche, config: &TrancheConfig) {
+        env.storage().instance().set(&DataKey::TrancheConfig(tranche), config);
+    }
+
+    // ========== SHARES ==========
+
+    pub fn get_balance(env: &Env, tranche: Tranche, owner: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(tranche, owner.clone()))
+            .unwrap_or(0)
+    }
+
+    pub fn set_balance(env: &Env, tranche: Tranche, owner: &Address, amount: i128) {
+        Self::set_persistent(env, &DataKey::Balance(tranche, owner.clone()), &amount);
+    }
+
+    pub fn get_locked_until(env: &Env, tranche: Tranche, owner: &Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::LockedUntil(tranche, owner.clone()))
+            .unwrap_or(0)
+    }
+
+    pub fn set_locked_until(env: &Env, tranche: Tranche, owner: &Address, timestamp: u64) {
+        Self::set_persistent(env, &DataKey::LockedUntil(tranche, owner.clone()), &timestamp);
+    }
+
+    // ========== ALLOWANCES ==========
+
+    /// Allowance of `spender` over `owner`'s shares, zero once expired
+    pub fn get_allowance(env: &Env, owner: &Address, spender: &Address) -> AllowanceValue {
+        let key = DataKey::Allowance(owner.clone(), spender.clone());
+        match env.storage().temporary().get::<_, AllowanceValue>(&key) {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => allowance,
+            _ => AllowanceValue {
+                amount: 0,
+                expiration_ledger: 0,
+            },
+        }
+    }
+
+    /// Store an allowance, kept alive until its expiration ledger
+    pub fn set_allowance(env: &Env, owner: &Address, spender: &Address, allowance: &AllowanceValue) {
+        let key = DataKey::Allowance(owner.clone(), spender.clone());
+        env.storage().temporary().set(&key, allowance);
+
+        if allowance.amount > 0 {
+            let live_for = allowance
+                .expiration_ledger
+                .saturating_sub(env.ledger().sequence());
+            env.storage().temporary().extend_ttl(&key, live_for, live_for);
+        }
+    }
+
+    // ========== POSITIONS ==========
+
+    pub fn get_position(env: &Env, invoice_id: u64) -> Option<PoolPosition> {
+        env.storage().persistent().get(&DataKey::Position(invoice_id))
+    }
+
+    pub fn set_position(env: &Env, position: &PoolPosition) {
+        Self::set_persistent(env, &DataKey::Position(position.invoice_id), position);
+    }
+
+    // ========== WITHDRAWAL QUEUE ==========
+
+    pub fn get_withdrawal(env: &Env, id: u64) -> Option<WithdrawalRequest> {
+        env.storage().persistent().get(&DataKey::Withdrawal(id))
+    }
+
+    pub fn set_withdrawal(env: &Env, request: &WithdrawalRequest) {
+        Self::set_persistent(env, &DataKey::Withdrawal(request.id), request);
+    }
+
+    pub fn remove_withdrawal(env: &Env, id: u64) {
+        env.storage().persistent().remove(&DataKey::Withdrawal(id));
+    }
+
+    pub fn get_queue_head(env: &Env) -> u64 {
+        env.storage().instance().get(&DataKey::QueueHead).unwrap_or(1)
+    }
+
+    pub fn set_queue_head(env: &Env, id: u64) {
+        env.storage().instance().set(&DataKey::QueueHead, &id);
+    }
+
+    pub fn get_queue_tail(env: &Env) -> u64 {
+        env.storage().instance().get(&DataKey::QueueTail).unwrap_or(1)
+    }
+
+    pub fn set_queue_tail(env: &Env, id: u64) {
+        env.storage().instance().set(&DataKey::QueueTail, &id);
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
@@ -0,0 +1,146 @@
+//! Data types for the Adelanta Pool Contract
+
+use soroban_sdk::{contracttype, Address, String};
+
+/// Pool tranche
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Tranche {
+    /// Paid its target return first and loses last; its shares are the
+    /// pool's SEP-41 token
+    Senior,
+    /// First-loss capital, paid its target return after the senior tranche;
+    /// its shares are not transferable
+    Junior,
+}
+
+/// Pool configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolConfig {
+    /// Admin address, appoints the manager
+    pub admin: Address,
+    /// Manager address, decides which invoices the pool funds
+    pub manager: Address,
+    /// USDC token contract address, the pool's deposit asset
+    pub asset: Address,
+    /// `AdelantaInvoiceContract` address the pool funds invoices through
+    pub invoice_contract: Address,
+    /// Share token name
+    pub name: String,
+    /// Share token symbol
+    pub symbol: String,
+    /// Minimum junior share of tranche assets (basis points), enforced on
+    /// senior deposits and junior withdrawals
+    pub min_junior_bps: u32,
+}
+
+/// Admin-set terms of a tranche
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrancheConfig {
+    /// Annual return the tranche is paid out of pool yield before any excess
+    /// is shared (basis points)
+    pub target_apr_bps: u32,
+    /// Time after a deposit before the depositor may withdraw
+    pub lockup_secs: u64,
+}
+
+/// Book value and shares of a tranche
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrancheState {
+    /// Tranche
+    pub tranche: Tranche,
+    /// USDC value of the tranche
+    pub assets: i128,
+    /// Total share supply, including shares locked in queued withdrawals
+    pub shares: i128,
+    /// Shares locked in queued withdrawals
+    pub queued_shares: i128,
+    /// Target return accrued and not yet paid
+    pub yield_due: i128,
+    /// Losses absorbed and not yet recovered
+    pub losses: i128,
+    /// Time the target return was last accrued
+    pub accrued_at: u64,
+}
+
+/// Pool balance sheet
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolState {
+    /// Idle USDC available for funding and withdrawals
+    pub cash: i128,
+    /// Principal deployed in open invoice positions, at cost
+    pub deployed: i128,
+    /// Senior tranche
+    pub senior: TrancheState,
+    /// Junior tranche
+    pub junior: TrancheState,
+}
+
+/// The pool's position in a funded invoice
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolPosition {
+    /// Invoice ID
+    pub invoice_id: u64,
+    /// USDC contributed to the invoice's advance
+    pub principal: i128,
+    /// Proceeds booked into the pool's cash so far
+    pub collected: i128,
+    /// Whether the invoice reached a final status and the unrecovered
+    /// principal was written off the pool's books
+    pub closed: bool,
+}
+
+/// Split of the income and losses booked by `collect` between tranches
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Waterfall {
+    /// Loss recoveries and yield credited to the senior tranche
+    pub senior_income: i128,
+    /// Loss recoveries and yield credited to the junior tranche
+    pub junior_income: i128,
+    /// Loss charged to the senior tranche
+    pub senior_loss: i128,
+    /// Loss charged to the junior tranche
+    pub junior_loss: i128,
+}
+
+/// A withdrawal waiting for liquidity
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawalRequest {
+    /// Request ID, requests are paid in ID order
+    pub id: u64,
+    /// Share owner, paid when the request is processed
+    pub owner: Address,
+    /// Tranche the shares belong to
+    pub tranche: Tranche,
+    /// Shares locked until the request is paid or cancelled
+    pub shares: i128,
+    /// Time the request was queued
+    pub requested_at: u64,
+}
+
+/// Outcome of a withdrawal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawalResult {
+    /// USDC paid out now, zero if the withdrawal was queued
+    pub assets: i128,
+    /// Queued request ID, if the pool lacked the liquidity to pay now
+    pub request_id: Option<u64>,
+}
+
+/// Share allowance granted by an owner to a spender
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowanceValue {
+    /// Shares the spender may transfer or burn
+    pub amount: i128,
+    /// Last ledger the allowance is valid in
+    pub expiration_ledger: u32,
+}
@@ -0,0 +1,74 @@
+//! Tranche waterfalls for the Adelanta Pool Contract
+//!
+//! `collect` turns each invoice outcome into pool income or loss, which is
+//! split between the tranches:
+//! - Losses are charged to the junior tranche until it is wiped out, then
+//!   to the senior tranche
+//! - Income first restores unrecovered losses, senior first
+//! - It then pays each tranche's accrued target return, senior first
+//! - Any excess goes to the junior tranche, which carries the first loss,
+//!   or to the senior tranche while the junior tranche is empty
+
+use crate::types::{TrancheConfig, TrancheState, Waterfall};
+
+const SECONDS_PER_YEAR: i128 = 365 * 86_400;
+
+/// Accrue a tranche's target return on its assets up to `now`
+pub fn accrue(state: &mut TrancheState, config: &TrancheConfig, now: u64) {
+    let elapsed = now.saturating_sub(state.accrued_at) as i128;
+
+    state.yield_due += (state.assets * config.target_apr_bps as i128 * elapsed) / (10000 * SECONDS_PER_YEAR);
+    state.accrued_at = now;
+}
+
+/// Charge `loss` to the tranches, junior first
+pub fn absorb_loss(senior: &mut TrancheState, junior: &mut TrancheState, loss: i128, split: &mut Waterfall) {
+    let junior_loss = loss.min(junior.assets);
+    let senior_loss = (loss - junior_loss).min(senior.assets);
+
+    junior.assets -= junior_loss;
+    junior.losses += junior_loss;
+    senior.assets -= senior_loss;
+    senior.losses += senior_loss;
+
+    split.junior_loss += junior_loss;
+    split.senior_loss += senior_loss;
+}
+
+/// Credit `income` to the tranches: loss recoveries, then target returns,
+/// then the excess to the junior tranche
+pub fn distribute_income(senior: &mut TrancheState, junior: &mut TrancheState, income: i128, split: &mut Waterfall) {
+    let mut rest = income;
+
+    let senior_recovery = rest.min(senior.losses);
+    senior.losses -= senior_recovery;
+    rest -= senior_recovery;
+
+    let junior_recovery = rest.min(junior.losses);
+    junior.losses -= junior_recovery;
+    rest -= junior_recovery;
+
+    let senior_target = rest.min(senior.yield_due);
+    senior.yield_due -= senior_target;
+    rest -= senior_target;
+
+    let junior_target = rest.min(junior.yield_due);
+    junior.yield_due -= junior_target;
+    rest -= junior_target;
+
+    // Senior is capped at its target return while the first-loss tranche
+    // has capital to keep the upside
+    let (senior_excess, junior_excess) = if junior.assets > 0 || senior.assets == 0 {
+        (0, rest)
+    } else {
+        (rest, 0)
+    };
+
+    let senior_income = senior_recovery + senior_target + senior_excess;
+    let junior_income = junior_recovery + junior_target + junior_excess;
+    senior.assets += senior_income;
+    junior.assets += junior_income;
+
+    split.senior_income += senior_income;
+    split.junior_income += junior_income;
+}
@@ -0,0 +1,1126 @@
+//! Main contract implementation for the Adelanta Risk Engine
+//!
+//! The invoice contract reports every credit event of its invoices here,
+//! asks for a quote when an invoice is created and for the first exposure
+//! limit a funding would breach. Parameters are set by the holders of the
+//! invoice contract's `Admin` and `FeeManager` roles.
+
+use soroban_sdk::{contract, contractimpl, Address, Env, String, Vec};
+
+use crate::errors::RiskError;
+use crate::events::Events;
+use crate::invoice::{Invoice, InvoiceClient, InvoiceStatus, Role};
+use crate::reputation::{self, MAX_RISK_SCORE};
+use crate::storage::Storage;
+use crate::types::{
+    CreditEvent, ExposureKey, ExposureLimits, PayerReputation, PricingQuote, PricingRule, ReputationParams,
+    RiskConfig, SmeReputation, SmeReputationV0,
+};
+
+/// Maximum number of rules in the risk pricing grid
+const MAX_PRICING_RULES: u32 = 50;
+
+#[contract]
+pub struct AdelantaRiskContract;
+
+#[contractimpl]
+impl AdelantaRiskContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the risk engine
+    ///
+    /// # Arguments
+    /// * `invoice_contract` - Invoice contract reporting credit events
+    pub fn initialize(env: Env, invoice_contract: Address) -> Result<(), RiskError> {
+        if Storage::has_config(&env) {
+            return Err(RiskError::AlreadyInitialized);
+        }
+
+        let config = RiskConfig { invoice_contract };
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // RISK PARAMETERS
+    // ============================================================
+
+    /// Replace the risk pricing grid (fee manager only)
+    ///
+    /// New invoices are priced by the first rule matching the SME's risk
+    /// score, country and industry: the invoice is advanced at the rule's
+    /// rate and must carry at least its fee. Invoices no rule matches are
+    /// rejected. An empty grid restores the default advance rate without a
+    /// fee floor.
+    pub fn set_pricing_grid(env: Env, caller: Address, grid: Vec<PricingRule>) -> Result<(), RiskError> {
+        caller.require_auth();
+
+        Self::require_role(&env, Role::FeeManager, &caller)?;
+
+        if grid.len() > MAX_PRICING_RULES {
+            return Err(RiskError::PricingGridTooLarge);
+        }
+        for rule in grid.iter() {
+            if rule.max_advance_bps > 10000 {
+                return Err(RiskError::InvalidAdvancePercentage);
+            }
+            if rule.min_fee_bps > 1000 {
+                return Err(RiskError::InvalidFeePercentage);
+            }
+        }
+
+        Storage::set_pricing_grid(&env, &grid);
+        Storage::extend_instance_ttl(&env);
+
+        Events::pricing_updated(&env, &caller, &grid);
+
+        Ok(())
+    }
+
+    /// Set the exposure limits of an asset (admin only)
+    ///
+    /// Funding that would push the outstanding principal of the invoice's
+    /// SME, payer, country, industry or the whole book above its limit is
+    /// rejected. Lowering a limit does not affect invoices already funded.
+    pub fn set_exposure_limits(
+        env: Env,
+        admin: Address,
+        asset: Address,
+        limits: ExposureLimits,
+    ) -> Result<(), RiskError> {
+        admin.require_auth();
+
+        let invoices = Self::require_role(&env, Role::Admin, &admin)?;
+
+        if invoices.get_asset(&asset).is_none() {
+            return Err(RiskError::AssetNotSupported);
+        }
+        let caps = [
+            limits.max_per_sme,
+            limits.max_per_payer,
+            limits.max_per_country,
+            limits.max_per_industry,
+            limits.max_total,
+        ];
+        if caps.iter().flatten().any(|cap| *cap < 0) {
+            return Err(RiskError::InvalidAmount);
+        }
+
+        Storage::set_exposure_limits(&env, &asset, &limits);
+        Storage::extend_instance_ttl(&env);
+
+        Events::exposure_limits_set(&env, &asset, &limits);
+
+        Ok(())
+    }
+
+    /// Set the SME risk scoring parameters (admin only)
+    ///
+    /// Scores are brought forward under the new parameters the next time
+    /// they are read or updated.
+    pub fn set_reputation_params(env: Env, admin: Address, params: ReputationParams) -> Result<(), RiskError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        let scores = [
+            params.initial_risk_score,
+            params.late_penalty,
+            params.late_day_penalty,
+            params.default_penalty,
+            params.acknowledgement_bonus,
+            params.history_bonus,
+            params.volume_bonus,
+        ];
+        if scores.iter().any(|score| *score > MAX_RISK_SCORE)
+            || params.decay_half_life_secs == 0
+            || params.volume_bonus_threshold < 0
+        {
+            return Err(RiskError::InvalidReputationParams);
+        }
+
+        Storage::set_reputation_params(&env, &params);
+        Storage::extend_instance_ttl(&env);
+
+        Events::reputation_params_set(&env, &admin, &params);
+
+        Ok(())
+    }
+
+    /// Set how much the payer's risk score weighs in quotes against the
+    /// SME's, in basis points (admin only)
+    pub fn set_payer_risk_weight(env: Env, admin: Address, weight_bps: u32) -> Result<(), RiskError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if weight_bps > 10000 {
+            return Err(RiskError::InvalidReputationParams);
+        }
+
+        Storage::set_payer_risk_weight(&env, weight_bps);
+        Storage::extend_instance_ttl(&env);
+
+        Events::payer_risk_weight_set(&env, &admin, weight_bps);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // CREDIT EVENTS
+    // ============================================================
+
+    /// Record a change in an invoice's life (invoice contract only)
+    ///
+    /// `invoice` is the invoice as stored after the change. The first
+    /// contribution books its advance against every exposure dimension;
+    /// payments to lenders, defaults, invalidations and cancellation
+    /// release it. Settlements, defaults,
+    /// acknowledgements and disputes enter the SME's or payer's reputation.
+    pub fn record_event(env: Env, invoice: Invoice, event: CreditEvent) -> Result<(), RiskError> {
+        Self::require_invoice_contract(&env)?;
+
+        let params = Storage::get_reputation_params(&env);
+        let now = env.ledger().timestamp();
+
+        match event {
+            CreditEvent::Created => {
+                let mut reputation = Self::sme_reputation(&env, &params, &invoice.sme);
+                reputation.total_invoices += 1;
+                Storage::set_sme_reputation(&env, &reputation);
+            }
+            CreditEvent::Acknowledged => {
+                let mut reputation = Self::sme_reputation(&env, &params, &invoice.sme);
+                reputation::record_acknowledgement(&mut reputation, &params, now);
+                Storage::set_sme_reputation(&env, &reputation);
+            }
+            CreditEvent::Reserved | CreditEvent::Funded => {
+                Self::set_outstanding(&env, &invoice, Self::principal_outstanding(&invoice));
+            }
+            CreditEvent::Paid => {
+                // Principal repaid to lenders no longer counts toward exposure
+                if Storage::get_invoice_exposure(&env, invoice.id).is_some() {
+                    Self::set_outstanding(&env, &invoice, Self::principal_outstanding(&invoice));
+                }
+                if invoice.status == InvoiceStatus::Settled {
+                    let mut reputation = Self::sme_reputation(&env, &params, &invoice.sme);
+                    reputation::record_settlement(&mut reputation, &params, &invoice, now);
+                    Storage::set_sme_reputation(&env, &reputation);
+
+                    let mut reputation = Self::payer_reputation(&env, &params, &invoice.payer_id);
+                    reputation::record_payment(&mut reputation, &params, &invoice, now);
+                    Storage::set_payer_reputation(&env, &reputation);
+                }
+            }
+            CreditEvent::Defaulted | CreditEvent::Invalidated => {
+                Self::set_outstanding(&env, &invoice, 0);
+
+                let mut reputation = Self::sme_reputation(&env, &params, &invoice.sme);
+                reputation::record_default(&mut reputation, &params, now);
+                Storage::set_sme_reputation(&env, &reputation);
+
+                // An invoice found invalid is not the payer's fault
+                if event == CreditEvent::Defaulted {
+                    let mut reputation = Self::payer_reputation(&env, &params, &invoice.payer_id);
+                    reputation::record_payer_default(&mut reputation, &params, now);
+                    Storage::set_payer_reputation(&env, &reputation);
+                }
+            }
+            CreditEvent::Disputed => {
+                let mut reputation = Self::payer_reputation(&env, &params, &invoice.payer_id);
+                reputation::record_dispute(&mut reputation, &params, now);
+                Storage::set_payer_reputation(&env, &reputation);
+            }
+            CreditEvent::Cancelled => {
+                Self::set_outstanding(&env, &invoice, 0);
+            }
+        }
+        Storage::extend_instance_ttl(&env);
+
+        Ok(())
+    }
+
+    /// Add an SME's history from the invoice contract's initial deployment
+    /// to its reputation (invoice contract only, while migrating)
+    pub fn import_sme_reputation(env: Env, legacy: SmeReputationV0) -> Result<(), RiskError> {
+        Self::require_invoice_contract(&env)?;
+
+        let params = Storage::get_reputation_params(&env);
+        let mut reputation = Self::sme_reputation(&env, &params, &legacy.sme);
+        reputation::record_legacy_history(&mut reputation, &params, &legacy, env.ledger().timestamp());
+        Storage::set_sme_reputation(&env, &reputation);
+        Storage::extend_instance_ttl(&env);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Get the advance rate and minimum fee an SME would get for an invoice
+    /// owed by `payer_id` in `country` and `industry` if created now
+    ///
+    /// The grid is matched on the SME's and the payer's risk scores blended
+    /// by the payer risk weight; with the default weight of 0 that is the
+    /// SME's score alone.
+    pub fn get_quote(
+        env: Env,
+        sme: Address,
+        payer_id: String,
+        country: String,
+        industry: String,
+    ) -> Result<PricingQuote, RiskError> {
+        let params = Storage::get_reputation_params(&env);
+        let now = env.ledger().timestamp();
+        let sme_risk_score = match Storage::get_sme_reputation(&env, &sme) {
+            Some(mut reputation) => {
+                reputation::refresh(&mut reputation, &params, now);
+                reputation.risk_score
+            }
+            None => params.initial_risk_score,
+        };
+        let payer_risk_score = match Storage::get_payer_reputation(&env, &payer_id) {
+            Some(mut reputation) => {
+                reputation::refresh_payer(&mut reputation, &params, now);
+                reputation.risk_score
+            }
+            None => params.initial_risk_score,
+        };
+        let risk_score = reputation::blended_risk_score(
+            sme_risk_score,
+            payer_risk_score,
+            Storage::get_payer_risk_weight(&env),
+        );
+
+        let grid = Storage::get_pricing_grid(&env);
+        if grid.is_empty() {
+            return Ok(PricingQuote {
+                risk_score,
+                sme_risk_score,
+                payer_risk_score,
+                advance_bps: None,
+                min_fee_bps: 0,
+            });
+        }
+
+        grid.iter()
+            .find(|rule| {
+                risk_score <= rule.max_risk_score
+                    && rule.country.as_ref().is_none_or(|code| *code == country)
+                    && rule.industry.as_ref().is_none_or(|code| *code == industry)
+            })
+            .map(|rule| PricingQuote {
+                risk_score,
+                sme_risk_score,
+                payer_risk_score,
+                advance_bps: Some(rule.max_advance_bps),
+                min_fee_bps: rule.min_fee_bps,
+            })
+            .ok_or(RiskError::RiskNotPriced)
+    }
+
+    /// Get the first exposure dimension, in the order SME, payer, country,
+    /// industry and global, whose limit funding the invoice's advance
+    /// would exceed
+    pub fn get_exposure_breach(env: Env, invoice: Invoice) -> Option<ExposureKey> {
+        let limits = Storage::get_exposure_limits(&env, &invoice.asset)?;
+        let caps = [
+            limits.max_per_sme,
+            limits.max_per_payer,
+            limits.max_per_country,
+            limits.max_per_industry,
+            limits.max_total,
+        ];
+        Self::exposure_keys(&invoice)
+            .into_iter()
+            .zip(caps)
+            .find(|(key, cap)| {
+                cap.is_some_and(|cap| {
+                    Storage::get_exposure(&env, &invoice.asset, key) + invoice.advance_amount > cap
+                })
+            })
+            .map(|(key, _)| key)
+    }
+
+    /// Get SME reputation, with the risk score as of now
+    pub fn get_sme_reputation(env: Env, sme: Address) -> Option<SmeReputation> {
+        let mut reputation = Storage::get_sme_reputation(&env, &sme)?;
+        let params = Storage::get_reputation_params(&env);
+        reputation::refresh(&mut reputation, &params, env.ledger().timestamp());
+        Some(reputation)
+    }
+
+    /// Get the SME risk scoring parameters
+    pub fn get_reputation_params(env: Env) -> ReputationParams {
+        Storage::get_reputation_params(&env)
+    }
+
+    /// Get a corporate payer's payment history, with the risk score as of now
+    pub fn get_payer_reputation(env: Env, payer_id: String) -> Option<PayerReputation> {
+        let mut reputation = Storage::get_payer_reputation(&env, &payer_id)?;
+        let params = Storage::get_reputation_params(&env);
+        reputation::refresh_payer(&mut reputation, &params, env.ledger().timestamp());
+        Some(reputation)
+    }
+
+    /// Get the weight of the payer's risk score in quotes (basis points)
+    pub fn get_payer_risk_weight(env: Env) -> u32 {
+        Storage::get_payer_risk_weight(&env)
+    }
+
+    /// Get the risk pricing grid
+    pub fn get_pricing_grid(env: Env) -> Vec<PricingRule> {
+        Storage::get_pricing_grid(&env)
+    }
+
+    /// Get the exposure limits of an asset
+    pub fn get_exposure_limits(env: Env, asset: Address) -> Option<ExposureLimits> {
+        Storage::get_exposure_limits(&env, &asset)
+    }
+
+    /// Get the outstanding principal of funded invoices in an asset along
+    /// an exposure dimension
+    pub fn get_exposure(env: Env, asset: Address, key: ExposureKey) -> i128 {
+        Storage::get_exposure(&env, &asset, &key)
+    }
+
+    /// Get the risk engine configuration
+    pub fn get_config(env: Env) -> Result<RiskConfig, RiskError> {
+        Storage::get_config(&env).ok_or(RiskError::NotInitialized)
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    /// Require `account` to hold `role` on the invoice contract
+    fn require_role<'a>(env: &'a Env, role: Role, account: &Address) -> Result<InvoiceClient<'a>, RiskError> {
+        let config = Storage::get_config(env).ok_or(RiskError::NotInitialized)?;
+        let invoices = InvoiceClient::new(env, &config.invoice_contract);
+        if !invoices.has_role(&role, account) {
+            return Err(RiskError::Unauthorized);
+        }
+        Ok(invoices)
+    }
+
+    /// Require the call to come from the invoice contract
+    fn require_invoice_contract(env: &Env) -> Result<(), RiskError> {
+        let config = Storage::get_config(env).ok_or(RiskError::NotInitialized)?;
+        config.invoice_contract.require_auth();
+        Ok(())
+    }
+
+    fn sme_reputation(env: &Env, params: &ReputationParams, sme: &Address) -> SmeReputation {
+        Storage::get_sme_reputation(env, sme)
+            .unwrap_or_else(|| reputation::new_reputation(sme, params, env.ledger().timestamp()))
+    }
+
+    fn payer_reputation(env: &Env, params: &ReputationParams, payer_id: &String) -> PayerReputation {
+        Storage::get_payer_reputation(env, payer_id)
+            .unwrap_or_else(|| reputation::new_payer_reputation(payer_id, params, env.ledger().timestamp()))
+    }
+
+    /// Advance of a funded invoice not yet repaid to its lenders
+    fn principal_outstanding(invoice: &Invoice) -> i128 {
+        invoice.advance_amount - invoice.lender_paid.min(invoice.advance_amount)
+    }
+
+    /// Exposure dimensions an invoice counts toward, in the order limits
+    /// are checked
+    fn exposure_keys(invoice: &Invoice) -> [ExposureKey; 5] {
+        [
+            ExposureKey::Sme(invoice.sme.clone()),
+            ExposureKey::Payer(invoice.payer_id.clone()),
+            ExposureKey::Country(invoice.country.clone()),
+            ExposureKey::Industry(invoice.industry.clone()),
+            ExposureKey::Global,
+        ]
+    }
+
+    /// Record `outstanding` as the invoice's principal at risk, moving every
+    /// exposure total it counts toward by the difference
+    fn set_outstanding(env: &Env, invoice: &Invoice, outstanding: i128) {
+        let recorded = Storage::get_invoice_exposure(env, invoice.id).unwrap_or(0);
+        let delta = outstanding - recorded;
+        if delta != 0 {
+            for key in Self::exposure_keys(invoice).iter() {
+                let total = Storage::get_exposure(env, &invoice.asset, key);
+                Storage::set_exposure(env, &invoice.asset, key, total + delta);
+            }
+        }
+
+        if outstanding > 0 {
+            Storage::set_invoice_exposure(env, invoice.id, outstanding);
+        } else {
+            Storage::remove_invoice_exposure(env, invoice.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reputation::{ACKNOWLEDGEMENT_RISK_BONUS, INITIAL_RISK_SCORE};
+    use soroban_sdk::{
+        testutils::{Address as _, EnvTestConfig, Ledger},
+        token::{Client as TokenClient, StellarAssetClient},
+        vec, BytesN, IntoVal, Symbol,
+    };
+
+    /// The invoice contract reporting credit events
+    #[allow(clippy::too_many_arguments)]
+    mod adelanta_invoice {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_invoice.wasm"
+        );
+    }
+
+    /// The settlement contract paying out the invoices
+    mod adelanta_settlement {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_settlement.wasm"
+        );
+    }
+    use adelanta_invoice::{
+        AdelantaError, Client as AdelantaInvoiceContractClient, DisputeResolution, FeeModel,
+        Role as InvoiceRole,
+    };
+
+    const DAY: u64 = 86_400;
+
+    struct TestContext {
+        env: Env,
+        client: AdelantaRiskContractClient<'static>,
+        invoices: AdelantaInvoiceContractClient<'static>,
+        settlement: adelanta_settlement::Client<'static>,
+        admin: Address,
+        sme: Address,
+        usdc: TokenClient<'static>,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    fn setup() -> TestContext {
+        setup_with_env(Env::default())
+    }
+
+    /// Risk engine of an invoice contract (90% advance, 0.5% protocol fee)
+    /// at timestamp 1000, with 10,000 USDC minted to the admin for
+    /// settlements
+    fn setup_with_env(env: Env) -> TestContext {
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+
+        let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc = TokenClient::new(&env, &usdc_address);
+        let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let invoice_id = env.register_contract_wasm(None, adelanta_invoice::WASM);
+        let invoices = AdelantaInvoiceContractClient::new(&env, &invoice_id);
+        invoices.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+        let settlement_id = env.register_contract_wasm(None, adelanta_settlement::WASM);
+        let settlement = adelanta_settlement::Client::new(&env, &settlement_id);
+        settlement.initialize(&invoice_id);
+        invoices.grant_role(&admin, &InvoiceRole::Settlement, &settlement_id);
+
+        let contract_id = env.register_contract(None, AdelantaRiskContract);
+        let client = AdelantaRiskContractClient::new(&env, &contract_id);
+        client.initialize(&invoice_id);
+        invoices.set_risk_engine(&admin, &Some(contract_id));
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            client,
+            invoices,
+            settlement,
+            admin,
+            sme,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Fingerprint unique to the next invoice
+    fn next_fingerprint(ctx: &TestContext) -> BytesN<32> {
+        BytesN::from_array(&ctx.env, &[ctx.invoices.get_stats().total_invoices as u8; 32])
+    }
+
+    /// Generate a lender holding 10,000 USDC
+    fn funded_lender(ctx: &TestContext) -> Address {
+        let lender = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&lender, &10000_0000000);
+        lender
+    }
+
+    /// Create a 2% fee invoice for the context's SME owed by CORP-001 in
+    /// Mexico, due at timestamp 2000
+    fn create_test_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        ctx.invoices.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    /// Create a 100 USDC invoice for `sme` at `start`, due 30 days later,
+    /// fund it and settle it in full at `settled_at`
+    fn settle_invoice_at(ctx: &TestContext, sme: &Address, lender: &Address, start: u64, settled_at: u64) {
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = start;
+        });
+        let invoice_id = ctx.invoices.create_invoice(
+            sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &100_0000000,
+            &(start + 30 * DAY),
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        );
+        ctx.invoices.fund_invoice(lender, &invoice_id);
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = settled_at;
+        });
+        ctx.settlement.settle_invoice(&ctx.admin, &invoice_id, &100_0000000, &None);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let ctx = setup();
+        assert_eq!(ctx.client.get_config().invoice_contract, ctx.invoices.address);
+        assert_eq!(
+            ctx.client.try_initialize(&ctx.invoices.address),
+            Err(Ok(RiskError::AlreadyInitialized))
+        );
+        assert_eq!(ctx.invoices.get_risk_engine(), Some(ctx.client.address.clone()));
+    }
+
+    #[test]
+    fn test_credit_events() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let corp = String::from_str(&ctx.env, "CORP-001");
+        let payers = Address::generate(&ctx.env);
+        let arbiter = Address::generate(&ctx.env);
+        ctx.invoices.grant_role(&ctx.admin, &InvoiceRole::Payers, &payers);
+
+        // Only the invoice contract reports credit events
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        let invoice: Invoice = ctx.env.invoke_contract(
+            &ctx.invoices.address,
+            &Symbol::new(&ctx.env, "get_invoice"),
+            vec![&ctx.env, invoice_id.into_val(&ctx.env)],
+        );
+        ctx.env.mock_auths(&[]);
+        assert!(ctx.client.try_record_event(&invoice, &CreditEvent::Paid).is_err());
+        ctx.env.mock_all_auths();
+
+        let reputation = ctx.client.get_sme_reputation(&ctx.sme).unwrap();
+        assert_eq!(reputation.total_invoices, 1);
+        assert_eq!(reputation.risk_score, INITIAL_RISK_SCORE);
+
+        // Fully acknowledged history lowers the SME's risk score
+        ctx.invoices.record_acknowledgement(&payers, &invoice_id);
+        let reputation = ctx.client.get_sme_reputation(&ctx.sme).unwrap();
+        assert_eq!(reputation.acknowledged_invoices, 1);
+        assert_eq!(reputation.risk_score, INITIAL_RISK_SCORE - ACKNOWLEDGEMENT_RISK_BONUS);
+
+        // Disputes count against the payer
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        assert_eq!(ctx.client.get_exposure(&ctx.usdc.address, &ExposureKey::Global), 900_0000000);
+        ctx.invoices.open_dispute(&payers, &invoice_id);
+        assert_eq!(ctx.client.get_payer_reputation(&corp).unwrap().disputed_invoices, 1);
+
+        // An invoice found invalid defaults against the SME only
+        ctx.invoices.close_dispute(&payers, &arbiter, &invoice_id, &DisputeResolution::Invalid);
+        assert_eq!(ctx.client.get_sme_reputation(&ctx.sme).unwrap().defaulted_invoices, 1);
+        let payer_reputation = ctx.client.get_payer_reputation(&corp).unwrap();
+        assert_eq!(payer_reputation.defaulted_invoices, 0);
+        assert_eq!(payer_reputation.disputed_invoices, 1);
+        assert_eq!(ctx.client.get_exposure(&ctx.usdc.address, &ExposureKey::Global), 0);
+    }
+
+    #[test]
+    fn test_risk_pricing_grid() {
+        let ctx = setup();
+        let mx = String::from_str(&ctx.env, "MX");
+        let co = String::from_str(&ctx.env, "CO");
+        let br = String::from_str(&ctx.env, "BR");
+        let design = String::from_str(&ctx.env, "DESIGN");
+        let corp = String::from_str(&ctx.env, "CORP-001");
+
+        // Without a grid the invoice contract's default advance applies
+        // with any fee
+        let quote = ctx.client.get_quote(&ctx.sme, &corp, &mx, &design);
+        assert_eq!(quote.risk_score, INITIAL_RISK_SCORE);
+        assert_eq!(quote.advance_bps, None);
+        assert_eq!(quote.min_fee_bps, 0);
+
+        let rule = |max_risk_score: u32, country: Option<&String>, industry: Option<&String>, max_advance_bps: u32, min_fee_bps: u32| {
+            PricingRule {
+                max_risk_score,
+                country: country.cloned(),
+                industry: industry.cloned(),
+                max_advance_bps,
+                min_fee_bps,
+            }
+        };
+        let grid = vec![
+            &ctx.env,
+            rule(300, None, None, 9500, 150),
+            rule(600, Some(&mx), Some(&design), 8500, 250),
+            rule(600, Some(&co), None, 8000, 300),
+        ];
+
+        let outsider = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_set_pricing_grid(&outsider, &grid),
+            Err(Ok(RiskError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_pricing_grid(&ctx.admin, &vec![&ctx.env, rule(300, None, None, 10001, 150)]),
+            Err(Ok(RiskError::InvalidAdvancePercentage))
+        );
+        ctx.client.set_pricing_grid(&ctx.admin, &grid);
+        assert_eq!(ctx.client.get_pricing_grid(), grid);
+
+        // A medium-risk SME matches the first rule for its country and industry
+        let quote = ctx.client.get_quote(&ctx.sme, &corp, &mx, &design);
+        assert_eq!(quote.advance_bps, Some(8500));
+        assert_eq!(quote.min_fee_bps, 250);
+        let quote = ctx.client.get_quote(&ctx.sme, &corp, &co, &design);
+        assert_eq!(quote.advance_bps, Some(8000));
+        assert_eq!(quote.min_fee_bps, 300);
+        assert_eq!(
+            ctx.client.try_get_quote(&ctx.sme, &corp, &br, &design),
+            Err(Ok(RiskError::RiskNotPriced))
+        );
+
+        // `create_invoice` enforces the quote
+        let create = |fee_bps: u32, country: &String| {
+            ctx.invoices.try_create_invoice(
+                &ctx.sme,
+                &corp,
+                &next_fingerprint(&ctx),
+                &ctx.usdc.address,
+                &1000_0000000,
+                &2000,
+                country,
+                &design,
+                &fee_bps,
+                &FeeModel::Flat,
+            )
+        };
+        assert_eq!(create(200, &mx), Err(Ok(AdelantaError::FeeBelowMinimum)));
+        assert_eq!(create(300, &br), Err(Ok(AdelantaError::RiskNotPriced)));
+
+        let invoice_id = create(250, &mx).unwrap().unwrap();
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.advance_amount, 850_0000000);
+        assert_eq!(invoice.fee_bps, 250);
+
+        // Acknowledgement cannot lift the advance above the grid's
+        let payers = Address::generate(&ctx.env);
+        ctx.invoices.grant_role(&ctx.admin, &InvoiceRole::Payers, &payers);
+        ctx.invoices.update_config(&ctx.admin, &None, &None, &None, &None, &None, &Some(9500));
+        ctx.invoices.record_acknowledgement(&payers, &invoice_id);
+        assert_eq!(ctx.invoices.get_invoice(&invoice_id).advance_amount, 850_0000000);
+
+        // Clearing the grid restores the default pricing
+        ctx.client.set_pricing_grid(&ctx.admin, &Vec::new(&ctx.env));
+        assert_eq!(ctx.client.get_quote(&ctx.sme, &corp, &br, &design).advance_bps, None);
+        assert_eq!(create(0, &br), Ok(Ok(invoice_id + 1)));
+        assert_eq!(ctx.invoices.get_invoice(&(invoice_id + 1)).advance_amount, 900_0000000);
+    }
+
+    #[test]
+    fn test_exposure_limits() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let asset = ctx.usdc.address.clone();
+        let sme_key = ExposureKey::Sme(ctx.sme.clone());
+        let payer_key = ExposureKey::Payer(String::from_str(&ctx.env, "CORP-001"));
+
+        let limits = ExposureLimits {
+            max_per_sme: Some(1500_0000000),
+            max_per_payer: Some(2000_0000000),
+            max_per_country: None,
+            max_per_industry: None,
+            max_total: None,
+        };
+        let outsider = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_set_exposure_limits(&outsider, &asset, &limits),
+            Err(Ok(RiskError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_exposure_limits(
+                &ctx.admin,
+                &asset,
+                &ExposureLimits { max_total: Some(-1), ..limits.clone() }
+            ),
+            Err(Ok(RiskError::InvalidAmount))
+        );
+        assert_eq!(
+            ctx.client.try_set_exposure_limits(&ctx.admin, &Address::generate(&ctx.env), &limits),
+            Err(Ok(RiskError::AssetNotSupported))
+        );
+        ctx.client.set_exposure_limits(&ctx.admin, &asset, &limits);
+        assert_eq!(ctx.client.get_exposure_limits(&asset), Some(limits.clone()));
+
+        // Funding books the advance against every dimension
+        let first_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &first_id);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 900_0000000);
+        assert_eq!(ctx.client.get_exposure(&asset, &payer_key), 900_0000000);
+        assert_eq!(
+            ctx.client.get_exposure(&asset, &ExposureKey::Country(String::from_str(&ctx.env, "MX"))),
+            900_0000000
+        );
+        assert_eq!(ctx.client.get_exposure(&asset, &ExposureKey::Global), 900_0000000);
+
+        let second_id = create_test_invoice(&ctx, 1000_0000000);
+        assert_eq!(
+            ctx.invoices.try_fund_invoice(&lender, &second_id),
+            Err(Ok(AdelantaError::SmeExposureExceeded))
+        );
+
+        // Principal repaid to the lender frees up room
+        ctx.settlement.settle_invoice(&ctx.admin, &first_id, &300_0000000, &None);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 600_0000000);
+        ctx.invoices.fund_invoice(&lender, &second_id);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 1500_0000000);
+
+        // Another SME owed by the same payer hits the payer limit, then the
+        // global one once the payer limit is lifted
+        let other_sme = Address::generate(&ctx.env);
+        let third_id = ctx.invoices.create_invoice(
+            &other_sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(&ctx),
+            &asset,
+            &1000_0000000,
+            &2000,
+            &String::from_str(&ctx.env, "CO"),
+            &String::from_str(&ctx.env, "RETAIL"),
+            &200,
+            &FeeModel::Flat,
+        );
+        assert_eq!(
+            ctx.invoices.try_fund_invoice(&lender, &third_id),
+            Err(Ok(AdelantaError::PayerExposureExceeded))
+        );
+        ctx.client.set_exposure_limits(
+            &ctx.admin,
+            &asset,
+            &ExposureLimits {
+                max_per_payer: None,
+                max_total: Some(2000_0000000),
+                ..limits
+            },
+        );
+        assert_eq!(
+            ctx.invoices.try_fund_invoice(&lender, &third_id),
+            Err(Ok(AdelantaError::GlobalExposureExceeded))
+        );
+
+        // Full settlement and defaults release the remaining principal
+        ctx.settlement.settle_invoice(&ctx.admin, &first_id, &700_0000000, &None);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 900_0000000);
+
+        let grace = ctx.invoices.get_config().grace_period_secs;
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2001 + grace;
+        });
+        ctx.invoices.mark_defaulted(&second_id);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 0);
+        assert_eq!(ctx.client.get_exposure(&asset, &payer_key), 0);
+        assert_eq!(ctx.client.get_exposure(&asset, &ExposureKey::Global), 0);
+    }
+
+    #[test]
+    fn test_exposure_reserved_by_partial_funding() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let asset = ctx.usdc.address.clone();
+        let sme_key = ExposureKey::Sme(ctx.sme.clone());
+
+        let limits = ExposureLimits {
+            max_per_sme: Some(1000_0000000),
+            max_per_payer: None,
+            max_per_country: None,
+            max_per_industry: None,
+            max_total: None,
+        };
+        ctx.client.set_exposure_limits(&ctx.admin, &asset, &limits);
+
+        // The first tranche reserves the whole advance
+        let first_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice_partial(&lender, &first_id, &300_0000000);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 900_0000000);
+
+        // Another invoice cannot start funding against the reserved room
+        let second_id = create_test_invoice(&ctx, 1000_0000000);
+        assert_eq!(
+            ctx.invoices.try_fund_invoice_partial(&lender, &second_id, &100_0000000),
+            Err(Ok(AdelantaError::SmeExposureExceeded))
+        );
+
+        // Later tranches complete the funding even once limits tighten
+        ctx.client.set_exposure_limits(
+            &ctx.admin,
+            &asset,
+            &ExposureLimits { max_per_sme: Some(500_0000000), ..limits.clone() },
+        );
+        ctx.invoices.fund_invoice_partial(&lender, &first_id, &600_0000000);
+        assert_eq!(ctx.invoices.get_invoice(&first_id).status, adelanta_invoice::InvoiceStatus::Funded);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 900_0000000);
+
+        // Cancelling a partially funded invoice releases its reservation
+        ctx.client.set_exposure_limits(
+            &ctx.admin,
+            &asset,
+            &ExposureLimits { max_per_sme: Some(2000_0000000), ..limits },
+        );
+        ctx.invoices.fund_invoice_partial(&lender, &second_id, &100_0000000);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 1800_0000000);
+        ctx.invoices.cancel_invoice(&ctx.sme, &second_id);
+        assert_eq!(ctx.client.get_exposure(&asset, &sme_key), 900_0000000);
+    }
+
+    #[test]
+    fn test_reputation_counters_and_lateness() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let params = ctx.client.get_reputation_params();
+        let outsider = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_set_reputation_params(&outsider, &params),
+            Err(Ok(RiskError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_reputation_params(
+                &ctx.admin,
+                &ReputationParams { decay_half_life_secs: 0, ..params.clone() }
+            ),
+            Err(Ok(RiskError::InvalidReputationParams))
+        );
+
+        // Without decay the score is the plain average of the penalties
+        let no_decay = ReputationParams {
+            decay_half_life_secs: u64::MAX,
+            ..params.clone()
+        };
+        ctx.client.set_reputation_params(&ctx.admin, &no_decay);
+        assert_eq!(ctx.client.get_reputation_params(), no_decay);
+
+        // On time after 20 days, 5 days late and 40 days late
+        settle_invoice_at(&ctx, &ctx.sme, &lender, 1000, 1000 + 20 * DAY);
+        settle_invoice_at(&ctx, &ctx.sme, &lender, 1000 + 30 * DAY, 1000 + 65 * DAY);
+        settle_invoice_at(&ctx, &ctx.sme, &lender, 1000 + 70 * DAY, 1000 + 140 * DAY);
+
+        let reputation = ctx.client.get_sme_reputation(&ctx.sme).unwrap();
+        assert_eq!(reputation.total_invoices, 3);
+        assert_eq!(reputation.settled_invoices, 3);
+        assert_eq!(reputation.on_time_invoices, 1);
+        assert_eq!(reputation.late_invoices, 2);
+        assert_eq!(reputation.defaulted_invoices, 0);
+        assert_eq!(reputation.total_volume, 300_0000000);
+        assert_eq!(reputation.total_settlement_days, 20 + 35 + 70);
+        assert_eq!(reputation.avg_settlement_days, 41);
+        assert_eq!(reputation.total_days_late, 5 + 40);
+        assert_eq!(reputation.on_time_rate_bps, 3333);
+        // (0 + (250 + 5 * 25) + 1000) / 3
+        assert_eq!(reputation.risk_score, 458);
+
+        // Two half-lives later the old invoices weigh a quarter of a new one
+        ctx.client.set_reputation_params(&ctx.admin, &params);
+        let last_settled = 1000 + 140 * DAY;
+        let two_half_lives = 2 * params.decay_half_life_secs;
+        assert_eq!(ctx.client.get_sme_reputation(&ctx.sme).unwrap().risk_score, 458);
+        settle_invoice_at(
+            &ctx,
+            &ctx.sme,
+            &lender,
+            last_settled + two_half_lives - 10 * DAY,
+            last_settled + two_half_lives,
+        );
+        let reputation = ctx.client.get_sme_reputation(&ctx.sme).unwrap();
+        assert_eq!(reputation.on_time_invoices, 2);
+        // 1375 / 4 / (3 / 4 + 1)
+        assert_eq!(reputation.risk_score, 196);
+        assert_eq!(ctx.client.get_quote(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+        ).risk_score, 196);
+    }
+
+    #[test]
+    fn test_reputation_long_history_decays() {
+        // Too many entries to keep a useful ledger snapshot
+        let ctx = setup_with_env(Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        }));
+        let lender = funded_lender(&ctx);
+        let improving = Address::generate(&ctx.env);
+        let worsening = Address::generate(&ctx.env);
+
+        // Two years of monthly invoices: one SME pays 10 days late and then
+        // on time, the other the other way round
+        for month in 0..24u64 {
+            let start = 1000 + month * 30 * DAY;
+            let on_time = start + 20 * DAY;
+            let late = start + 40 * DAY;
+            let (improving_at, worsening_at) = if month < 12 { (late, on_time) } else { (on_time, late) };
+            settle_invoice_at(&ctx, &improving, &lender, start, improving_at);
+            settle_invoice_at(&ctx, &worsening, &lender, start, worsening_at);
+        }
+
+        let now = 1000 + 23 * 30 * DAY + 40 * DAY;
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = now;
+        });
+        let improving = ctx.client.get_sme_reputation(&improving).unwrap();
+        let worsening = ctx.client.get_sme_reputation(&worsening).unwrap();
+
+        // Both have the same lifetime record
+        for reputation in [&improving, &worsening] {
+            assert_eq!(reputation.settled_invoices, 24);
+            assert_eq!(reputation.on_time_invoices, 12);
+            assert_eq!(reputation.late_invoices, 12);
+            assert_eq!(reputation.total_days_late, 12 * 10);
+            assert_eq!(reputation.on_time_rate_bps, 5000);
+        }
+
+        // Undecayed, both would score 500 / 2 less the history bonus; the
+        // recent year dominates instead
+        assert!(improving.risk_score < 50, "{}", improving.risk_score);
+        assert!(worsening.risk_score > 250, "{}", worsening.risk_score);
+    }
+
+    #[test]
+    fn test_reputation_default_penalty_fades() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let half_life = ctx.client.get_reputation_params().decay_half_life_secs;
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        let defaulted_at = 2001 + ctx.invoices.get_config().grace_period_secs;
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = defaulted_at;
+        });
+        ctx.invoices.mark_defaulted(&invoice_id);
+
+        let score_at = |elapsed: u64| {
+            ctx.env.ledger().with_mut(|li| {
+                li.timestamp = defaulted_at + elapsed;
+            });
+            ctx.client.get_sme_reputation(&ctx.sme).unwrap().risk_score
+        };
+        assert_eq!(score_at(0), INITIAL_RISK_SCORE + 200);
+        assert_eq!(score_at(half_life), INITIAL_RISK_SCORE + 100);
+        assert_eq!(score_at(4 * half_life), INITIAL_RISK_SCORE + 12);
+        assert_eq!(ctx.client.get_sme_reputation(&ctx.sme).unwrap().defaulted_invoices, 1);
+    }
+
+    #[test]
+    fn test_payer_reputation_and_blended_quote() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let other_sme = Address::generate(&ctx.env);
+        let corp = String::from_str(&ctx.env, "CORP-001");
+        let mx = String::from_str(&ctx.env, "MX");
+        let design = String::from_str(&ctx.env, "DESIGN");
+
+        let params = ctx.client.get_reputation_params();
+        ctx.client.set_reputation_params(
+            &ctx.admin,
+            &ReputationParams {
+                decay_half_life_secs: u64::MAX,
+                ..params
+            },
+        );
+        assert!(ctx.client.get_payer_reputation(&corp).is_none());
+
+        // The payer pays one SME's invoice on time and another's 10 days late
+        let defaulted_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &defaulted_id);
+        settle_invoice_at(&ctx, &ctx.sme, &lender, 1000, 1000 + 20 * DAY);
+        settle_invoice_at(&ctx, &other_sme, &lender, 1000 + 20 * DAY, 1000 + 60 * DAY);
+
+        let reputation = ctx.client.get_payer_reputation(&corp).unwrap();
+        assert_eq!(reputation.invoices_paid, 2);
+        assert_eq!(reputation.late_invoices, 1);
+        assert_eq!(reputation.total_volume, 200_0000000);
+        assert_eq!(reputation.total_days_past_due, 10);
+        assert_eq!(reputation.avg_days_past_due, 5);
+        assert_eq!(reputation.disputed_invoices, 0);
+        // (0 + (250 + 10 * 25)) / 2
+        assert_eq!(reputation.risk_score, 250);
+
+        // Defaults count against both the payer and the SME
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 61 * DAY;
+        });
+        ctx.invoices.mark_defaulted(&defaulted_id);
+        let reputation = ctx.client.get_payer_reputation(&corp).unwrap();
+        assert_eq!(reputation.defaulted_invoices, 1);
+        assert_eq!(reputation.risk_score, 450);
+
+        // Quotes price the SME alone until the payer is given a weight
+        let quote = ctx.client.get_quote(&ctx.sme, &corp, &mx, &design);
+        assert_eq!(quote.sme_risk_score, 200);
+        assert_eq!(quote.payer_risk_score, 450);
+        assert_eq!(quote.risk_score, 200);
+
+        let outsider = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_set_payer_risk_weight(&outsider, &5000),
+            Err(Ok(RiskError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_set_payer_risk_weight(&ctx.admin, &10001),
+            Err(Ok(RiskError::InvalidReputationParams))
+        );
+        ctx.client.set_payer_risk_weight(&ctx.admin, &5000);
+        assert_eq!(ctx.client.get_payer_risk_weight(), 5000);
+
+        let quote = ctx.client.get_quote(&ctx.sme, &corp, &mx, &design);
+        assert_eq!(quote.risk_score, 325);
+
+        // Payers without history get the initial score
+        let quote = ctx.client.get_quote(&ctx.sme, &String::from_str(&ctx.env, "CORP-002"), &mx, &design);
+        assert_eq!(quote.payer_risk_score, INITIAL_RISK_SCORE);
+        assert_eq!(quote.risk_score, 350);
+    }
+}
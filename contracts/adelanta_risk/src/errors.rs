@@ -0,0 +1,29 @@
+//! Custom error types for the Adelanta Risk Engine Contract
+
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RiskError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Caller lacks the required role on the invoice contract
+    Unauthorized = 3,
+    /// Advance rate above 100%
+    InvalidAdvancePercentage = 4,
+    /// Minimum fee above 10%
+    InvalidFeePercentage = 5,
+    /// Pricing grid has too many rules
+    PricingGridTooLarge = 6,
+    /// Asset is not approved on the invoice contract
+    AssetNotSupported = 7,
+    /// Negative exposure limit
+    InvalidAmount = 8,
+    /// Reputation parameters or payer weight out of range
+    InvalidReputationParams = 9,
+    /// No rule of the pricing grid matches the invoice
+    RiskNotPriced = 10,
+}
@@ -0,0 +1,64 @@
+//! Contract events for the Adelanta Risk Engine Contract
+//!
+//! Events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. Credit events are published by the invoice
+//! contract as the invoice changes; only parameter changes are published
+//! here.
+
+use soroban_sdk::{symbol_short, Address, Env, Symbol, Vec};
+
+use crate::types::{ExposureLimits, PricingRule, ReputationParams, RiskConfig};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const PRICING_UPDATED: Symbol = symbol_short!("pricing");
+pub const EXPOSURE_LIMITS_SET: Symbol = symbol_short!("exp_lim");
+pub const REPUTATION_PARAMS_SET: Symbol = symbol_short!("rep_cfg");
+pub const PAYER_RISK_WEIGHT_SET: Symbol = symbol_short!("payer_wt");
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    /// Topics: `("init", version, invoice_contract)`
+    pub fn initialized(env: &Env, config: &RiskConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.invoice_contract.clone()),
+            config.clone(),
+        );
+    }
+
+    /// Topics: `("pricing", version, caller)`
+    pub fn pricing_updated(env: &Env, caller: &Address, grid: &Vec<PricingRule>) {
+        env.events().publish(
+            (PRICING_UPDATED, EVENTS_VERSION, caller.clone()),
+            grid.clone(),
+        );
+    }
+
+    /// Topics: `("exp_lim", version, asset)`
+    pub fn exposure_limits_set(env: &Env, asset: &Address, limits: &ExposureLimits) {
+        env.events().publish(
+            (EXPOSURE_LIMITS_SET, EVENTS_VERSION, asset.clone()),
+            limits.clone(),
+        );
+    }
+
+    /// Topics: `("rep_cfg", version, caller)`
+    pub fn reputation_params_set(env: &Env, caller: &Address, params: &ReputationParams) {
+        env.events().publish(
+            (REPUTATION_PARAMS_SET, EVENTS_VERSION, caller.clone()),
+            params.clone(),
+        );
+    }
+
+    /// Topics: `("payer_wt", version, caller)`; payload is the weight in basis points
+    pub fn payer_risk_weight_set(env: &Env, caller: &Address, weight_bps: u32) {
+        env.events().publish(
+            (PAYER_RISK_WEIGHT_SET, EVENTS_VERSION, caller.clone()),
+            weight_bps,
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Client for the Adelanta invoice contract
+//!
+//! Declares only the entry points the risk engine calls. Types come from
+//! `adelanta_types`, shared with the invoice contract.
+
+use soroban_sdk::{contractclient, Address, Env};
+
+pub use adelanta_types::{AssetConfig, Invoice, InvoiceStatus, Role};
+
+/// Subset of the `AdelantaInvoiceContract` interface used by the risk engine
+///
+/// The engine only calls it from its own entry points: the invoice
+/// contract calls into the engine while an invoice changes, and contracts
+/// cannot be re-entered.
+#[allow(dead_code)]
+#[contractclient(name = "InvoiceClient")]
+pub trait InvoiceContract {
+    /// Whether `account` holds `role`
+    fn has_role(env: Env, role: Role, account: Address) -> bool;
+
+    /// An approved asset's configuration
+    fn get_asset(env: Env, asset: Address) -> Option<AssetConfig>;
+}
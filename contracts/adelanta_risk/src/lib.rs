@@ -0,0 +1,30 @@
+//! # Adelanta Risk Engine Contract
+//!
+//! A Soroban smart contract scoring and limiting the credit risk taken by
+//! the Adelanta protocol.
+//!
+//! ## Overview
+//! The invoice contract reports each invoice's creation, acknowledgement,
+//! funding, payments, default and disputes. From those the engine keeps
+//! the reputation of every SME and corporate payer, and the principal
+//! outstanding per SME, payer, country and industry. The invoice contract
+//! prices new invoices with the engine's quote and rejects fundings that
+//! would breach an exposure limit.
+//!
+//! ## Key Features
+//! - Time-decayed SME and payer risk scores
+//! - Risk pricing grid of advance rates and minimum fees
+//! - Exposure limits per asset
+//! - Parameters managed by the invoice contract's role holders
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+mod invoice;
+mod reputation;
+
+pub use contract::AdelantaRiskContract;
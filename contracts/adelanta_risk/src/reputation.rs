@@ -0,0 +1,363 @@
+//! SME and payer risk scoring for the Adelanta Risk Engine Contract
+//!
+//! Reputation keeps exact lifetime counters next to time-decayed sums of
+//! the SME's or payer's invoice outcomes. Every outcome enters the decayed sums with a
+//! weight of `WEIGHT_UNIT`, which halves every `decay_half_life_secs`, so
+//! the risk score follows recent behaviour while old lateness and defaults
+//! fade out.
+
+use soroban_sdk::{Address, String};
+
+use crate::invoice::Invoice;
+use crate::types::{PayerReputation, ReputationParams, SmeReputation, SmeReputationV0};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Fixed-point scale of decay factors
+const DECAY_SCALE: i128 = 1_000_000_000;
+
+/// ln 2, scaled by `DECAY_SCALE`
+const LN_2: i128 = 693_147_181;
+
+/// Weight of a fresh invoice outcome in the decayed sums
+pub const WEIGHT_UNIT: i128 = 1_000_000;
+
+/// Highest (worst) risk score
+pub const MAX_RISK_SCORE: u32 = 1000;
+
+/// Risk score of an SME with no settlement history (medium risk)
+pub const INITIAL_RISK_SCORE: u32 = 500;
+
+/// Risk score added per defaulted invoice
+pub const DEFAULT_RISK_PENALTY: u32 = 200;
+
+/// Risk score removed when every invoice of an SME is payer-acknowledged
+pub const ACKNOWLEDGEMENT_RISK_BONUS: u32 = 100;
+
+/// Scoring parameters used until the admin sets others
+pub fn default_params() -> ReputationParams {
+    ReputationParams {
+        initial_risk_score: INITIAL_RISK_SCORE,
+        late_penalty: 250,
+        late_day_penalty: 25,
+        default_penalty: DEFAULT_RISK_PENALTY,
+        decay_half_life_secs: 180 * SECONDS_PER_DAY,
+        acknowledgement_bonus: ACKNOWLEDGEMENT_RISK_BONUS,
+        history_bonus: 100,
+        history_min_invoices: 10,
+        volume_bonus: 100,
+        volume_bonus_threshold: 100000_0000000,
+    }
+}
+
+/// Reputation of an SME without any invoice
+pub fn new_reputation(sme: &Address, params: &ReputationParams, now: u64) -> SmeReputation {
+    SmeReputation {
+        sme: sme.clone(),
+        total_invoices: 0,
+        settled_invoices: 0,
+        on_time_invoices: 0,
+        late_invoices: 0,
+        defaulted_invoices: 0,
+        acknowledged_invoices: 0,
+        total_volume: 0,
+        total_settlement_days: 0,
+        total_days_late: 0,
+        avg_settlement_days: 0,
+        on_time_rate_bps: 10000, // Start at 100%
+        decayed_settled: 0,
+        decayed_lateness: 0,
+        decayed_defaults: 0,
+        decayed_at: now,
+        risk_score: params.initial_risk_score,
+    }
+}
+
+/// Reputation of a payer without any paid invoice
+pub fn new_payer_reputation(payer_id: &String, params: &ReputationParams, now: u64) -> PayerReputation {
+    PayerReputation {
+        payer_id: payer_id.clone(),
+        invoices_paid: 0,
+        late_invoices: 0,
+        defaulted_invoices: 0,
+        disputed_invoices: 0,
+        total_volume: 0,
+        total_days_past_due: 0,
+        avg_days_past_due: 0,
+        decayed_paid: 0,
+        decayed_lateness: 0,
+        decayed_defaults: 0,
+        decayed_at: now,
+        risk_score: params.initial_risk_score,
+    }
+}
+
+/// Record the settlement of `invoice`
+pub fn record_settlement(reputation: &mut SmeReputation, params: &ReputationParams, invoice: &Invoice, now: u64) {
+    decay(reputation, params, now);
+
+    let settled_at = invoice.settled_at.unwrap_or(now);
+    let funded_at = invoice.funded_at.unwrap_or(settled_at);
+    let days_late = days_past_due(invoice, now);
+
+    reputation.settled_invoices += 1;
+    reputation.total_volume += invoice.amount;
+    reputation.total_settlement_days += settled_at.saturating_sub(funded_at) / SECONDS_PER_DAY;
+    if days_late == 0 {
+        reputation.on_time_invoices += 1;
+    } else {
+        reputation.late_invoices += 1;
+        reputation.total_days_late += days_late;
+    }
+
+    reputation.avg_settlement_days = (reputation.total_settlement_days / reputation.settled_invoices as u64) as u32;
+    reputation.on_time_rate_bps = ((reputation.on_time_invoices as u64 * 10000) / reputation.settled_invoices as u64) as u32;
+
+    reputation.decayed_settled += WEIGHT_UNIT;
+    reputation.decayed_lateness += WEIGHT_UNIT * lateness_penalty(params, days_late) as i128;
+
+    reputation.risk_score = risk_score(reputation, params);
+}
+
+/// Record the default of one of the SME's invoices
+pub fn record_default(reputation: &mut SmeReputation, params: &ReputationParams, now: u64) {
+    decay(reputation, params, now);
+
+    reputation.defaulted_invoices += 1;
+    reputation.decayed_defaults += WEIGHT_UNIT;
+
+    reputation.risk_score = risk_score(reputation, params);
+}
+
+/// Record a payer acknowledgement of one of the SME's invoices
+pub fn record_acknowledgement(reputation: &mut SmeReputation, params: &ReputationParams, now: u64) {
+    decay(reputation, params, now);
+
+    reputation.acknowledged_invoices += 1;
+
+    reputation.risk_score = risk_score(reputation, params);
+}
+
+/// Add the history an SME built up under the invoice contract's initial
+/// deployment. The on-time count is recovered from the stored rate and the
+/// days to settlement from their average; days late were never recorded,
+/// so late invoices enter the decayed sums at full weight as of `now`, with
+/// the maximum penalty.
+pub fn record_legacy_history(
+    reputation: &mut SmeReputation,
+    params: &ReputationParams,
+    legacy: &SmeReputationV0,
+    now: u64,
+) {
+    decay(reputation, params, now);
+
+    let on_time_invoices = ((legacy.on_time_rate_bps as u64 * legacy.settled_invoices as u64 + 5000) / 10000)
+        .min(legacy.settled_invoices as u64) as u32;
+    let late_invoices = legacy.settled_invoices - on_time_invoices;
+
+    reputation.total_invoices += legacy.total_invoices;
+    reputation.settled_invoices += legacy.settled_invoices;
+    reputation.on_time_invoices += on_time_invoices;
+    reputation.late_invoices += late_invoices;
+    reputation.total_volume += legacy.total_volume;
+    reputation.total_settlement_days += legacy.avg_settlement_days as u64 * legacy.settled_invoices as u64;
+    if reputation.settled_invoices > 0 {
+        reputation.avg_settlement_days = (reputation.total_settlement_days / reputation.settled_invoices as u64) as u32;
+        reputation.on_time_rate_bps = ((reputation.on_time_invoices as u64 * 10000) / reputation.settled_invoices as u64) as u32;
+    }
+
+    reputation.decayed_settled += WEIGHT_UNIT * legacy.settled_invoices as i128;
+    reputation.decayed_lateness += WEIGHT_UNIT * MAX_RISK_SCORE as i128 * late_invoices as i128;
+
+    reputation.risk_score = risk_score(reputation, params);
+}
+
+/// Bring the decayed sums and risk score forward to `now`
+pub fn refresh(reputation: &mut SmeReputation, params: &ReputationParams, now: u64) {
+    decay(reputation, params, now);
+    reputation.risk_score = risk_score(reputation, params);
+}
+
+/// Record the payer's full payment of `invoice`
+pub fn record_payment(reputation: &mut PayerReputation, params: &ReputationParams, invoice: &Invoice, now: u64) {
+    decay_payer(reputation, params, now);
+
+    let days_late = days_past_due(invoice, now);
+
+    reputation.invoices_paid += 1;
+    reputation.total_volume += invoice.amount;
+    reputation.total_days_past_due += days_late;
+    if days_late > 0 {
+        reputation.late_invoices += 1;
+    }
+    reputation.avg_days_past_due = (reputation.total_days_past_due / reputation.invoices_paid as u64) as u32;
+
+    reputation.decayed_paid += WEIGHT_UNIT;
+    reputation.decayed_lateness += WEIGHT_UNIT * lateness_penalty(params, days_late) as i128;
+
+    reputation.risk_score = payer_risk_score(reputation, params);
+}
+
+/// Record the default of one of the payer's invoices
+pub fn record_payer_default(reputation: &mut PayerReputation, params: &ReputationParams, now: u64) {
+    decay_payer(reputation, params, now);
+
+    reputation.defaulted_invoices += 1;
+    reputation.decayed_defaults += WEIGHT_UNIT;
+
+    reputation.risk_score = payer_risk_score(reputation, params);
+}
+
+/// Record a dispute raised against one of the payer's invoices
+pub fn record_dispute(reputation: &mut PayerReputation, params: &ReputationParams, now: u64) {
+    decay_payer(reputation, params, now);
+
+    reputation.disputed_invoices += 1;
+
+    reputation.risk_score = payer_risk_score(reputation, params);
+}
+
+/// Bring the payer's decayed sums and risk score forward to `now`
+pub fn refresh_payer(reputation: &mut PayerReputation, params: &ReputationParams, now: u64) {
+    decay_payer(reputation, params, now);
+    reputation.risk_score = payer_risk_score(reputation, params);
+}
+
+/// Risk score weighing the payer's score by `payer_weight_bps` against the
+/// SME's
+pub fn blended_risk_score(sme_risk_score: u32, payer_risk_score: u32, payer_weight_bps: u32) -> u32 {
+    let payer_weight = payer_weight_bps.min(10000) as u64;
+    ((sme_risk_score as u64 * (10000 - payer_weight) + payer_risk_score as u64 * payer_weight) / 10000) as u32
+}
+
+/// Whole days, a started day counting in full, `invoice` was settled after
+/// its due date
+fn days_past_due(invoice: &Invoice, now: u64) -> u64 {
+    invoice
+        .settled_at
+        .unwrap_or(now)
+        .saturating_sub(invoice.due_date)
+        .div_ceil(SECONDS_PER_DAY)
+}
+
+/// Penalty of an invoice settled `days_late` days after its due date
+fn lateness_penalty(params: &ReputationParams, days_late: u64) -> u32 {
+    if days_late == 0 {
+        return 0;
+    }
+    let per_day = (params.late_day_penalty as u64).saturating_mul(days_late);
+    (params.late_penalty as u64)
+        .saturating_add(per_day)
+        .min(MAX_RISK_SCORE as u64) as u32
+}
+
+/// Risk score (0-1000, lower is better) from the decayed outcomes and the
+/// lifetime bonuses
+fn risk_score(reputation: &SmeReputation, params: &ReputationParams) -> u32 {
+    let base_score = average_lateness(params, reputation.decayed_settled, reputation.decayed_lateness);
+    let default_penalty = default_penalty(params, reputation.decayed_defaults);
+
+    let volume_bonus = if reputation.total_volume > params.volume_bonus_threshold {
+        params.volume_bonus
+    } else {
+        0
+    };
+    let history_bonus = if reputation.settled_invoices > params.history_min_invoices {
+        params.history_bonus
+    } else {
+        0
+    };
+    let acknowledgement_bonus = (reputation.acknowledged_invoices as u64 * params.acknowledgement_bonus as u64)
+        .checked_div(reputation.total_invoices as u64)
+        .unwrap_or(0) as u32;
+
+    base_score
+        .saturating_sub(volume_bonus.saturating_add(history_bonus).saturating_add(acknowledgement_bonus))
+        .saturating_add(default_penalty)
+        .min(MAX_RISK_SCORE)
+}
+
+/// Risk score (0-1000, lower is better) of a payer from its decayed
+/// outcomes
+fn payer_risk_score(reputation: &PayerReputation, params: &ReputationParams) -> u32 {
+    average_lateness(params, reputation.decayed_paid, reputation.decayed_lateness)
+        .saturating_add(default_penalty(params, reputation.decayed_defaults))
+        .min(MAX_RISK_SCORE)
+}
+
+/// Average lateness penalty of the decayed settlements, or the initial
+/// score without any
+fn average_lateness(params: &ReputationParams, settled: i128, lateness: i128) -> u32 {
+    if settled > 0 {
+        (lateness / settled) as u32
+    } else {
+        params.initial_risk_score
+    }
+}
+
+/// Penalty of the decayed defaults
+fn default_penalty(params: &ReputationParams, defaults: i128) -> u32 {
+    (params.default_penalty as i128 * defaults / WEIGHT_UNIT).min(MAX_RISK_SCORE as i128) as u32
+}
+
+/// Decay the sums by half for every `decay_half_life_secs` elapsed since
+/// they were last brought forward
+fn decay(reputation: &mut SmeReputation, params: &ReputationParams, now: u64) {
+    let factor = DecayFactor::new(params, reputation.decayed_at, now);
+    reputation.decayed_settled = factor.apply(reputation.decayed_settled);
+    reputation.decayed_lateness = factor.apply(reputation.decayed_lateness);
+    reputation.decayed_defaults = factor.apply(reputation.decayed_defaults);
+    reputation.decayed_at = now;
+}
+
+/// Decay the payer's sums like `decay`
+fn decay_payer(reputation: &mut PayerReputation, params: &ReputationParams, now: u64) {
+    let factor = DecayFactor::new(params, reputation.decayed_at, now);
+    reputation.decayed_paid = factor.apply(reputation.decayed_paid);
+    reputation.decayed_lateness = factor.apply(reputation.decayed_lateness);
+    reputation.decayed_defaults = factor.apply(reputation.decayed_defaults);
+    reputation.decayed_at = now;
+}
+
+/// Decay from `decayed_at` to `now`: whole half-lives, then the fraction
+/// of the last one
+struct DecayFactor {
+    halvings: u64,
+    fraction: i128,
+}
+
+impl DecayFactor {
+    fn new(params: &ReputationParams, decayed_at: u64, now: u64) -> Self {
+        let elapsed = now.saturating_sub(decayed_at);
+        let half_life = params.decay_half_life_secs.max(1);
+        DecayFactor {
+            halvings: elapsed / half_life,
+            fraction: half_life_fraction(elapsed % half_life, half_life),
+        }
+    }
+
+    fn apply(&self, value: i128) -> i128 {
+        if self.halvings >= 64 {
+            return 0;
+        }
+        ((value >> self.halvings) * self.fraction) / DECAY_SCALE
+    }
+}
+
+/// `2^(-elapsed / half_life)` for `elapsed` below `half_life`, scaled by
+/// `DECAY_SCALE`, from the series of `e^(-x)` with `x = ln 2 * elapsed / half_life`
+fn half_life_fraction(elapsed: u64, half_life: u64) -> i128 {
+    let x = (LN_2 * elapsed as i128) / half_life as i128;
+
+    let mut factor = DECAY_SCALE;
+    let mut term = DECAY_SCALE;
+    for k in 1..=5 {
+        term = (term * x) / (DECAY_SCALE * k);
+        if k % 2 == 1 {
+            factor -= term;
+        } else {
+            factor += term;
+        }
+    }
+    factor
+}
@@ -0,0 +1,167 @@
+//! Storage keys and helpers for the Adelanta Risk Engine Contract
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, String, Val, Vec};
+
+use crate::reputation;
+use crate::types::{
+    ExposureKey, ExposureLimits, PayerReputation, PricingRule, ReputationParams, RiskConfig, SmeReputation,
+};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Risk engine configuration
+    Config,
+    /// Risk pricing grid
+    PricingGrid,
+    /// Exposure limits per asset
+    ExposureLimits(Address),
+    /// Outstanding principal by (asset, exposure key)
+    Exposure(Address, ExposureKey),
+    /// Outstanding principal an invoice counts toward its exposures
+    InvoiceExposure(u64),
+    /// SME reputation data
+    SmeReputation(Address),
+    /// SME risk scoring parameters
+    ReputationParams,
+    /// Corporate payer reputation by payer ID
+    PayerReputation(String),
+    /// Weight of the payer's risk score in quotes (basis points)
+    PayerRiskWeight,
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<RiskConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &RiskConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== PRICING ==========
+
+    pub fn get_pricing_grid(env: &Env) -> Vec<PricingRule> {
+        env.storage()
+            .instance()
+            .get(&DataKey::PricingGrid)
+            .unwrap_or(Vec::new(env))
+    }
+
+    pub fn set_pricing_grid(env: &Env, grid: &Vec<PricingRule>) {
+        env.storage().instance().set(&DataKey::PricingGrid, grid);
+    }
+
+    // ========== EXPOSURE ==========
+
+    pub fn get_exposure_limits(env: &Env, asset: &Address) -> Option<ExposureLimits> {
+        env.storage()
+            .instance()
+            .get(&DataKey::ExposureLimits(asset.clone()))
+    }
+
+    pub fn set_exposure_limits(env: &Env, asset: &Address, limits: &ExposureLimits) {
+        env.storage()
+            .instance()
+            .set(&DataKey::ExposureLimits(asset.clone()), limits);
+    }
+
+    pub fn get_exposure(env: &Env, asset: &Address, key: &ExposureKey) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Exposure(asset.clone(), key.clone()))
+            .unwrap_or(0i128)
+    }
+
+    pub fn set_exposure(env: &Env, asset: &Address, key: &ExposureKey, amount: i128) {
+        let key = DataKey::Exposure(asset.clone(), key.clone());
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            Self::set_persistent(env, &key, &amount);
+        }
+    }
+
+    pub fn get_invoice_exposure(env: &Env, id: u64) -> Option<i128> {
+        env.storage().persistent().get(&DataKey::InvoiceExposure(id))
+    }
+
+    pub fn set_invoice_exposure(env: &Env, id: u64, amount: i128) {
+        Self::set_persistent(env, &DataKey::InvoiceExposure(id), &amount);
+    }
+
+    pub fn remove_invoice_exposure(env: &Env, id: u64) {
+        env.storage().persistent().remove(&DataKey::InvoiceExposure(id));
+    }
+
+    // ========== SME REPUTATION ==========
+
+    pub fn get_sme_reputation(env: &Env, sme: &Address) -> Option<SmeReputation> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SmeReputation(sme.clone()))
+    }
+
+    pub fn set_sme_reputation(env: &Env, reputation: &SmeReputation) {
+        Self::set_persistent(env, &DataKey::SmeReputation(reputation.sme.clone()), reputation);
+    }
+
+    pub fn get_reputation_params(env: &Env) -> ReputationParams {
+        env.storage()
+            .instance()
+            .get(&DataKey::ReputationParams)
+            .unwrap_or_else(reputation::default_params)
+    }
+
+    pub fn set_reputation_params(env: &Env, params: &ReputationParams) {
+        env.storage().instance().set(&DataKey::ReputationParams, params);
+    }
+
+    // ========== PAYER REPUTATION ==========
+
+    pub fn get_payer_reputation(env: &Env, payer_id: &String) -> Option<PayerReputation> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PayerReputation(payer_id.clone()))
+    }
+
+    pub fn set_payer_reputation(env: &Env, reputation: &PayerReputation) {
+        Self::set_persistent(env, &DataKey::PayerReputation(reputation.payer_id.clone()), reputation);
+    }
+
+    pub fn get_payer_risk_weight(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PayerRiskWeight)
+            .unwrap_or(0u32)
+    }
+
+    pub fn set_payer_risk_weight(env: &Env, weight_bps: u32) {
+        env.storage().instance().set(&DataKey::PayerRiskWeight, &weight_bps);
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    /// Write a persistent record, keeping it alive for the maximum TTL
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
@@ -0,0 +1,226 @@
+//! Data types for the Adelanta Risk Engine Contract
+
+use soroban_sdk::{contracttype, Address, String};
+
+/// Risk engine configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RiskConfig {
+    /// Invoice contract reporting credit events; its admin and fee manager
+    /// roles manage the engine's parameters
+    pub invoice_contract: Address,
+}
+
+/// Change in an invoice's life reported by the invoice contract
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CreditEvent {
+    /// Invoice created
+    Created,
+    /// Payer confirmed the invoice
+    Acknowledged,
+    /// First contribution received, reserving the advance against the
+    /// exposure limits until funding completes or the invoice is cancelled
+    Reserved,
+    /// Advance fully subscribed and released to the SME
+    Funded,
+    /// Payment received, settling the invoice in full or in part
+    Paid,
+    /// Payer failed to pay before the grace period expired
+    Defaulted,
+    /// Funded invoice found invalid in a dispute, defaulting against the
+    /// SME only
+    Invalidated,
+    /// Invoice disputed
+    Disputed,
+    /// Partially funded invoice cancelled, releasing its reserved advance
+    Cancelled,
+}
+
+/// Row of the risk pricing grid
+///
+/// Rules are matched in grid order; the first rule whose risk band, country
+/// and industry match the blended risk score and invoice prices it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PricingRule {
+    /// Highest blended SME and payer risk score the rule applies to
+    /// (0-1000, lower is better)
+    pub max_risk_score: u32,
+    /// Country code the rule is limited to, or any country if unset
+    pub country: Option<String>,
+    /// Industry code the rule is limited to, or any industry if unset
+    pub industry: Option<String>,
+    /// Advance rate granted (basis points)
+    pub max_advance_bps: u32,
+    /// Lowest lender fee accepted (basis points)
+    pub min_fee_bps: u32,
+}
+
+/// Pricing an SME gets for an invoice
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PricingQuote {
+    /// Risk score the quote is based on, blending the SME's and the payer's
+    pub risk_score: u32,
+    /// Risk score of the SME
+    pub sme_risk_score: u32,
+    /// Risk score of the payer
+    pub payer_risk_score: u32,
+    /// Advance rate of the invoice (basis points); unset while no grid is
+    /// set, leaving the invoice contract's default advance
+    pub advance_bps: Option<u32>,
+    /// Lowest `fee_bps` the invoice may be created with
+    pub min_fee_bps: u32,
+}
+
+/// Dimension outstanding principal is tracked and limited along
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExposureKey {
+    /// All funded invoices
+    Global,
+    /// Invoices of an SME
+    Sme(Address),
+    /// Invoices owed by a corporate payer
+    Payer(String),
+    /// Invoices in a country
+    Country(String),
+    /// Invoices in an industry
+    Industry(String),
+}
+
+/// Caps on the outstanding principal of funded invoices in one asset;
+/// unset limits are not enforced
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExposureLimits {
+    /// Maximum outstanding principal per SME
+    pub max_per_sme: Option<i128>,
+    /// Maximum outstanding principal per payer ID
+    pub max_per_payer: Option<i128>,
+    /// Maximum outstanding principal per country
+    pub max_per_country: Option<i128>,
+    /// Maximum outstanding principal per industry
+    pub max_per_industry: Option<i128>,
+    /// Maximum outstanding principal across all invoices
+    pub max_total: Option<i128>,
+}
+
+/// SME reputation data
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SmeReputation {
+    /// SME address
+    pub sme: Address,
+    /// Total invoices created
+    pub total_invoices: u32,
+    /// Successfully settled invoices
+    pub settled_invoices: u32,
+    /// Settled invoices paid by their due date
+    pub on_time_invoices: u32,
+    /// Settled invoices paid after their due date
+    pub late_invoices: u32,
+    /// Invoices that went into default
+    pub defaulted_invoices: u32,
+    /// Invoices acknowledged by their payer
+    pub acknowledged_invoices: u32,
+    /// Total volume processed (USDC)
+    pub total_volume: i128,
+    /// Sum of the days from funding to settlement of settled invoices
+    pub total_settlement_days: u64,
+    /// Sum of the days past due of late invoices
+    pub total_days_late: u64,
+    /// Average days to settlement
+    pub avg_settlement_days: u32,
+    /// On-time payment rate (basis points, 10000 = 100%)
+    pub on_time_rate_bps: u32,
+    /// Time-decayed weight of settled invoices
+    pub decayed_settled: i128,
+    /// Time-decayed sum of settled invoices' lateness penalties
+    pub decayed_lateness: i128,
+    /// Time-decayed weight of defaulted invoices
+    pub decayed_defaults: i128,
+    /// Timestamp the decayed sums were last brought forward to
+    pub decayed_at: u64,
+    /// Current risk score (0-1000, lower is better)
+    pub risk_score: u32,
+}
+
+/// SME reputation as kept by the invoice contract's initial deployment,
+/// handed over by its migration
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SmeReputationV0 {
+    pub sme: Address,
+    pub total_invoices: u32,
+    pub settled_invoices: u32,
+    pub total_volume: i128,
+    pub avg_settlement_days: u32,
+    pub on_time_rate_bps: u32,
+    pub risk_score: u32,
+}
+
+/// Payment history of a corporate payer across all of its invoices
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PayerReputation {
+    /// Corporate payer identifier
+    pub payer_id: String,
+    /// Invoices paid in full
+    pub invoices_paid: u32,
+    /// Paid invoices settled after their due date
+    pub late_invoices: u32,
+    /// Invoices that went into default
+    pub defaulted_invoices: u32,
+    /// Invoices the payer disputed
+    pub disputed_invoices: u32,
+    /// Total amount of the paid invoices
+    pub total_volume: i128,
+    /// Sum of the days past due of paid invoices
+    pub total_days_past_due: u64,
+    /// Average days past due of paid invoices
+    pub avg_days_past_due: u32,
+    /// Time-decayed weight of paid invoices
+    pub decayed_paid: i128,
+    /// Time-decayed sum of paid invoices' lateness penalties
+    pub decayed_lateness: i128,
+    /// Time-decayed weight of defaulted invoices
+    pub decayed_defaults: i128,
+    /// Timestamp the decayed sums were last brought forward to
+    pub decayed_at: u64,
+    /// Current risk score (0-1000, lower is better)
+    pub risk_score: u32,
+}
+
+/// Parameters of SME and payer risk scoring
+///
+/// Settled invoices are scored 0 when paid on time and
+/// `late_penalty + late_day_penalty * days late` (capped at 1000)
+/// otherwise. The risk score is the average of those scores, each invoice
+/// weighted down by half every `decay_half_life_secs`, plus
+/// `default_penalty` per equally decayed default, less the SME bonuses.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReputationParams {
+    /// Risk score of an SME with no settlement history
+    pub initial_risk_score: u32,
+    /// Penalty of a late invoice, regardless of days late
+    pub late_penalty: u32,
+    /// Penalty per day late
+    pub late_day_penalty: u32,
+    /// Risk score added per defaulted invoice
+    pub default_penalty: u32,
+    /// Time for the weight of an invoice outcome to halve
+    pub decay_half_life_secs: u64,
+    /// Risk score removed when every invoice is payer-acknowledged
+    pub acknowledgement_bonus: u32,
+    /// Risk score removed once `history_min_invoices` are settled
+    pub history_bonus: u32,
+    /// Settled invoices above which the history bonus applies
+    pub history_min_invoices: u32,
+    /// Risk score removed once `volume_bonus_threshold` is processed
+    pub volume_bonus: u32,
+    /// Settled volume above which the volume bonus applies
+    pub volume_bonus_threshold: i128,
+}
@@ -0,0 +1,5 @@
+//! Builds the contracts this crate's tests import
+
+fn main() {
+    adelanta_testdata::build(&["adelanta_invoice"]);
+}
@@ -0,0 +1,1633 @@
+//! Main contract implementation for the Adelanta Settlement Contract
+//!
+//! Payments are split here and paid straight out to the lenders, the
+//! treasury and the SME. The invoice contract records each payment when the
+//! settlement contract calls it with its `Settlement` role. Settlers and
+//! anchor keys are managed by the holders of the invoice contract's
+//! `Settler` and `Admin` roles, and settlement pauses with its `Settle`
+//! operation.
+
+use soroban_sdk::{
+    contract, contractimpl, token, xdr::ToXdr, Address, Bytes, BytesN, Env, String,
+};
+
+use adelanta_types::{compliance, fx, interest};
+
+use crate::errors::SettlementError;
+use crate::events::Events;
+use crate::invoice::{ContractConfig, Invoice, InvoiceClient, InvoiceStatus, Operation, Payment, Role};
+use crate::storage::Storage;
+use crate::types::{DepositResult, EscrowDeposit, PaymentAttestation, SettlementConfig, SettlementResult};
+
+/// Maximum age of a payment attestation when it is submitted
+const MAX_ATTESTATION_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[contract]
+pub struct AdelantaSettlementContract;
+
+#[contractimpl]
+impl AdelantaSettlementContract {
+    // ============================================================
+    // INITIALIZATION
+    // ============================================================
+
+    /// Initialize the settlement contract
+    ///
+    /// # Arguments
+    /// * `invoice_contract` - Invoice contract whose invoices are settled
+    pub fn initialize(env: Env, invoice_contract: Address) -> Result<(), SettlementError> {
+        if Storage::has_config(&env) {
+            return Err(SettlementError::AlreadyInitialized);
+        }
+
+        let config = SettlementConfig { invoice_contract };
+
+        Storage::set_config(&env, &config);
+        Storage::extend_instance_ttl(&env);
+
+        Events::initialized(&env, &config);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // SETTLEMENT
+    // ============================================================
+
+    /// Settle a payment from the corporate payer (settler only)
+    ///
+    /// Payments on fiat-denominated invoices are received in the invoice
+    /// currency and converted into the asset by the settler, which passes
+    /// both amounts. The rate they imply must be within the slippage
+    /// tolerance of the oracle rate.
+    ///
+    /// # Arguments
+    /// * `caller` - Must hold the `Settler` role (Anchor callback)
+    /// * `invoice_id` - Invoice to settle
+    /// * `settlement_amount` - Amount of this payment from the corporate payer,
+    ///   in the invoice's asset
+    /// * `fiat_amount` - Amount received in the invoice currency that
+    ///   `settlement_amount` was converted from; required for fiat-denominated
+    ///   invoices
+    pub fn settle_invoice(
+        env: Env,
+        caller: Address,
+        invoice_id: u64,
+        settlement_amount: i128,
+        fiat_amount: Option<i128>,
+    ) -> Result<SettlementResult, SettlementError> {
+        // Only settlers (Anchors) can settle
+        caller.require_auth();
+
+        let invoices = Self::require_role(&env, Role::Settler, &caller)?;
+        Self::require_not_paused(&invoices)?;
+
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+
+        // Check invoice is funded
+        Self::require_settleable(&invoice)?;
+
+        // Validate settlement amount
+        if settlement_amount <= 0 {
+            return Err(SettlementError::InvalidAmount);
+        }
+
+        if invoice.currency.is_some() && fiat_amount.is_none() {
+            return Err(SettlementError::InvalidAmount);
+        }
+        let (face_value_paid, fx_rate) =
+            Self::face_value_paid(&env, &invoices, &invoice, settlement_amount, fiat_amount)?;
+
+        Self::apply_payment(&env, &invoices, &caller, invoice, settlement_amount, face_value_paid, fx_rate)
+    }
+
+    /// Settle a payment proven by an anchor-signed attestation
+    ///
+    /// Instead of trusting a settler's amount, the asset amount comes from an
+    /// attestation signed with a registered anchor key. `payer` provides the
+    /// asset, which is split through the usual waterfall. Each anchor
+    /// reference can be used once, and the attestation must be less than
+    /// 7 days old. An invalid signature fails the call. For fiat-denominated
+    /// invoices, the rate implied by the attested amounts must be within the
+    /// slippage tolerance of the oracle rate.
+    ///
+    /// # Arguments
+    /// * `payer` - Account paying the attested asset amount (e.g. the anchor)
+    /// * `attestation` - Signed payment details
+    /// * `public_key` - Registered anchor key that signed the attestation
+    /// * `signature` - ed25519 signature over the XDR of `(contract_address, attestation)`
+    pub fn settle_with_attestation(
+        env: Env,
+        payer: Address,
+        attestation: PaymentAttestation,
+        public_key: BytesN<32>,
+        signature: BytesN<64>,
+    ) -> Result<SettlementResult, SettlementError> {
+        payer.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        Self::require_not_paused(&invoices)?;
+
+        if !Storage::is_anchor_key(&env, &public_key) {
+            return Err(SettlementError::AnchorKeyNotRegistered);
+        }
+        if Storage::has_attestation(&env, &attestation.anchor_reference) {
+            return Err(SettlementError::AttestationReplayed);
+        }
+        let now = env.ledger().timestamp();
+        if attestation.timestamp > now || now - attestation.timestamp > MAX_ATTESTATION_AGE_SECS {
+            return Err(SettlementError::AttestationExpired);
+        }
+        if attestation.asset_amount <= 0 {
+            return Err(SettlementError::InvalidAmount);
+        }
+
+        env.crypto().ed25519_verify(
+            &public_key,
+            &Self::attestation_message(&env, &attestation),
+            &signature,
+        );
+
+        let invoice = Self::get_invoice(&invoices, attestation.invoice_id)?;
+        Self::require_settleable(&invoice)?;
+
+        let (face_value_paid, fx_rate) = Self::face_value_paid(
+            &env,
+            &invoices,
+            &invoice,
+            attestation.asset_amount,
+            Some(attestation.fiat_amount),
+        )?;
+
+        Storage::set_attestation(&env, &attestation);
+        Events::payment_attested(&env, &attestation, &public_key);
+
+        Self::apply_payment(
+            &env,
+            &invoices,
+            &payer,
+            invoice,
+            attestation.asset_amount,
+            face_value_paid,
+            fx_rate,
+        )
+    }
+
+    /// Pay an invoice directly into the contract (called by Anchor or payer)
+    ///
+    /// The payment is transferred into the contract and split out from its
+    /// own balance, so no trusted intermediary has to hold the funds.
+    /// Deposits against invoices that cannot take a payment (unknown, not yet
+    /// funded, cancelled, already settled or in another asset) are held in
+    /// escrow instead.
+    ///
+    /// # Arguments
+    /// * `payer` - Depositing address (must be the caller)
+    /// * `invoice_id` - Invoice the payment is for
+    /// * `asset` - Approved asset deposited
+    /// * `amount` - Amount deposited
+    pub fn deposit_payment(
+        env: Env,
+        payer: Address,
+        invoice_id: u64,
+        asset: Address,
+        amount: i128,
+    ) -> Result<DepositResult, SettlementError> {
+        payer.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        Self::require_not_paused(&invoices)?;
+
+        if invoices.get_asset(&asset).is_none() {
+            return Err(SettlementError::AssetNotSupported);
+        }
+        if amount <= 0 {
+            return Err(SettlementError::InvalidAmount);
+        }
+
+        let contract_address = env.current_contract_address();
+        let token_client = token::Client::new(&env, &asset);
+        token_client.transfer(&payer, &contract_address, &amount);
+
+        let payable = Self::get_payable_invoice(&invoices, invoice_id)
+            .filter(|invoice| invoice.asset == asset);
+        match payable {
+            Some(invoice) => {
+                let (face_value_paid, fx_rate) = Self::face_value_paid(&env, &invoices, &invoice, amount, None)?;
+                let result = Self::apply_payment(
+                    &env,
+                    &invoices,
+                    &contract_address,
+                    invoice,
+                    amount,
+                    face_value_paid,
+                    fx_rate,
+                )?;
+                Ok(DepositResult::Applied(result))
+            }
+            None => {
+                let deposit = EscrowDeposit {
+                    id: Storage::next_escrow_id(&env),
+                    depositor: payer,
+                    invoice_id,
+                    asset,
+                    amount,
+                    deposited_at: env.ledger().timestamp(),
+                };
+                Storage::set_escrow(&env, &deposit);
+                Storage::extend_instance_ttl(&env);
+
+                Events::deposit_escrowed(&env, &deposit);
+
+                Ok(DepositResult::Escrowed(deposit.id))
+            }
+        }
+    }
+
+    /// Apply an escrowed deposit to an invoice (settler only)
+    ///
+    /// Used once the intended invoice is funded, or to correct a deposit
+    /// made against the wrong invoice ID.
+    pub fn match_deposit(
+        env: Env,
+        caller: Address,
+        escrow_id: u64,
+        invoice_id: u64,
+    ) -> Result<SettlementResult, SettlementError> {
+        caller.require_auth();
+
+        let invoices = Self::require_role(&env, Role::Settler, &caller)?;
+        Self::require_not_paused(&invoices)?;
+
+        let mut deposit = Storage::get_escrow(&env, escrow_id).ok_or(SettlementError::EscrowNotFound)?;
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+        if !Self::is_payable(&invoice) {
+            return Err(match invoice.status {
+                InvoiceStatus::Settled => SettlementError::InvoiceAlreadySettled,
+                InvoiceStatus::Disputed => SettlementError::InvoiceDisputed,
+                _ => SettlementError::InvoiceNotFunded,
+            });
+        }
+        if deposit.asset != invoice.asset {
+            return Err(SettlementError::AssetMismatch);
+        }
+
+        Storage::remove_escrow(&env, escrow_id);
+        deposit.invoice_id = invoice_id;
+        Events::escrow_matched(&env, &deposit);
+
+        let (face_value_paid, fx_rate) = Self::face_value_paid(&env, &invoices, &invoice, deposit.amount, None)?;
+        let contract_address = env.current_contract_address();
+        Self::apply_payment(
+            &env,
+            &invoices,
+            &contract_address,
+            invoice,
+            deposit.amount,
+            face_value_paid,
+            fx_rate,
+        )
+    }
+
+    /// Return an escrowed deposit to its depositor (depositor or settler)
+    pub fn refund_deposit(env: Env, caller: Address, escrow_id: u64) -> Result<(), SettlementError> {
+        caller.require_auth();
+
+        let invoices = Self::invoice_client(&env)?;
+        let deposit = Storage::get_escrow(&env, escrow_id).ok_or(SettlementError::EscrowNotFound)?;
+
+        if caller != deposit.depositor && !invoices.has_role(&Role::Settler, &caller) {
+            return Err(SettlementError::Unauthorized);
+        }
+
+        Storage::remove_escrow(&env, escrow_id);
+
+        let token_client = token::Client::new(&env, &deposit.asset);
+        compliance::pay_out(
+            &env,
+            &invoices.get_compliance_registry(),
+            &token_client,
+            &env.current_contract_address(),
+            &deposit.depositor,
+            deposit.invoice_id,
+            deposit.amount,
+        );
+
+        Events::escrow_refunded(&env, &deposit);
+
+        Ok(())
+    }
+
+    /// Distribute a late recovery on a defaulted or written-off invoice
+    /// (settler only)
+    ///
+    /// Recoveries use the same waterfall as settlement, so the lender is
+    /// made whole first. Once the full amount is recovered the invoice
+    /// becomes `Settled`.
+    pub fn recover_invoice(
+        env: Env,
+        caller: Address,
+        invoice_id: u64,
+        recovery_amount: i128,
+    ) -> Result<SettlementResult, SettlementError> {
+        caller.require_auth();
+
+        let invoices = Self::require_role(&env, Role::Settler, &caller)?;
+        Self::require_not_paused(&invoices)?;
+
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+        if !matches!(
+            invoice.status,
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff
+        ) {
+            return Err(SettlementError::InvoiceNotDefaulted);
+        }
+
+        if recovery_amount <= 0 {
+            return Err(SettlementError::InvalidAmount);
+        }
+
+        let (face_value_paid, fx_rate) = Self::face_value_paid(&env, &invoices, &invoice, recovery_amount, None)?;
+        Self::apply_payment(&env, &invoices, &caller, invoice, recovery_amount, face_value_paid, fx_rate)
+    }
+
+    // ============================================================
+    // ANCHOR KEYS
+    // ============================================================
+
+    /// Register an anchor ed25519 key for payment attestations (admin only)
+    pub fn register_anchor_key(env: Env, admin: Address, public_key: BytesN<32>) -> Result<(), SettlementError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        Storage::set_anchor_key(&env, &public_key);
+
+        Events::anchor_key_set(&env, &public_key, true);
+
+        Ok(())
+    }
+
+    /// Revoke an anchor key (admin only)
+    pub fn revoke_anchor_key(env: Env, admin: Address, public_key: BytesN<32>) -> Result<(), SettlementError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if !Storage::is_anchor_key(&env, &public_key) {
+            return Err(SettlementError::AnchorKeyNotRegistered);
+        }
+        Storage::remove_anchor_key(&env, &public_key);
+
+        Events::anchor_key_set(&env, &public_key, false);
+
+        Ok(())
+    }
+
+    /// Replace an anchor key with a new one in a single step (admin only)
+    pub fn rotate_anchor_key(
+        env: Env,
+        admin: Address,
+        old_key: BytesN<32>,
+        new_key: BytesN<32>,
+    ) -> Result<(), SettlementError> {
+        admin.require_auth();
+
+        Self::require_role(&env, Role::Admin, &admin)?;
+
+        if !Storage::is_anchor_key(&env, &old_key) {
+            return Err(SettlementError::AnchorKeyNotRegistered);
+        }
+        Storage::remove_anchor_key(&env, &old_key);
+        Storage::set_anchor_key(&env, &new_key);
+
+        Events::anchor_key_set(&env, &old_key, false);
+        Events::anchor_key_set(&env, &new_key, true);
+
+        Ok(())
+    }
+
+    // ============================================================
+    // VIEW FUNCTIONS
+    // ============================================================
+
+    /// Check whether an anchor key is registered
+    pub fn is_anchor_key(env: Env, public_key: BytesN<32>) -> bool {
+        Storage::is_anchor_key(&env, &public_key)
+    }
+
+    /// Get the attestation applied under an anchor reference
+    pub fn get_attestation(env: Env, anchor_reference: String) -> Option<PaymentAttestation> {
+        Storage::get_attestation(&env, &anchor_reference)
+    }
+
+    /// Get an escrowed payer deposit
+    pub fn get_escrow(env: Env, escrow_id: u64) -> Option<EscrowDeposit> {
+        Storage::get_escrow(&env, escrow_id)
+    }
+
+    /// Get the settlement contract configuration
+    pub fn get_config(env: Env) -> Result<SettlementConfig, SettlementError> {
+        Storage::get_config(&env).ok_or(SettlementError::NotInitialized)
+    }
+
+    /// Calculate expected settlement distribution
+    ///
+    /// Runs the settlement waterfall on `settlement_amount`, the cumulative
+    /// amount received from the payer, with the lender fee accrued as of
+    /// `timestamp`, and returns the cumulative
+    /// `(lender_amount, sme_amount, protocol_fee)` it would be split into.
+    pub fn preview_settlement(
+        env: Env,
+        invoice_id: u64,
+        settlement_amount: i128,
+        timestamp: u64,
+    ) -> Result<(i128, i128, i128), SettlementError> {
+        let invoices = Self::invoice_client(&env)?;
+        let invoice = Self::get_invoice(&invoices, invoice_id)?;
+
+        let lender_due = invoice.advance_amount + interest::lender_fee(&invoice, timestamp);
+        let protocol_due = Self::protocol_fee_due(&invoices.get_config(), &invoice);
+
+        Ok(Self::settlement_waterfall(settlement_amount.max(0), lender_due, protocol_due))
+    }
+
+    // ============================================================
+    // INTERNAL HELPERS
+    // ============================================================
+
+    fn invoice_client(env: &Env) -> Result<InvoiceClient<'_>, SettlementError> {
+        let config = Storage::get_config(env).ok_or(SettlementError::NotInitialized)?;
+        Ok(InvoiceClient::new(env, &config.invoice_contract))
+    }
+
+    /// Require `account` to hold `role` on the invoice contract
+    fn require_role<'a>(env: &'a Env, role: Role, account: &Address) -> Result<InvoiceClient<'a>, SettlementError> {
+        let invoices = Self::invoice_client(env)?;
+        if !invoices.has_role(&role, account) {
+            return Err(SettlementError::Unauthorized);
+        }
+        Ok(invoices)
+    }
+
+    fn require_not_paused(invoices: &InvoiceClient) -> Result<(), SettlementError> {
+        if invoices.is_paused(&Operation::Settle) {
+            return Err(SettlementError::Paused);
+        }
+        Ok(())
+    }
+
+    fn get_invoice(invoices: &InvoiceClient, invoice_id: u64) -> Result<Invoice, SettlementError> {
+        match invoices.try_get_invoice(&invoice_id) {
+            Ok(Ok(invoice)) => Ok(invoice),
+            _ => Err(SettlementError::InvoiceNotFound),
+        }
+    }
+
+    /// Load an invoice that can take a payment, if there is one
+    fn get_payable_invoice(invoices: &InvoiceClient, invoice_id: u64) -> Option<Invoice> {
+        Self::get_invoice(invoices, invoice_id)
+            .ok()
+            .filter(Self::is_payable)
+    }
+
+    /// Whether an invoice can take a payment (funded and not yet fully
+    /// paid, including defaulted invoices awaiting recovery)
+    fn is_payable(invoice: &Invoice) -> bool {
+        matches!(
+            invoice.status,
+            InvoiceStatus::Funded
+                | InvoiceStatus::PartiallySettled
+                | InvoiceStatus::Defaulted
+                | InvoiceStatus::WrittenOff
+        )
+    }
+
+    /// Check an invoice can take a regular settlement payment
+    fn require_settleable(invoice: &Invoice) -> Result<(), SettlementError> {
+        match invoice.status {
+            InvoiceStatus::Funded | InvoiceStatus::PartiallySettled => Ok(()),
+            InvoiceStatus::Settled => Err(SettlementError::InvoiceAlreadySettled),
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff => {
+                Err(SettlementError::InvoiceDefaulted)
+            }
+            InvoiceStatus::Disputed => Err(SettlementError::InvoiceDisputed),
+            _ => Err(SettlementError::InvoiceNotFunded),
+        }
+    }
+
+    /// Face value in the invoice's currency a payment of `amount` covers,
+    /// and the FX rate it was paid at
+    ///
+    /// A fiat amount given with the payment is checked against the oracle
+    /// rate; without one the payment is converted at the oracle rate.
+    /// Payments on invoices denominated in their asset cover their amount.
+    fn face_value_paid(
+        env: &Env,
+        invoices: &InvoiceClient,
+        invoice: &Invoice,
+        amount: i128,
+        fiat_amount: Option<i128>,
+    ) -> Result<(i128, Option<i128>), SettlementError> {
+        let Some(currency) = invoice.currency.clone() else {
+            return Ok((amount, None));
+        };
+
+        let fx_config = invoices.get_fx_config().ok_or(SettlementError::OracleNotConfigured)?;
+        let oracle_rate = fx::rate(env, &fx_config, &currency, &invoice.asset)?;
+        let (rate, face_value_paid) = match fiat_amount {
+            Some(fiat_amount) => {
+                if fiat_amount <= 0 {
+                    return Err(SettlementError::InvalidAmount);
+                }
+                let rate = (amount * fx::FX_RATE_SCALE) / fiat_amount;
+                if !fx::within_slippage(&fx_config, oracle_rate, rate) {
+                    return Err(SettlementError::FxSlippageExceeded);
+                }
+                (rate, fiat_amount)
+            }
+            None => (oracle_rate, fx::to_currency(amount, oracle_rate)),
+        };
+
+        Ok((face_value_paid, Some(rate)))
+    }
+
+    /// Message an anchor signs for an attestation, bound to this contract
+    fn attestation_message(env: &Env, attestation: &PaymentAttestation) -> Bytes {
+        (env.current_contract_address(), attestation.clone()).to_xdr(env)
+    }
+
+    /// Run a payment from `payer` through the settlement waterfall, have the
+    /// invoice contract record it and pay out lenders, treasury and SME.
+    /// Used for regular settlement tranches and for recoveries on defaulted
+    /// invoices.
+    fn apply_payment(
+        env: &Env,
+        invoices: &InvoiceClient,
+        payer: &Address,
+        invoice: Invoice,
+        amount: i128,
+        face_value_paid: i128,
+        fx_rate: Option<i128>,
+    ) -> Result<SettlementResult, SettlementError> {
+        let lender = invoice.lender.clone().ok_or(SettlementError::InvoiceNotFunded)?;
+        let is_recovery = matches!(
+            invoice.status,
+            InvoiceStatus::Defaulted | InvoiceStatus::WrittenOff
+        );
+        let config = invoices.get_config();
+
+        // This payment covers whatever the lenders and treasury are still
+        // owed, with the lender fee accrued up to now
+        let lender_fee = interest::lender_fee(&invoice, env.ledger().timestamp());
+        let lender_due = invoice.advance_amount + lender_fee;
+        let protocol_due = Self::protocol_fee_due(&config, &invoice);
+        let (lender_amount, sme_amount, protocol_fee) = Self::settlement_waterfall(
+            amount,
+            (lender_due - invoice.lender_paid).max(0),
+            (protocol_due - invoice.protocol_fee_paid).max(0),
+        );
+
+        // The invoice contract updates the amounts paid and the status;
+        // defaulted invoices keep their status until the full amount has
+        // been recovered
+        let invoice = invoices.record_payment(
+            &env.current_contract_address(),
+            &invoice.id,
+            &Payment {
+                amount,
+                face_value_paid,
+                fx_rate,
+                lender_amount,
+                protocol_fee,
+            },
+        );
+
+        // Transfer from payer (who has received the invoice asset from Anchor)
+        let token_client = token::Client::new(env, &invoice.asset);
+        let registry = invoices.get_compliance_registry();
+
+        // Pay lenders pro rata to their contributions
+        if lender_amount > 0 {
+            Self::distribute_to_lenders(env, invoices, &registry, &token_client, payer, &invoice, lender_amount);
+        }
+
+        // Pay protocol fee to treasury
+        if protocol_fee > 0 {
+            token_client.transfer(payer, &config.treasury, &protocol_fee);
+        }
+
+        // Pay SME remainder
+        if sme_amount > 0 {
+            compliance::pay_out(env, &registry, &token_client, payer, &invoice.sme, invoice.id, sme_amount);
+        }
+
+        Storage::extend_instance_ttl(env);
+
+        let result = SettlementResult {
+            invoice_id: invoice.id,
+            lender_amount,
+            sme_amount,
+            protocol_fee,
+            timestamp: env.ledger().timestamp(),
+        };
+
+        let fully_settled = invoice.status == InvoiceStatus::Settled;
+        if is_recovery {
+            Events::invoice_recovered(env, &invoice, &lender, amount, &result);
+        }
+        if fully_settled {
+            Events::invoice_settled(env, &invoice, &lender, amount, &result);
+        } else if !is_recovery {
+            Events::invoice_partially_settled(env, &invoice, &lender, amount, &result);
+        }
+
+        Ok(result)
+    }
+
+    /// Split an amount into `(lender, sme, protocol_fee)`: the lender's
+    /// outstanding principal + fee is covered first, then the outstanding
+    /// protocol fee, and the SME gets whatever is left
+    fn settlement_waterfall(
+        amount: i128,
+        lender_outstanding: i128,
+        protocol_outstanding: i128,
+    ) -> (i128, i128, i128) {
+        let lender_amount = amount.min(lender_outstanding);
+        let protocol_fee = (amount - lender_amount).min(protocol_outstanding);
+        let sme_amount = amount - lender_amount - protocol_fee;
+
+        (lender_amount, sme_amount, protocol_fee)
+    }
+
+    /// Protocol fee on the total invoice amount
+    fn protocol_fee_due(config: &ContractConfig, invoice: &Invoice) -> i128 {
+        (invoice.amount * config.protocol_fee_bps as i128) / 10000
+    }
+
+    /// Split the lender portion of a settlement across contributors pro rata.
+    /// The last contributor absorbs rounding dust.
+    fn distribute_to_lenders(
+        env: &Env,
+        invoices: &InvoiceClient,
+        registry: &Option<Address>,
+        token_client: &token::Client,
+        from: &Address,
+        invoice: &Invoice,
+        lender_amount: i128,
+    ) {
+        let contributions = invoices.get_invoice_lenders(&invoice.id);
+        let last = contributions.len() - 1;
+        let mut distributed = 0i128;
+
+        for (index, contribution) in contributions.iter().enumerate() {
+            let share = if index as u32 == last {
+                lender_amount - distributed
+            } else {
+                (lender_amount * contribution.amount) / invoice.advance_amount
+            };
+            distributed += share;
+            if share > 0 {
+                compliance::pay_out(env, registry, token_client, from, &contribution.lender, invoice.id, share);
+            }
+        }
+    }
+}
+
+// ============================================================
+// TESTS
+// ============================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::EVENTS_VERSION;
+    use adelanta_types::fx::{OracleAsset, PriceData, PriceOracle};
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::{
+        contract, symbol_short,
+        testutils::{Address as _, Events as _, Ledger},
+        token::{Client as TokenClient, StellarAssetClient},
+        vec, IntoVal, Val, Vec,
+    };
+
+    /// The invoice contract whose invoices are settled
+    #[allow(clippy::too_many_arguments)]
+    mod adelanta_invoice {
+        soroban_sdk::contractimport!(
+            file = "../target/testdata/wasm32-unknown-unknown/release/adelanta_invoice.wasm"
+        );
+    }
+    use adelanta_invoice::{
+        AdelantaError, Client as AdelantaInvoiceContractClient, DayCount, FaceValue, FeeModel, InterestTerms,
+        Operation as InvoiceOperation, Role as InvoiceRole,
+    };
+
+    const DAY: u64 = 86_400;
+
+    struct TestContext {
+        env: Env,
+        contract_id: Address,
+        client: AdelantaSettlementContractClient<'static>,
+        invoices: AdelantaInvoiceContractClient<'static>,
+        admin: Address,
+        treasury: Address,
+        sme: Address,
+        usdc: TokenClient<'static>,
+        usdc_admin: StellarAssetClient<'static>,
+    }
+
+    /// Settlement contract of an invoice contract (90% advance, 0.5%
+    /// protocol fee) at timestamp 1000, with 10,000 USDC minted to the admin
+    /// for settlements
+    fn setup() -> TestContext {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let treasury = Address::generate(&env);
+        let sme = Address::generate(&env);
+
+        let usdc_address = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        let usdc = TokenClient::new(&env, &usdc_address);
+        let usdc_admin = StellarAssetClient::new(&env, &usdc_address);
+        usdc_admin.mint(&admin, &10000_0000000);
+
+        let invoice_id = env.register_contract_wasm(None, adelanta_invoice::WASM);
+        let invoices = AdelantaInvoiceContractClient::new(&env, &invoice_id);
+        invoices.initialize(&admin, &usdc_address, &treasury, &9000, &50);
+
+        let contract_id = env.register_contract(None, AdelantaSettlementContract);
+        let client = AdelantaSettlementContractClient::new(&env, &contract_id);
+        client.initialize(&invoice_id);
+        invoices.grant_role(&admin, &InvoiceRole::Settlement, &contract_id);
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1000;
+        });
+
+        TestContext {
+            env,
+            contract_id,
+            client,
+            invoices,
+            admin,
+            treasury,
+            sme,
+            usdc,
+            usdc_admin,
+        }
+    }
+
+    /// Fingerprint unique to the next invoice
+    fn next_fingerprint(ctx: &TestContext) -> BytesN<32> {
+        BytesN::from_array(&ctx.env, &[ctx.invoices.get_stats().total_invoices as u8; 32])
+    }
+
+    /// Generate a lender holding 10,000 USDC
+    fn funded_lender(ctx: &TestContext) -> Address {
+        let lender = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&lender, &10000_0000000);
+        lender
+    }
+
+    /// Create a 2% fee invoice for the context's SME in `asset`, due at
+    /// timestamp 2000
+    fn create_invoice_in(ctx: &TestContext, asset: &Address, amount: i128) -> u64 {
+        ctx.invoices.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            asset,
+            &amount,
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        )
+    }
+
+    /// Create a 2% fee USDC invoice for the context's SME, due at timestamp 2000
+    fn create_test_invoice(ctx: &TestContext, amount: i128) -> u64 {
+        create_invoice_in(ctx, &ctx.usdc.address, amount)
+    }
+
+    /// Last event published in the environment, wrapped for comparison
+    fn last_event(env: &Env) -> Vec<(Address, Vec<Val>, Val)> {
+        vec![env, env.events().all().last().unwrap()]
+    }
+
+    #[test]
+    fn test_installment_settlement_waterfall() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        // 1st tranche: entirely to the lender
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &500_0000000, &None);
+        assert_eq!(result.lender_amount, 500_0000000);
+        assert_eq!(result.protocol_fee, 0);
+        assert_eq!(result.sme_amount, 0);
+
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::PartiallySettled);
+        assert_eq!(invoice.amount_received, 500_0000000);
+        assert_eq!(invoice.settled_at, None);
+
+        // 2nd tranche: completes the lender's 918, then 2 of the 5 protocol fee
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &420_0000000, &None);
+        assert_eq!(result.lender_amount, 418_0000000);
+        assert_eq!(result.protocol_fee, 2_0000000);
+        assert_eq!(result.sme_amount, 0);
+        assert_eq!(ctx.client.preview_settlement(&invoice_id, &920_0000000, &1000), (918_0000000, 0, 2_0000000));
+
+        // Final tranche: rest of the protocol fee, remainder to the SME
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &80_0000000, &None);
+        assert_eq!(result.lender_amount, 0);
+        assert_eq!(result.protocol_fee, 3_0000000);
+        assert_eq!(result.sme_amount, 77_0000000);
+
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::Settled);
+        assert_eq!(invoice.amount_received, 1000_0000000);
+        assert_eq!(invoice.lender_paid, 918_0000000);
+        assert_eq!(invoice.protocol_fee_paid, 5_0000000);
+        assert_eq!(invoice.settled_at, Some(1000));
+
+        // Same totals as a single full payment
+        assert_eq!(ctx.usdc.balance(&lender), 10018_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.treasury), 5_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 977_0000000);
+
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &invoice_id, &1_0000000, &None),
+            Err(Ok(SettlementError::InvoiceAlreadySettled))
+        );
+    }
+
+    #[test]
+    fn test_settled_event() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        ctx.client.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("settled"), EVENTS_VERSION, invoice_id, ctx.sme.clone()).into_val(&ctx.env),
+                    crate::events::InvoiceSettledEvent {
+                        lender: lender.clone(),
+                        settlement_amount: 1000_0000000,
+                        lender_amount: 918_0000000,
+                        sme_amount: 77_0000000,
+                        protocol_fee: 5_0000000,
+                        status: crate::invoice::InvoiceStatus::Settled,
+                        timestamp: 1000,
+                    }
+                    .into_val(&ctx.env),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_settlers_are_invoice_contract_settlers() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let anchor = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&anchor, &1000_0000000);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        assert_eq!(
+            ctx.client.try_settle_invoice(&anchor, &invoice_id, &1000_0000000, &None),
+            Err(Ok(SettlementError::Unauthorized))
+        );
+        ctx.invoices.grant_role(&ctx.admin, &InvoiceRole::Settler, &anchor);
+        ctx.client.settle_invoice(&anchor, &invoice_id, &1000_0000000, &None);
+
+        // Payments are only recorded for the settlement contract
+        assert_eq!(
+            ctx.invoices.try_record_payment(
+                &anchor,
+                &invoice_id,
+                &adelanta_invoice::Payment {
+                    amount: 1000_0000000,
+                    face_value_paid: 1000_0000000,
+                    fx_rate: None,
+                    lender_amount: 0,
+                    protocol_fee: 0,
+                },
+            ),
+            Err(Ok(AdelantaError::Unauthorized))
+        );
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &99, &1000_0000000, &None),
+            Err(Ok(SettlementError::InvoiceNotFound))
+        );
+    }
+
+    #[test]
+    fn test_partial_settlement_split_across_syndicate() {
+        let ctx = setup();
+        let lender_a = funded_lender(&ctx);
+        let lender_b = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice_partial(&lender_a, &invoice_id, &600_0000000);
+        ctx.invoices.fund_invoice(&lender_b, &invoice_id);
+
+        ctx.client.settle_invoice(&ctx.admin, &invoice_id, &300_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000 - 600_0000000 + 200_0000000);
+        assert_eq!(ctx.usdc.balance(&lender_b), 10000_0000000 - 300_0000000 + 100_0000000);
+
+        // Positions stay tradable while the invoice is partially settled
+        let holder = Address::generate(&ctx.env);
+        ctx.invoices.transfer_position(&lender_b, &holder, &invoice_id);
+
+        ctx.client.settle_invoice(&ctx.admin, &invoice_id, &700_0000000, &None);
+        assert_eq!(ctx.usdc.balance(&lender_a), 10000_0000000 - 600_0000000 + 612_0000000);
+        assert_eq!(ctx.usdc.balance(&holder), 206_0000000);
+    }
+
+    #[test]
+    fn test_preview_settlement_waterfall() {
+        let ctx = setup();
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        assert_eq!(ctx.client.preview_settlement(&invoice_id, &0, &1000), (0, 0, 0));
+        assert_eq!(ctx.client.preview_settlement(&invoice_id, &700_0000000, &1000), (700_0000000, 0, 0));
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &921_0000000, &1000),
+            (918_0000000, 0, 3_0000000)
+        );
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &1000_0000000, &1000),
+            (918_0000000, 77_0000000, 5_0000000)
+        );
+        // Overpayment beyond the invoice amount goes to the SME
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &1010_0000000, &1000),
+            (918_0000000, 87_0000000, 5_0000000)
+        );
+        assert_eq!(
+            ctx.client.try_preview_settlement(&99, &1000_0000000, &1000),
+            Err(Ok(SettlementError::InvoiceNotFound))
+        );
+    }
+
+    #[test]
+    fn test_write_off_and_recovery_pays_lender_first() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        ctx.invoices.update_config(&ctx.admin, &None, &None, &None, &Some(100), &None, &None);
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2101;
+        });
+        assert_eq!(
+            ctx.client.try_recover_invoice(&ctx.admin, &invoice_id, &100_0000000),
+            Err(Ok(SettlementError::InvoiceNotDefaulted))
+        );
+
+        ctx.invoices.mark_defaulted(&invoice_id);
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None),
+            Err(Ok(SettlementError::InvoiceDefaulted))
+        );
+        ctx.invoices.write_off(&ctx.admin, &invoice_id);
+
+        // Partial recovery goes entirely to the lender
+        let result = ctx.client.recover_invoice(&ctx.admin, &invoice_id, &600_0000000);
+        assert_eq!(result.lender_amount, 600_0000000);
+        assert_eq!(result.sme_amount, 0);
+        assert_eq!(
+            ctx.invoices.get_invoice(&invoice_id).status,
+            adelanta_invoice::InvoiceStatus::WrittenOff
+        );
+
+        // Full recovery closes the invoice
+        let result = ctx.client.recover_invoice(&ctx.admin, &invoice_id, &400_0000000);
+        assert_eq!(result.lender_amount, 318_0000000);
+        assert_eq!(result.protocol_fee, 5_0000000);
+        assert_eq!(result.sme_amount, 77_0000000);
+
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::Settled);
+        assert_eq!(invoice.settled_at, Some(2101));
+        assert_eq!(ctx.usdc.balance(&lender), 10018_0000000);
+    }
+
+    /// Create an invoice with accruing interest for the context's SME
+    fn create_interest_invoice(
+        ctx: &TestContext,
+        due_date: u64,
+        fee_bps: u32,
+        terms: InterestTerms,
+    ) -> u64 {
+        ctx.invoices.create_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &1000_0000000,
+            &due_date,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &fee_bps,
+            &FeeModel::Accrued(terms),
+        )
+    }
+
+    #[test]
+    fn test_interest_accrues_with_minimum_fee_floor() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        // 12% annual ACT/360 on the 900 advance, floored at 1% (9 USDC)
+        let terms = InterestTerms {
+            annual_rate_bps: 1200,
+            late_rate_bps: 0,
+            day_count: DayCount::Act360,
+        };
+        let invoice_id = create_interest_invoice(&ctx, 1000 + 90 * DAY, 100, terms);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        // 10 days accrue 3 USDC, below the floor
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &1000_0000000, &(1000 + 10 * DAY)),
+            (909_0000000, 86_0000000, 5_0000000)
+        );
+        // A started day counts in full
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &1000_0000000, &(1000 + 40 * DAY + 1)),
+            (912_3000000, 82_7000000, 5_0000000)
+        );
+
+        // 60 days accrue 18 USDC
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 60 * DAY;
+        });
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(result.protocol_fee, 5_0000000);
+        assert_eq!(result.sme_amount, 77_0000000);
+    }
+
+    #[test]
+    fn test_late_interest_accrues_after_due_date() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        // 10% annual ACT/365 plus 20% late interest, due after 30 days
+        let terms = InterestTerms {
+            annual_rate_bps: 1000,
+            late_rate_bps: 2000,
+            day_count: DayCount::Act365,
+        };
+        let invoice_id = create_interest_invoice(&ctx, 1000 + 30 * DAY, 0, terms);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        // Paid 15 days late: 45 days of interest plus 15 days of late interest
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 45 * DAY;
+        });
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        let expected_fee = 110958904 + 73972602;
+        assert_eq!(result.lender_amount, 900_0000000 + expected_fee);
+        assert_eq!(result.sme_amount, 1000_0000000 - 900_0000000 - expected_fee - 5_0000000);
+    }
+
+    #[test]
+    fn test_late_interest_capped_at_invoice_amount() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+
+        // 10% annual ACT/365 plus 20% late interest, due after 30 days
+        let terms = InterestTerms {
+            annual_rate_bps: 1000,
+            late_rate_bps: 2000,
+            day_count: DayCount::Act365,
+        };
+        let invoice_id = create_interest_invoice(&ctx, 1000 + 30 * DAY, 0, terms);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        // Paid a year late, interest would exceed the 100 the invoice
+        // amount leaves above the advance; the lender takes all of it
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 1000 + 395 * DAY;
+        });
+        assert_eq!(
+            ctx.client.preview_settlement(&invoice_id, &1000_0000000, &(1000 + 395 * DAY)),
+            (1000_0000000, 0, 0)
+        );
+
+        // Paying the invoice amount settles it with the lender paid in full
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None);
+        assert_eq!(result.lender_amount, 1000_0000000);
+        assert_eq!(result.protocol_fee, 0);
+        assert_eq!(result.sme_amount, 0);
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::Settled);
+        assert_eq!(invoice.lender_paid, 1000_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 10100_0000000);
+    }
+
+    #[test]
+    fn test_payer_deposit_settles_from_contract_balance() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payer = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+
+        let result = ctx.client.deposit_payment(&payer, &invoice_id, &ctx.usdc.address, &1000_0000000);
+        assert_eq!(
+            result,
+            DepositResult::Applied(SettlementResult {
+                invoice_id,
+                lender_amount: 918_0000000,
+                sme_amount: 77_0000000,
+                protocol_fee: 5_0000000,
+                timestamp: 1000,
+            })
+        );
+
+        // Split straight out of the deposit; the admin never held the funds
+        assert_eq!(ctx.usdc.balance(&payer), 9000_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.admin), 10000_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+        assert_eq!(ctx.usdc.balance(&lender), 10018_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.sme), 977_0000000);
+        assert_eq!(
+            ctx.invoices.get_invoice(&invoice_id).status,
+            adelanta_invoice::InvoiceStatus::Settled
+        );
+    }
+
+    #[test]
+    fn test_unmatched_deposit_escrowed_and_refunded() {
+        let ctx = setup();
+        let payer = funded_lender(&ctx);
+        let stranger = Address::generate(&ctx.env);
+
+        // Not funded yet, so the deposit cannot be applied
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        let result = ctx.client.deposit_payment(&payer, &invoice_id, &ctx.usdc.address, &1000_0000000);
+        assert_eq!(result, DepositResult::Escrowed(1));
+
+        assert_eq!(
+            ctx.client.get_escrow(&1),
+            Some(EscrowDeposit {
+                id: 1,
+                depositor: payer.clone(),
+                invoice_id,
+                asset: ctx.usdc.address.clone(),
+                amount: 1000_0000000,
+                deposited_at: 1000,
+            })
+        );
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 1000_0000000);
+
+        assert_eq!(
+            ctx.client.try_refund_deposit(&stranger, &1),
+            Err(Ok(SettlementError::Unauthorized))
+        );
+
+        ctx.client.refund_deposit(&payer, &1);
+        assert_eq!(ctx.client.get_escrow(&1), None);
+        assert_eq!(ctx.usdc.balance(&payer), 10000_0000000);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+        assert_eq!(
+            ctx.client.try_refund_deposit(&payer, &1),
+            Err(Ok(SettlementError::EscrowNotFound))
+        );
+    }
+
+    #[test]
+    fn test_escrowed_deposit_matched_to_invoice() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payer = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+
+        // Deposit against an unknown invoice ID is held
+        assert_eq!(
+            ctx.client.deposit_payment(&payer, &99, &ctx.usdc.address, &600_0000000),
+            DepositResult::Escrowed(1)
+        );
+
+        // Cannot be matched while the invoice is unfunded
+        assert_eq!(
+            ctx.client.try_match_deposit(&ctx.admin, &1, &invoice_id),
+            Err(Ok(SettlementError::InvoiceNotFunded))
+        );
+
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        let result = ctx.client.match_deposit(&ctx.admin, &1, &invoice_id);
+        assert_eq!(result.lender_amount, 600_0000000);
+
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::PartiallySettled);
+        assert_eq!(invoice.amount_received, 600_0000000);
+        assert_eq!(ctx.client.get_escrow(&1), None);
+        assert_eq!(ctx.usdc.balance(&ctx.contract_id), 0);
+    }
+
+    #[test]
+    fn test_payments_in_the_invoice_asset() {
+        let ctx = setup();
+        let eurc_address = ctx.env.register_stellar_asset_contract_v2(ctx.admin.clone()).address();
+        let eurc = TokenClient::new(&ctx.env, &eurc_address);
+        StellarAssetClient::new(&ctx.env, &eurc_address).mint(&ctx.admin, &10000_0000000);
+        let lender = funded_lender(&ctx);
+        StellarAssetClient::new(&ctx.env, &eurc_address).mint(&lender, &10000_0000000);
+        ctx.invoices.approve_asset(&ctx.admin, &eurc_address, &10_0000000, &5000_0000000);
+
+        // Settlement moves the invoice's asset
+        let eurc_id = create_invoice_in(&ctx, &eurc_address, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &eurc_id);
+        ctx.client.settle_invoice(&ctx.admin, &eurc_id, &1000_0000000, &None);
+        assert_eq!(eurc.balance(&lender), 10018_0000000);
+        assert_eq!(eurc.balance(&ctx.treasury), 5_0000000);
+        assert_eq!(ctx.usdc.balance(&lender), 10000_0000000);
+
+        // A deposit in another asset is escrowed and cannot be matched
+        let payer = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&payer, &1000_0000000);
+        let other_eurc_id = create_invoice_in(&ctx, &eurc_address, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &other_eurc_id);
+        let escrow_id = match ctx.client.deposit_payment(&payer, &other_eurc_id, &ctx.usdc.address, &1000_0000000) {
+            DepositResult::Escrowed(id) => id,
+            other => panic!("expected escrow, got {:?}", other),
+        };
+        assert_eq!(
+            ctx.client.try_match_deposit(&ctx.admin, &escrow_id, &other_eurc_id),
+            Err(Ok(SettlementError::AssetMismatch))
+        );
+        ctx.client.refund_deposit(&payer, &escrow_id);
+        assert_eq!(ctx.usdc.balance(&payer), 1000_0000000);
+        let unknown = Address::generate(&ctx.env);
+        assert_eq!(
+            ctx.client.try_deposit_payment(&payer, &other_eurc_id, &unknown, &1000_0000000),
+            Err(Ok(SettlementError::AssetNotSupported))
+        );
+
+        // Revoking the asset stops new invoices; open ones still settle in it
+        ctx.invoices.revoke_asset(&ctx.admin, &eurc_address);
+        ctx.client.settle_invoice(&ctx.admin, &other_eurc_id, &1000_0000000, &None);
+        assert_eq!(
+            ctx.invoices.get_invoice(&other_eurc_id).status,
+            adelanta_invoice::InvoiceStatus::Settled
+        );
+    }
+
+    #[test]
+    fn test_pause_settle() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let payer = funded_lender(&ctx);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        let escrow_id = match ctx.client.deposit_payment(&payer, &99, &ctx.usdc.address, &100_0000000) {
+            DepositResult::Escrowed(id) => id,
+            DepositResult::Applied(_) => panic!("deposit should be escrowed"),
+        };
+
+        ctx.invoices.pause(&ctx.admin, &InvoiceOperation::Settle);
+
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &invoice_id, &1000_0000000, &None),
+            Err(Ok(SettlementError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_deposit_payment(&payer, &invoice_id, &ctx.usdc.address, &1000_0000000),
+            Err(Ok(SettlementError::Paused))
+        );
+        assert_eq!(
+            ctx.client.try_match_deposit(&ctx.admin, &escrow_id, &invoice_id),
+            Err(Ok(SettlementError::Paused))
+        );
+
+        // Defaults wait for the unpause, and so do recoveries
+        ctx.env.ledger().with_mut(|li| {
+            li.timestamp = 2000 + 30 * DAY + 1;
+        });
+        assert_eq!(
+            ctx.invoices.try_mark_defaulted(&invoice_id),
+            Err(Ok(AdelantaError::Paused))
+        );
+        ctx.invoices.unpause(&ctx.admin, &InvoiceOperation::Settle);
+        ctx.invoices.mark_defaulted(&invoice_id);
+        ctx.invoices.pause(&ctx.admin, &InvoiceOperation::Settle);
+        assert_eq!(
+            ctx.client.try_recover_invoice(&ctx.admin, &invoice_id, &1000_0000000),
+            Err(Ok(SettlementError::Paused))
+        );
+
+        ctx.invoices.unpause(&ctx.admin, &InvoiceOperation::Settle);
+        ctx.client.recover_invoice(&ctx.admin, &invoice_id, &1000_0000000);
+        assert_eq!(
+            ctx.invoices.get_invoice(&invoice_id).status,
+            adelanta_invoice::InvoiceStatus::Settled
+        );
+    }
+
+    /// Anchor key pair derived from a fixed seed
+    fn anchor_key(ctx: &TestContext, seed: u8) -> (SigningKey, BytesN<32>) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let public_key = BytesN::from_array(&ctx.env, &signing_key.verifying_key().to_bytes());
+        (signing_key, public_key)
+    }
+
+    /// Attestation for the full invoice amount received at timestamp 1500
+    fn test_attestation(ctx: &TestContext, invoice_id: u64, reference: &str) -> PaymentAttestation {
+        PaymentAttestation {
+            invoice_id,
+            fiat_amount: 17500_0000000,
+            asset_amount: 1000_0000000,
+            anchor_reference: String::from_str(&ctx.env, reference),
+            timestamp: 1500,
+        }
+    }
+
+    fn sign_attestation(
+        ctx: &TestContext,
+        signing_key: &SigningKey,
+        attestation: &PaymentAttestation,
+    ) -> BytesN<64> {
+        let message = (ctx.contract_id.clone(), attestation.clone()).to_xdr(&ctx.env);
+        let mut buf = [0u8; 512];
+        let len = message.len() as usize;
+        message.copy_into_slice(&mut buf[..len]);
+        BytesN::from_array(&ctx.env, &signing_key.sign(&buf[..len]).to_bytes())
+    }
+
+    #[test]
+    fn test_settle_with_attestation() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let (signing_key, public_key) = anchor_key(&ctx, 7);
+        let anchor = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&anchor, &10000_0000000);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        ctx.env.ledger().with_mut(|li| li.timestamp = 1600);
+
+        let attestation = test_attestation(&ctx, invoice_id, "ANCHOR-TX-1");
+        let signature = sign_attestation(&ctx, &signing_key, &attestation);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &attestation, &public_key, &signature),
+            Err(Ok(SettlementError::AnchorKeyNotRegistered))
+        );
+
+        ctx.client.register_anchor_key(&ctx.admin, &public_key);
+        assert!(ctx.client.is_anchor_key(&public_key));
+
+        // A signature over different amounts is rejected
+        let mut tampered = attestation.clone();
+        tampered.asset_amount = 2000_0000000;
+        assert!(ctx
+            .client
+            .try_settle_with_attestation(&anchor, &tampered, &public_key, &signature)
+            .is_err());
+
+        let result = ctx.client.settle_with_attestation(&anchor, &attestation, &public_key, &signature);
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(result.protocol_fee, 5_0000000);
+        assert_eq!(ctx.usdc.balance(&anchor), 9000_0000000);
+        assert_eq!(
+            ctx.invoices.get_invoice(&invoice_id).status,
+            adelanta_invoice::InvoiceStatus::Settled
+        );
+        assert_eq!(
+            ctx.client.get_attestation(&attestation.anchor_reference),
+            Some(attestation.clone())
+        );
+
+        // The anchor reference cannot be used again, even for another invoice
+        let other_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &other_id);
+        let replay = test_attestation(&ctx, other_id, "ANCHOR-TX-1");
+        let replay_signature = sign_attestation(&ctx, &signing_key, &replay);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &replay, &public_key, &replay_signature),
+            Err(Ok(SettlementError::AttestationReplayed))
+        );
+    }
+
+    #[test]
+    fn test_attestation_expiry_and_key_rotation() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let (old_signing_key, old_key) = anchor_key(&ctx, 7);
+        let (new_signing_key, new_key) = anchor_key(&ctx, 8);
+        let anchor = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&anchor, &10000_0000000);
+
+        let invoice_id = create_test_invoice(&ctx, 1000_0000000);
+        ctx.invoices.fund_invoice(&lender, &invoice_id);
+        ctx.client.register_anchor_key(&ctx.admin, &old_key);
+
+        // Attestations from the future or older than 7 days are rejected
+        let attestation = test_attestation(&ctx, invoice_id, "ANCHOR-TX-1");
+        let signature = sign_attestation(&ctx, &old_signing_key, &attestation);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &attestation, &old_key, &signature),
+            Err(Ok(SettlementError::AttestationExpired))
+        );
+        ctx.env.ledger().with_mut(|li| li.timestamp = 1500 + 7 * 24 * 60 * 60 + 1);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &attestation, &old_key, &signature),
+            Err(Ok(SettlementError::AttestationExpired))
+        );
+
+        let now = ctx.env.ledger().timestamp();
+        ctx.client.rotate_anchor_key(&ctx.admin, &old_key, &new_key);
+        assert_eq!(
+            last_event(&ctx.env),
+            vec![
+                &ctx.env,
+                (
+                    ctx.contract_id.clone(),
+                    (symbol_short!("anchr_key"), EVENTS_VERSION, new_key.clone()).into_val(&ctx.env),
+                    true.into_val(&ctx.env),
+                ),
+            ]
+        );
+        assert!(!ctx.client.is_anchor_key(&old_key));
+        assert_eq!(
+            ctx.client.try_rotate_anchor_key(&ctx.admin, &old_key, &new_key),
+            Err(Ok(SettlementError::AnchorKeyNotRegistered))
+        );
+
+        let mut partial = test_attestation(&ctx, invoice_id, "ANCHOR-TX-2");
+        partial.asset_amount = 400_0000000;
+        partial.timestamp = now;
+        let old_signature = sign_attestation(&ctx, &old_signing_key, &partial);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &partial, &old_key, &old_signature),
+            Err(Ok(SettlementError::AnchorKeyNotRegistered))
+        );
+
+        let new_signature = sign_attestation(&ctx, &new_signing_key, &partial);
+        ctx.client.settle_with_attestation(&anchor, &partial, &new_key, &new_signature);
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::PartiallySettled);
+        assert_eq!(invoice.amount_received, 400_0000000);
+
+        // Only admins of the invoice contract manage anchor keys
+        assert_eq!(
+            ctx.client.try_revoke_anchor_key(&lender, &new_key),
+            Err(Ok(SettlementError::Unauthorized))
+        );
+    }
+
+    /// SEP-40 oracle returning prices set by the test
+    #[contract]
+    struct MockOracle;
+
+    #[contractimpl]
+    impl MockOracle {
+        pub fn set_price(env: Env, asset: OracleAsset, price: i128, timestamp: u64) {
+            env.storage().instance().set(&asset, &PriceData { price, timestamp });
+        }
+    }
+
+    #[contractimpl]
+    impl PriceOracle for MockOracle {
+        fn lastprice(env: Env, asset: OracleAsset) -> Option<PriceData> {
+            env.storage().instance().get(&asset)
+        }
+    }
+
+    /// Oracle quoting USDC at $1 and MXN at $0.05, configured with a 1 hour
+    /// maximum price age and 2% slippage tolerance
+    fn setup_oracle(ctx: &TestContext) -> MockOracleClient<'static> {
+        let oracle_id = ctx.env.register_contract(None, MockOracle);
+        let oracle = MockOracleClient::new(&ctx.env, &oracle_id);
+        oracle.set_price(&OracleAsset::Stellar(ctx.usdc.address.clone()), &1_0000000, &1000);
+        oracle.set_price(&OracleAsset::Other(symbol_short!("MXN")), &500000, &1000);
+        ctx.invoices.set_fx_config(&ctx.admin, &oracle_id, &3600, &200);
+        oracle
+    }
+
+    /// Create and fund a 20,000 MXN invoice settled in USDC, due at
+    /// timestamp 2000
+    fn create_fiat_test_invoice(ctx: &TestContext, lender: &Address) -> u64 {
+        let invoice_id = ctx.invoices.create_fiat_invoice(
+            &ctx.sme,
+            &String::from_str(&ctx.env, "CORP-001"),
+            &next_fingerprint(ctx),
+            &ctx.usdc.address,
+            &FaceValue {
+                currency: symbol_short!("MXN"),
+                amount: 20000_0000000,
+            },
+            &2000,
+            &String::from_str(&ctx.env, "MX"),
+            &String::from_str(&ctx.env, "DESIGN"),
+            &200,
+            &FeeModel::Flat,
+        );
+        ctx.invoices.fund_invoice(lender, &invoice_id);
+        invoice_id
+    }
+
+    #[test]
+    fn test_fiat_settlement_checks_fx_slippage() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        let oracle = setup_oracle(&ctx);
+        let invoice_id = create_fiat_test_invoice(&ctx, &lender);
+
+        // Settlers pay in USDC and state the MXN it converted from, at a
+        // rate within the slippage tolerance of the oracle
+        oracle.set_price(&OracleAsset::Other(symbol_short!("MXN")), &495000, &1000);
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &invoice_id, &990_0000000, &None),
+            Err(Ok(SettlementError::InvalidAmount))
+        );
+        assert_eq!(
+            ctx.client.try_settle_invoice(&ctx.admin, &invoice_id, &900_0000000, &Some(20000_0000000)),
+            Err(Ok(SettlementError::FxSlippageExceeded))
+        );
+
+        // Paying the face value settles the invoice, even though the USDC
+        // it converted to is below the amount priced at funding
+        let result = ctx.client.settle_invoice(&ctx.admin, &invoice_id, &990_0000000, &Some(20000_0000000));
+        assert_eq!(result.lender_amount, 918_0000000);
+        assert_eq!(result.protocol_fee, 5_0000000);
+        assert_eq!(result.sme_amount, 67_0000000);
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.settlement_fx_rate, Some(495000));
+        assert_eq!(invoice.amount_received, 990_0000000);
+        assert_eq!(invoice.face_value_received, 20000_0000000);
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::Settled);
+    }
+
+    #[test]
+    fn test_attested_fiat_settlement_checks_fx_slippage() {
+        let ctx = setup();
+        let lender = funded_lender(&ctx);
+        setup_oracle(&ctx);
+        let (signing_key, public_key) = anchor_key(&ctx, 7);
+        ctx.client.register_anchor_key(&ctx.admin, &public_key);
+        let anchor = Address::generate(&ctx.env);
+        ctx.usdc_admin.mint(&anchor, &10000_0000000);
+
+        let invoice_id = create_fiat_test_invoice(&ctx, &lender);
+
+        // 20,000 MXN converted at 0.045 is 10% below the oracle rate
+        let mut attestation = test_attestation(&ctx, invoice_id, "ANCHOR-TX-1");
+        attestation.fiat_amount = 20000_0000000;
+        attestation.asset_amount = 900_0000000;
+        attestation.timestamp = 1000;
+        let signature = sign_attestation(&ctx, &signing_key, &attestation);
+        assert_eq!(
+            ctx.client.try_settle_with_attestation(&anchor, &attestation, &public_key, &signature),
+            Err(Ok(SettlementError::FxSlippageExceeded))
+        );
+
+        attestation.asset_amount = 1005_0000000;
+        let signature = sign_attestation(&ctx, &signing_key, &attestation);
+        ctx.client.settle_with_attestation(&anchor, &attestation, &public_key, &signature);
+        let invoice = ctx.invoices.get_invoice(&invoice_id);
+        assert_eq!(invoice.settlement_fx_rate, Some(502500));
+        assert_eq!(invoice.status, adelanta_invoice::InvoiceStatus::Settled);
+    }
+
+    #[test]
+    fn test_initialize_once() {
+        let ctx = setup();
+
+        assert_eq!(
+            ctx.client.get_config(),
+            SettlementConfig {
+                invoice_contract: ctx.invoices.address.clone(),
+            }
+        );
+        assert_eq!(
+            ctx.client.try_initialize(&ctx.invoices.address),
+            Err(Ok(SettlementError::AlreadyInitialized))
+        );
+    }
+}
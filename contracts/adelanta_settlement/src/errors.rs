@@ -0,0 +1,61 @@
+//! Custom error types for the Adelanta Settlement Contract
+
+use adelanta_types::fx::FxError;
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum SettlementError {
+    /// Contract has not been initialized
+    NotInitialized = 1,
+    /// Contract is already initialized
+    AlreadyInitialized = 2,
+    /// Caller lacks the required role on the invoice contract
+    Unauthorized = 3,
+    /// Settlement is paused on the invoice contract
+    Paused = 4,
+    /// Invoice not found
+    InvoiceNotFound = 5,
+    /// Invalid amount (zero or negative)
+    InvalidAmount = 6,
+    /// Invoice not funded yet
+    InvoiceNotFunded = 7,
+    /// Invoice already settled
+    InvoiceAlreadySettled = 8,
+    /// Invoice is defaulted or written off
+    InvoiceDefaulted = 9,
+    /// Invoice is not defaulted
+    InvoiceNotDefaulted = 10,
+    /// Invoice is disputed and waiting for an arbiter
+    InvoiceDisputed = 11,
+    /// Escrowed deposit not found
+    EscrowNotFound = 12,
+    /// Anchor signing key is not registered
+    AnchorKeyNotRegistered = 13,
+    /// Anchor reference was already used by an earlier attestation
+    AttestationReplayed = 14,
+    /// Attestation timestamp is in the future or too old
+    AttestationExpired = 15,
+    /// Asset is not approved on the invoice contract
+    AssetNotSupported = 16,
+    /// Deposit asset differs from the invoice's asset
+    AssetMismatch = 17,
+    /// No FX oracle is configured for fiat-denominated invoices
+    OracleNotConfigured = 18,
+    /// Oracle has no usable price for the currency or asset
+    PriceUnavailable = 19,
+    /// Oracle price is older than the configured maximum age
+    StalePrice = 20,
+    /// FX rate moved beyond the slippage tolerance
+    FxSlippageExceeded = 21,
+}
+
+impl From<FxError> for SettlementError {
+    fn from(error: FxError) -> Self {
+        match error {
+            FxError::PriceUnavailable => SettlementError::PriceUnavailable,
+            FxError::StalePrice => SettlementError::StalePrice,
+        }
+    }
+}
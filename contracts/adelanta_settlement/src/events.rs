@@ -0,0 +1,161 @@
+//! Contract events for the Adelanta Settlement Contract
+//!
+//! Events follow the invoice contract's layout, with topics
+//! `(name, version, ...keys)`. Payments are published here with the
+//! invoice's status after the payment; the invoice contract publishes no
+//! event of its own for them.
+
+use soroban_sdk::{contracttype, symbol_short, Address, BytesN, Env, Symbol};
+
+use crate::invoice::{Invoice, InvoiceStatus};
+use crate::types::{EscrowDeposit, PaymentAttestation, SettlementConfig, SettlementResult};
+
+/// Event schema version, bumped on any breaking change to topics or payloads
+pub const EVENTS_VERSION: u32 = 1;
+
+pub const INITIALIZED: Symbol = symbol_short!("init");
+pub const ANCHOR_KEY_SET: Symbol = symbol_short!("anchr_key");
+pub const PAYMENT_ATTESTED: Symbol = symbol_short!("attested");
+pub const INVOICE_PARTIALLY_SETTLED: Symbol = symbol_short!("part_sett");
+pub const INVOICE_SETTLED: Symbol = symbol_short!("settled");
+pub const INVOICE_RECOVERED: Symbol = symbol_short!("recovered");
+pub const DEPOSIT_ESCROWED: Symbol = symbol_short!("escrowed");
+pub const ESCROW_MATCHED: Symbol = symbol_short!("esc_match");
+pub const ESCROW_REFUNDED: Symbol = symbol_short!("esc_rfnd");
+
+/// Payload of the `part_sett`, `settled` and `recovered` events
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvoiceSettledEvent {
+    pub lender: Address,
+    pub settlement_amount: i128,
+    pub lender_amount: i128,
+    pub sme_amount: i128,
+    pub protocol_fee: i128,
+    pub status: InvoiceStatus,
+    pub timestamp: u64,
+}
+
+/// Event publishing helpers
+pub struct Events;
+
+impl Events {
+    /// Topics: `("init", version, invoice_contract)`
+    pub fn initialized(env: &Env, config: &SettlementConfig) {
+        env.events().publish(
+            (INITIALIZED, EVENTS_VERSION, config.invoice_contract.clone()),
+            config.clone(),
+        );
+    }
+
+    // ========== ANCHOR ATTESTATIONS ==========
+
+    /// Topics: `("anchr_key", version, public_key)`; payload is whether the
+    /// key is registered
+    pub fn anchor_key_set(env: &Env, public_key: &BytesN<32>, registered: bool) {
+        env.events().publish(
+            (ANCHOR_KEY_SET, EVENTS_VERSION, public_key.clone()),
+            registered,
+        );
+    }
+
+    /// Topics: `("attested", version, invoice_id, public_key)`
+    pub fn payment_attested(env: &Env, attestation: &PaymentAttestation, public_key: &BytesN<32>) {
+        env.events().publish(
+            (
+                PAYMENT_ATTESTED,
+                EVENTS_VERSION,
+                attestation.invoice_id,
+                public_key.clone(),
+            ),
+            attestation.clone(),
+        );
+    }
+
+    // ========== PAYMENTS ==========
+
+    /// Topics: `("part_sett", version, invoice_id, sme)`
+    pub fn invoice_partially_settled(
+        env: &Env,
+        invoice: &Invoice,
+        lender: &Address,
+        settlement_amount: i128,
+        result: &SettlementResult,
+    ) {
+        env.events().publish(
+            (INVOICE_PARTIALLY_SETTLED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            Self::settled_event(invoice, lender, settlement_amount, result),
+        );
+    }
+
+    /// Topics: `("settled", version, invoice_id, sme)`
+    pub fn invoice_settled(
+        env: &Env,
+        invoice: &Invoice,
+        lender: &Address,
+        settlement_amount: i128,
+        result: &SettlementResult,
+    ) {
+        env.events().publish(
+            (INVOICE_SETTLED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            Self::settled_event(invoice, lender, settlement_amount, result),
+        );
+    }
+
+    /// Topics: `("recovered", version, invoice_id, sme)`
+    pub fn invoice_recovered(
+        env: &Env,
+        invoice: &Invoice,
+        lender: &Address,
+        recovery_amount: i128,
+        result: &SettlementResult,
+    ) {
+        env.events().publish(
+            (INVOICE_RECOVERED, EVENTS_VERSION, invoice.id, invoice.sme.clone()),
+            Self::settled_event(invoice, lender, recovery_amount, result),
+        );
+    }
+
+    fn settled_event(
+        invoice: &Invoice,
+        lender: &Address,
+        settlement_amount: i128,
+        result: &SettlementResult,
+    ) -> InvoiceSettledEvent {
+        InvoiceSettledEvent {
+            lender: lender.clone(),
+            settlement_amount,
+            lender_amount: result.lender_amount,
+            sme_amount: result.sme_amount,
+            protocol_fee: result.protocol_fee,
+            status: invoice.status.clone(),
+            timestamp: result.timestamp,
+        }
+    }
+
+    // ========== ESCROW ==========
+
+    /// Topics: `("escrowed", version, invoice_id, depositor)`
+    pub fn deposit_escrowed(env: &Env, deposit: &EscrowDeposit) {
+        env.events().publish(
+            (DEPOSIT_ESCROWED, EVENTS_VERSION, deposit.invoice_id, deposit.depositor.clone()),
+            deposit.clone(),
+        );
+    }
+
+    /// Topics: `("esc_match", version, invoice_id, depositor)`
+    pub fn escrow_matched(env: &Env, deposit: &EscrowDeposit) {
+        env.events().publish(
+            (ESCROW_MATCHED, EVENTS_VERSION, deposit.invoice_id, deposit.depositor.clone()),
+            deposit.clone(),
+        );
+    }
+
+    /// Topics: `("esc_rfnd", version, invoice_id, depositor)`
+    pub fn escrow_refunded(env: &Env, deposit: &EscrowDeposit) {
+        env.events().publish(
+            (ESCROW_REFUNDED, EVENTS_VERSION, deposit.invoice_id, deposit.depositor.clone()),
+            deposit.clone(),
+        );
+    }
+}
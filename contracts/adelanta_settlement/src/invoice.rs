@@ -0,0 +1,45 @@
+//! Client for the Adelanta invoice contract
+//!
+//! Declares only the entry points the settlement contract calls. Types come
+//! from `adelanta_types`, shared with the invoice contract.
+
+use soroban_sdk::{contractclient, Address, Env, Vec};
+
+pub use adelanta_types::{
+    AssetConfig, ContractConfig, FxConfig, Invoice, InvoiceStatus, LenderContribution, Operation,
+    Payment, Role,
+};
+
+/// Subset of the `AdelantaInvoiceContract` interface used by the settlement
+/// contract
+#[allow(dead_code)]
+#[contractclient(name = "InvoiceClient")]
+pub trait InvoiceContract {
+    /// Invoice details
+    fn get_invoice(env: Env, invoice_id: u64) -> Invoice;
+
+    /// Lenders that funded an invoice and their contributions
+    fn get_invoice_lenders(env: Env, invoice_id: u64) -> Vec<LenderContribution>;
+
+    /// Contract configuration
+    fn get_config(env: Env) -> ContractConfig;
+
+    /// Price oracle settings for fiat-denominated invoices
+    fn get_fx_config(env: Env) -> Option<FxConfig>;
+
+    /// Compliance registry holding the payouts to frozen accounts
+    fn get_compliance_registry(env: Env) -> Option<Address>;
+
+    /// An approved asset's configuration
+    fn get_asset(env: Env, asset: Address) -> Option<AssetConfig>;
+
+    /// Whether `account` holds `role`
+    fn has_role(env: Env, role: Role, account: Address) -> bool;
+
+    /// Whether an operation is paused
+    fn is_paused(env: Env, operation: Operation) -> bool;
+
+    /// Record a payment split out by the settlement contract, returning the
+    /// invoice as updated by it
+    fn record_payment(env: Env, settlement: Address, invoice_id: u64, payment: Payment) -> Invoice;
+}
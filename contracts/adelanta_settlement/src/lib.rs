@@ -0,0 +1,29 @@
+//! # Adelanta Settlement Contract
+//!
+//! A Soroban smart contract taking the corporate payers' payments on
+//! invoices of the Adelanta protocol.
+//!
+//! ## Overview
+//! When the corporate client pays, the settlement contract splits the
+//! payment through the waterfall: the lenders get their principal and fee
+//! pro rata to their contributions, the treasury its protocol fee, and the
+//! SME the remainder. The invoice contract records each payment and moves
+//! the invoice to `PartiallySettled` or `Settled`.
+//!
+//! ## Key Features
+//! - Settlements posted by the invoice contract's settlers (Anchors)
+//! - Payments proven by anchor-signed attestations
+//! - Payer deposits, escrowed when they cannot be applied yet
+//! - Recoveries on defaulted invoices through the same waterfall
+//! - FX checks on payments of fiat-denominated invoices
+
+#![no_std]
+
+mod contract;
+mod storage;
+mod types;
+mod errors;
+mod events;
+mod invoice;
+
+pub use contract::AdelantaSettlementContract;
@@ -0,0 +1,117 @@
+//! Storage keys and helpers for the Adelanta Settlement Contract
+
+use soroban_sdk::{contracttype, BytesN, Env, IntoVal, String, Val};
+
+use crate::types::{EscrowDeposit, PaymentAttestation, SettlementConfig};
+
+/// Storage key types
+#[contracttype]
+pub enum DataKey {
+    /// Settlement contract configuration
+    Config,
+    /// Registered anchor ed25519 public key
+    AnchorKey(BytesN<32>),
+    /// Applied payment attestation by anchor reference
+    Attestation(String),
+    /// Unapplied payer deposit by escrow ID
+    Escrow(u64),
+    /// Next escrow ID counter
+    NextEscrowId,
+}
+
+/// Storage helper functions
+pub struct Storage;
+
+impl Storage {
+    // ========== CONFIG ==========
+
+    pub fn get_config(env: &Env) -> Option<SettlementConfig> {
+        env.storage().instance().get(&DataKey::Config)
+    }
+
+    pub fn set_config(env: &Env, config: &SettlementConfig) {
+        env.storage().instance().set(&DataKey::Config, config);
+    }
+
+    pub fn has_config(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::Config)
+    }
+
+    // ========== ANCHOR ATTESTATIONS ==========
+
+    pub fn is_anchor_key(env: &Env, public_key: &BytesN<32>) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::AnchorKey(public_key.clone()))
+    }
+
+    pub fn set_anchor_key(env: &Env, public_key: &BytesN<32>) {
+        Self::set_persistent(env, &DataKey::AnchorKey(public_key.clone()), &true);
+    }
+
+    pub fn remove_anchor_key(env: &Env, public_key: &BytesN<32>) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::AnchorKey(public_key.clone()));
+    }
+
+    pub fn get_attestation(env: &Env, anchor_reference: &String) -> Option<PaymentAttestation> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Attestation(anchor_reference.clone()))
+    }
+
+    pub fn has_attestation(env: &Env, anchor_reference: &String) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Attestation(anchor_reference.clone()))
+    }
+
+    pub fn set_attestation(env: &Env, attestation: &PaymentAttestation) {
+        Self::set_persistent(
+            env,
+            &DataKey::Attestation(attestation.anchor_reference.clone()),
+            attestation,
+        );
+    }
+
+    // ========== ESCROW ==========
+
+    pub fn get_escrow(env: &Env, id: u64) -> Option<EscrowDeposit> {
+        env.storage().persistent().get(&DataKey::Escrow(id))
+    }
+
+    pub fn set_escrow(env: &Env, deposit: &EscrowDeposit) {
+        Self::set_persistent(env, &DataKey::Escrow(deposit.id), deposit);
+    }
+
+    pub fn remove_escrow(env: &Env, id: u64) {
+        env.storage().persistent().remove(&DataKey::Escrow(id));
+    }
+
+    pub fn next_escrow_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextEscrowId)
+            .unwrap_or(1u64);
+        env.storage().instance().set(&DataKey::NextEscrowId, &(id + 1));
+        id
+    }
+
+    // ========== TTL MANAGEMENT ==========
+
+    /// Write a persistent record, keeping it alive for the maximum TTL
+    fn set_persistent<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        env.storage().persistent().set(key, value);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().extend_ttl(key, max_ttl - 1000, max_ttl);
+    }
+
+    pub fn extend_instance_ttl(env: &Env) {
+        let max_ttl = env.storage().max_ttl();
+        env.storage()
+            .instance()
+            .extend_ttl(max_ttl - 1000, max_ttl);
+    }
+}
@@ -0,0 +1,77 @@
+//! Data types for the Adelanta Settlement Contract
+
+use soroban_sdk::{contracttype, Address, String};
+
+/// Settlement contract configuration
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SettlementConfig {
+    /// Invoice contract whose invoices are settled; it must grant the
+    /// settlement contract its `Settlement` role, and its roles manage
+    /// settlers and anchor keys
+    pub invoice_contract: Address,
+}
+
+/// Anchor-signed evidence of a payer's fiat payment for an invoice
+///
+/// Anchors sign the XDR of `(contract_address, attestation)` with a
+/// registered ed25519 key, where `contract_address` is the settlement
+/// contract.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentAttestation {
+    /// Invoice ID
+    pub invoice_id: u64,
+    /// Fiat amount received by the anchor (7 decimals)
+    pub fiat_amount: i128,
+    /// Amount the fiat payment converted to, in the invoice asset
+    pub asset_amount: i128,
+    /// Anchor transaction reference, usable once
+    pub anchor_reference: String,
+    /// Time the anchor received the payment
+    pub timestamp: u64,
+}
+
+/// Settlement result data
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SettlementResult {
+    /// Invoice ID
+    pub invoice_id: u64,
+    /// Amount paid to lender (principal + fee)
+    pub lender_amount: i128,
+    /// Amount paid to SME (remainder)
+    pub sme_amount: i128,
+    /// Protocol fee collected
+    pub protocol_fee: i128,
+    /// Settlement timestamp
+    pub timestamp: u64,
+}
+
+/// Payer deposit held by the contract because it could not be applied
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowDeposit {
+    /// Escrow record identifier
+    pub id: u64,
+    /// Address that deposited the funds
+    pub depositor: Address,
+    /// Invoice the deposit was made against
+    pub invoice_id: u64,
+    /// Asset deposited
+    pub asset: Address,
+    /// Amount held
+    pub amount: i128,
+    /// Deposit timestamp
+    pub deposited_at: u64,
+}
+
+/// Outcome of a payer deposit
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DepositResult {
+    /// Deposit was split out to lenders, treasury and SME
+    Applied(SettlementResult),
+    /// Deposit could not be applied and is held in escrow under this ID
+    Escrowed(u64),
+}
@@ -0,0 +1,72 @@
+//! Wasm of the workspace contracts for the tests of their siblings
+//!
+//! Tests calling another contract of the workspace import its wasm with
+//! `contractimport!`. Each such crate's build script calls [`build`], which
+//! builds the imported contracts from the workspace into
+//! `target/testdata`, so tests always run against the current sources.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Target the contracts are built for
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Directory under the workspace's `target` the contracts are built into,
+/// kept apart from the outer build so the nested one does not wait on its
+/// lock
+const TARGET_DIR: &str = "testdata";
+
+/// Variables the outer build sets for its own compilation, which must not
+/// leak into the nested wasm build
+const OUTER_BUILD_VARS: [&str; 7] = [
+    "CARGO_ENCODED_RUSTFLAGS",
+    "RUSTFLAGS",
+    "RUSTC_WRAPPER",
+    "RUSTC_WORKSPACE_WRAPPER",
+    "CLIPPY_ARGS",
+    "CARGO_TARGET_DIR",
+    "CARGO_BUILD_TARGET",
+];
+
+/// Build `contracts` (workspace package names) for the tests of the crate
+/// running the build script. Their wasm lands in
+/// `target/testdata/wasm32-unknown-unknown/release`.
+///
+/// Contracts built for wasm skip this step, so the nested build of a
+/// contract never builds the contracts its own tests import.
+pub fn build(contracts: &[&str]) {
+    if env::var("TARGET").is_ok_and(|target| target == WASM_TARGET) {
+        return;
+    }
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("run from a build script"));
+    let workspace = manifest_dir.parent().expect("crate inside the workspace");
+
+    // Rebuild when the imported contracts or the types they share change
+    for contract in contracts.iter().copied().chain(["adelanta_types"]) {
+        watch(&workspace.join(contract));
+    }
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(workspace)
+        .args(["build", "--release", "--target", WASM_TARGET, "--target-dir"])
+        .arg(workspace.join("target").join(TARGET_DIR));
+    for contract in contracts {
+        command.args(["--package", contract]);
+    }
+    for var in OUTER_BUILD_VARS {
+        command.env_remove(var);
+    }
+
+    let status = command.status().expect("failed to run cargo");
+    assert!(status.success(), "failed to build {contracts:?} for {WASM_TARGET}");
+}
+
+/// Rerun the build script when a crate's manifest or sources change
+fn watch(crate_dir: &Path) {
+    println!("cargo:rerun-if-changed={}", crate_dir.join("Cargo.toml").display());
+    println!("cargo:rerun-if-changed={}", crate_dir.join("src").display());
+}
@@ -0,0 +1,63 @@
+//! Payouts to accounts frozen in the compliance registry
+//!
+//! The registry is the sibling contract where compliance officers freeze
+//! accounts. Payouts to a frozen account are handed to the registry, which
+//! holds them until they are released or redirected.
+
+use soroban_sdk::auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation};
+use soroban_sdk::{contractclient, token, vec, Address, Env, IntoVal, Symbol};
+
+/// Subset of the compliance registry interface handling frozen payouts
+#[allow(dead_code)]
+#[contractclient(name = "PayoutRegistryClient")]
+pub trait PayoutRegistry {
+    /// Check whether an account is frozen
+    fn is_frozen(env: Env, account: Address) -> bool;
+
+    /// Hold a payout destined for a frozen account, transferring it from
+    /// `from`; returns the retained payout ID
+    fn retain_payout(env: Env, from: Address, account: Address, asset: Address, amount: i128, invoice_id: u64) -> u64;
+}
+
+/// Pay `amount` for an invoice from `from` to `to`, or hand it to
+/// `registry` to hold while `to` is frozen
+pub fn pay_out(
+    env: &Env,
+    registry: &Option<Address>,
+    token_client: &token::Client,
+    from: &Address,
+    to: &Address,
+    invoice_id: u64,
+    amount: i128,
+) {
+    let registry = registry
+        .as_ref()
+        .filter(|registry| PayoutRegistryClient::new(env, registry).is_frozen(to));
+    let Some(registry) = registry else {
+        token_client.transfer(from, to, &amount);
+        return;
+    };
+
+    // The registry pulls the payout, which needs the contract's
+    // authorization when paying from its own balance
+    if *from == env.current_contract_address() {
+        env.authorize_as_current_contract(vec![
+            env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: token_client.address.clone(),
+                    fn_name: Symbol::new(env, "transfer"),
+                    args: (from.clone(), registry.clone(), amount).into_val(env),
+                },
+                sub_invocations: vec![env],
+            }),
+        ]);
+    }
+    PayoutRegistryClient::new(env, registry).retain_payout(
+        from,
+        to,
+        &token_client.address,
+        &amount,
+        &invoice_id,
+    );
+}
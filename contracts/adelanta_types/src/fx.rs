@@ -0,0 +1,88 @@
+//! FX rates of fiat-denominated invoices
+//!
+//! Rates come from a price oracle implementing the SEP-40 `lastprice`
+//! interface, which quotes fiat currencies and Stellar assets in a common
+//! base (e.g. USD). The rate of a currency in an asset is the ratio of the
+//! two prices, so the oracle's decimals cancel out.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, Symbol};
+
+use crate::FxConfig;
+
+/// Scale of FX rates, expressed in asset units per currency unit
+pub const FX_RATE_SCALE: i128 = 10_000_000;
+
+/// Asset identifier of the SEP-40 oracle interface
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OracleAsset {
+    /// Stellar asset contract
+    Stellar(Address),
+    /// Off-chain asset, e.g. a fiat currency code
+    Other(Symbol),
+}
+
+/// Price record of the SEP-40 oracle interface
+#[contracttype(export = false)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    /// Price in the oracle's base asset
+    pub price: i128,
+    /// Time the price was recorded
+    pub timestamp: u64,
+}
+
+/// Subset of the SEP-40 price oracle interface used by the contracts
+#[allow(dead_code)]
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    /// Most recent price of `asset`
+    fn lastprice(env: Env, asset: OracleAsset) -> Option<PriceData>;
+}
+
+/// Reason the oracle could not price a rate, mapped by each contract onto
+/// its own error
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FxError {
+    /// The oracle has no usable price for the currency or the asset
+    PriceUnavailable,
+    /// The oracle's price is older than the configured maximum age
+    StalePrice,
+}
+
+/// Current rate of `currency` in `asset` units (scaled by `FX_RATE_SCALE`)
+pub fn rate(env: &Env, config: &FxConfig, currency: &Symbol, asset: &Address) -> Result<i128, FxError> {
+    let oracle = PriceOracleClient::new(env, &config.oracle);
+    let currency_price = price(env, config, &oracle, OracleAsset::Other(currency.clone()))?;
+    let asset_price = price(env, config, &oracle, OracleAsset::Stellar(asset.clone()))?;
+
+    Ok((currency_price * FX_RATE_SCALE) / asset_price)
+}
+
+/// Convert a currency amount into asset units at `rate`
+pub fn to_asset(amount: i128, rate: i128) -> i128 {
+    (amount * rate) / FX_RATE_SCALE
+}
+
+/// Convert an asset amount into currency units at `rate`
+pub fn to_currency(amount: i128, rate: i128) -> i128 {
+    (amount * FX_RATE_SCALE) / rate
+}
+
+/// Whether `rate` deviates from `reference` by at most the slippage tolerance
+pub fn within_slippage(config: &FxConfig, reference: i128, rate: i128) -> bool {
+    (rate - reference).abs() * 10000 <= reference * config.max_slippage_bps as i128
+}
+
+/// Oracle price of `asset`, rejecting missing and stale prices
+fn price(env: &Env, config: &FxConfig, oracle: &PriceOracleClient, asset: OracleAsset) -> Result<i128, FxError> {
+    let data = oracle.lastprice(&asset).ok_or(FxError::PriceUnavailable)?;
+    if data.price <= 0 {
+        return Err(FxError::PriceUnavailable);
+    }
+    if env.ledger().timestamp().saturating_sub(data.timestamp) > config.max_price_age_secs {
+        return Err(FxError::StalePrice);
+    }
+
+    Ok(data.price)
+}
@@ -0,0 +1,61 @@
+//! Lender fee owed on an invoice
+//!
+//! Invoices either charge a flat `fee_bps` on the advance, or use
+//! `FeeModel::Accrued` terms under which the fee accrues daily from funding
+//! to settlement, with `fee_bps` acting as the minimum fee floor and extra
+//! late interest accruing past the due date. Late interest stops once the
+//! lender is owed the whole invoice amount, since the payer never owes more.
+
+use crate::{DayCount, FeeModel, Invoice};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Lender fee owed on `invoice` if it settles at `as_of`
+pub fn lender_fee(invoice: &Invoice, as_of: u64) -> i128 {
+    let flat_fee = (invoice.advance_amount * invoice.fee_bps as i128) / 10000;
+
+    let terms = match &invoice.fee_model {
+        FeeModel::Accrued(terms) => terms,
+        FeeModel::Flat => return flat_fee,
+    };
+
+    // Unfunded invoices accrue as if funded at `as_of`
+    let start = invoice.funded_at.unwrap_or(as_of);
+    let basis = year_basis(&terms.day_count);
+
+    let accrued = accrue(
+        invoice.advance_amount,
+        terms.annual_rate_bps,
+        days_between(start, as_of),
+        basis,
+    );
+    let late = accrue(
+        invoice.advance_amount,
+        terms.late_rate_bps,
+        days_between(invoice.due_date.max(start), as_of),
+        basis,
+    );
+
+    // Paying the invoice amount settles it, so it must cover the lender
+    let fee = accrued.max(flat_fee);
+    let late_cap = (invoice.amount - invoice.advance_amount - fee).max(0);
+    fee + late.min(late_cap)
+}
+
+/// Days in the year denominator of a day-count convention
+fn year_basis(day_count: &DayCount) -> i128 {
+    match day_count {
+        DayCount::Act360 => 360,
+        DayCount::Act365 => 365,
+    }
+}
+
+/// Simple interest on `principal` at an annual rate over `days`
+fn accrue(principal: i128, annual_rate_bps: u32, days: u64, year_basis: i128) -> i128 {
+    (principal * annual_rate_bps as i128 * days as i128) / (10000 * year_basis)
+}
+
+/// Actual days elapsed from `start` to `end`, a started day counting in full
+fn days_between(start: u64, end: u64) -> u64 {
+    end.saturating_sub(start).div_ceil(SECONDS_PER_DAY)
+}
@@ -0,0 +1,313 @@
+//! Invoice types and helpers shared by the Adelanta contracts
+//!
+//! Defined once here for the invoice contract and the contracts calling it,
+//! so values decode the same on both sides of the contract boundary. The
+//! FX pricing, lender fees and frozen-account payouts both the invoice and
+//! settlement contracts compute live here as well.
+
+#![no_std]
+
+pub mod compliance;
+pub mod fx;
+pub mod interest;
+
+use soroban_sdk::{contracttype, Address, String, Symbol};
+
+/// Status of an invoice in its lifecycle
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvoiceStatus {
+    /// Invoice created, waiting for funding
+    Created,
+    /// Payer confirmed the invoice amount and due date, waiting for funding
+    Acknowledged,
+    /// Invoice funded by liquidity provider
+    Funded,
+    /// Payer has paid part of the invoice amount
+    PartiallySettled,
+    /// Invoice fully settled, payment distributed
+    Settled,
+    /// Invoice cancelled/expired
+    Cancelled,
+    /// Payer failed to pay before the grace period expired
+    Defaulted,
+    /// Defaulted invoice written off; late recoveries still accepted
+    WrittenOff,
+    /// Invoice contested, waiting for an arbiter's resolution
+    Disputed,
+}
+
+/// Day-count convention used to accrue interest
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DayCount {
+    /// Actual days elapsed over a 360-day year
+    Act360,
+    /// Actual days elapsed over a 365-day year
+    Act365,
+}
+
+/// Time-based pricing of the lender fee
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterestTerms {
+    /// Annualized discount rate on the advance (basis points)
+    pub annual_rate_bps: u32,
+    /// Additional annualized rate accruing after the due date (basis points)
+    pub late_rate_bps: u32,
+    /// Day-count convention
+    pub day_count: DayCount,
+}
+
+/// How the lender fee of an invoice is priced
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FeeModel {
+    /// Flat `fee_bps` on the advance
+    Flat,
+    /// Interest accruing from funding to settlement, floored at `fee_bps`
+    Accrued(InterestTerms),
+}
+
+/// Access control roles
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Grants and revokes roles, manages treasury and write-offs
+    Admin,
+    /// Anchor or operator allowed to post settlements and recoveries
+    Settler,
+    /// Allowed to pause contract operations
+    Pauser,
+    /// Allowed to change advance and protocol fee parameters
+    FeeManager,
+    /// Sets the compliance registry accounts are checked against
+    Compliance,
+    /// Resolves invoice disputes
+    Arbiter,
+    /// Marketplace contract allowed to settle position sales
+    Marketplace,
+    /// Settlement contract allowed to record payments
+    Settlement,
+    /// Payers contract allowed to record acknowledgements and disputes
+    Payers,
+}
+
+/// Operations that can be paused independently
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// `create_invoice`
+    Create,
+    /// `fund_invoice`, `fund_invoice_partial`
+    Fund,
+    /// `settle_invoice`, `settle_with_attestation`, `deposit_payment`,
+    /// `match_deposit`, `recover_invoice` of the settlement contract,
+    /// `mark_defaulted`, and disputes raised and resolved through the
+    /// payers contract
+    Settle,
+    /// `transfer_position`, `trade_position`
+    Trade,
+}
+
+/// Core invoice data structure
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Invoice {
+    /// Unique invoice identifier
+    pub id: u64,
+    /// SME (invoice owner) address
+    pub sme: Address,
+    /// Corporate payer identifier (hashed reference)
+    pub payer_id: String,
+    /// Stellar asset contract the invoice is funded and settled in
+    pub asset: Address,
+    /// Fiat currency of a fiat-denominated invoice (e.g. MXN); `None` when
+    /// the invoice is denominated in `asset`
+    pub currency: Option<Symbol>,
+    /// Face value in `currency` (7 decimals); 0 when denominated in `asset`
+    pub face_value: i128,
+    /// Cumulative face value paid, in `currency`; 0 when denominated in `asset`
+    pub face_value_received: i128,
+    /// FX rate (`asset` units per `currency` unit, 7 decimals) the invoice
+    /// is priced at; quoted at creation and fixed when funding starts
+    pub funding_fx_rate: Option<i128>,
+    /// FX rate of the latest payment settled in `currency`
+    pub settlement_fx_rate: Option<i128>,
+    /// Total invoice amount in the invoice's asset (7 decimals); the face
+    /// value converted at `funding_fx_rate` for fiat-denominated invoices
+    pub amount: i128,
+    /// Amount advanced to SME (typically 90%)
+    pub advance_amount: i128,
+    /// Fee percentage charged (basis points, e.g., 200 = 2%);
+    /// the minimum fee under `FeeModel::Accrued`
+    pub fee_bps: u32,
+    /// Flat fee or time-based interest
+    pub fee_model: FeeModel,
+    /// Lead liquidity provider (first contributor to the funding)
+    pub lender: Option<Address>,
+    /// Advance amount subscribed so far by all contributors
+    pub funded_amount: i128,
+    /// Cumulative amount received from the payer (invoice asset)
+    pub amount_received: i128,
+    /// Cumulative amount paid out to lenders
+    pub lender_paid: i128,
+    /// Cumulative protocol fee paid to the treasury
+    pub protocol_fee_paid: i128,
+    /// Current status
+    pub status: InvoiceStatus,
+    /// Creation timestamp
+    pub created_at: u64,
+    /// Funding timestamp
+    pub funded_at: Option<u64>,
+    /// Settlement timestamp
+    pub settled_at: Option<u64>,
+    /// Default timestamp
+    pub defaulted_at: Option<u64>,
+    /// Due date timestamp
+    pub due_date: u64,
+    /// Country code (e.g., "MX", "CO", "BR")
+    pub country: String,
+    /// Industry/sector code
+    pub industry: String,
+}
+
+/// A single lender's share in the funding of an invoice
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LenderContribution {
+    /// Contributing lender
+    pub lender: Address,
+    /// Amount of the advance contributed (invoice asset)
+    pub amount: i128,
+}
+
+/// A lender's position in an invoice and what it has returned so far
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LenderPosition {
+    /// Invoice the position belongs to
+    pub invoice_id: u64,
+    /// Lender holding the position
+    pub lender: Address,
+    /// Amount of the advance contributed (invoice asset)
+    pub amount: i128,
+    /// Settlement proceeds paid to the lender, or the refunded contribution
+    /// if the invoice was cancelled (invoice asset)
+    pub paid: i128,
+    /// Whether the invoice reached a final status (settled, cancelled or
+    /// written off)
+    pub closed: bool,
+}
+
+/// Arbiter's decision on a disputed invoice
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DisputeResolution {
+    /// The invoice stands in full
+    Valid,
+    /// The payer owes the given amount instead (in the invoice's asset)
+    Adjusted(i128),
+    /// The invoice is void
+    Invalid,
+}
+
+/// Record of a lender position changing hands
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionTransfer {
+    /// Previous holder
+    pub from: Address,
+    /// New holder
+    pub to: Address,
+    /// Contribution amount carried by the position
+    pub amount: i128,
+    /// Price paid in the invoice asset (0 for direct transfers)
+    pub price: i128,
+    /// Transfer timestamp
+    pub timestamp: u64,
+}
+
+/// Settlement asset approved by the admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetConfig {
+    /// Stellar asset contract address
+    pub asset: Address,
+    /// Minimum invoice amount in this asset
+    pub min_invoice_amount: i128,
+    /// Maximum invoice amount in this asset
+    pub max_invoice_amount: i128,
+    /// Whether new invoices may be created in this asset
+    pub enabled: bool,
+}
+
+/// Price oracle settings for fiat-denominated invoices
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FxConfig {
+    /// SEP-40 price oracle quoting currencies and assets in a common base
+    pub oracle: Address,
+    /// Maximum age of an oracle price (seconds)
+    pub max_price_age_secs: u64,
+    /// Maximum FX rate deviation accepted (basis points)
+    pub max_slippage_bps: u32,
+}
+
+/// Contract configuration
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ContractConfig {
+    /// Admin address set at initialization (holds every role initially)
+    pub admin: Address,
+    /// Asset approved at initialization (USDC); invoices created before
+    /// the asset registry existed are in this asset
+    pub usdc_token: Address,
+    /// Treasury address for protocol fees
+    pub treasury: Address,
+    /// Default advance percentage (basis points, 9000 = 90%)
+    pub default_advance_bps: u32,
+    /// Protocol fee (basis points, 50 = 0.5%)
+    pub protocol_fee_bps: u32,
+    /// Minimum invoice amount `usdc_token` was approved with
+    pub min_invoice_amount: i128,
+    /// Maximum invoice amount `usdc_token` was approved with
+    pub max_invoice_amount: i128,
+    /// Time after due date before an unpaid invoice can be defaulted (seconds)
+    pub grace_period_secs: u64,
+    /// Whether invoices must be acknowledged by their payer before funding
+    pub require_acknowledgement: bool,
+    /// Advance percentage for invoices acknowledged before funding starts
+    /// (basis points, applied when above the invoice's advance rate)
+    pub acknowledged_advance_bps: u32,
+}
+
+/// Payment split out by the settlement contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Payment {
+    /// Amount paid, in the invoice asset
+    pub amount: i128,
+    /// Face value in the invoice currency the payment covers
+    pub face_value_paid: i128,
+    /// Rate the payment was converted at, for fiat-denominated invoices
+    pub fx_rate: Option<i128>,
+    /// Amount paid to lenders (principal + fee)
+    pub lender_amount: i128,
+    /// Protocol fee collected
+    pub protocol_fee: i128,
+}
+
+/// Funding event data
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FundingEvent {
+    /// Invoice ID
+    pub invoice_id: u64,
+    /// Lender address
+    pub lender: Address,
+    /// Amount funded
+    pub amount: i128,
+    /// Timestamp
+    pub timestamp: u64,
+}
@@ -3,9 +3,9 @@
 
 set -e
 
-echo "🔨 Building Adelanta Invoice Contract..."
+echo "🔨 Building Adelanta Contracts..."
 
-# Build the contract
+# Build the contracts
 cargo build --target wasm32-unknown-unknown --release
 
 echo "✅ Build complete!"
@@ -13,9 +13,11 @@ echo "✅ Build complete!"
 # Optimize the WASM (requires soroban-cli)
 if command -v soroban &> /dev/null; then
     echo "📦 Optimizing WASM..."
-    soroban contract optimize \
-        --wasm target/wasm32-unknown-unknown/release/adelanta_invoice.wasm \
-        --out-wasm target/wasm32-unknown-unknown/release/adelanta_invoice_optimized.wasm
+    for contract in adelanta_invoice adelanta_pool adelanta_compliance adelanta_marketplace adelanta_risk adelanta_settlement adelanta_payers; do
+        soroban contract optimize \
+            --wasm target/wasm32-unknown-unknown/release/$contract.wasm \
+            --out-wasm target/wasm32-unknown-unknown/release/${contract}_optimized.wasm
+    done
     
     echo "✅ Optimization complete!"
     
@@ -29,4 +31,4 @@ else
 fi
 
 echo ""
-echo "🎉 Done! Contract ready for deployment."
+echo "🎉 Done! Contracts ready for deployment."
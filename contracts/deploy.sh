@@ -50,6 +50,78 @@ echo -e "Contract ID: ${YELLOW}$CONTRACT_ID${NC}"
 echo "$CONTRACT_ID" > .contract_id
 echo "Contract ID saved to .contract_id"
 
+POOL_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_pool.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Pool deployed!${NC}"
+echo -e "Pool ID: ${YELLOW}$POOL_ID${NC}"
+
+echo "$POOL_ID" > .pool_id
+echo "Pool ID saved to .pool_id"
+
+COMPLIANCE_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_compliance.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Compliance registry deployed!${NC}"
+echo -e "Compliance ID: ${YELLOW}$COMPLIANCE_ID${NC}"
+
+echo "$COMPLIANCE_ID" > .compliance_id
+echo "Compliance ID saved to .compliance_id"
+
+MARKETPLACE_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_marketplace.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Marketplace deployed!${NC}"
+echo -e "Marketplace ID: ${YELLOW}$MARKETPLACE_ID${NC}"
+
+echo "$MARKETPLACE_ID" > .marketplace_id
+echo "Marketplace ID saved to .marketplace_id"
+
+RISK_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_risk.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Risk engine deployed!${NC}"
+echo -e "Risk engine ID: ${YELLOW}$RISK_ID${NC}"
+
+echo "$RISK_ID" > .risk_id
+echo "Risk engine ID saved to .risk_id"
+
+SETTLEMENT_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_settlement.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Settlement contract deployed!${NC}"
+echo -e "Settlement ID: ${YELLOW}$SETTLEMENT_ID${NC}"
+
+echo "$SETTLEMENT_ID" > .settlement_id
+echo "Settlement ID saved to .settlement_id"
+
+PAYERS_ID=$(soroban contract deploy \
+    --wasm target/wasm32-unknown-unknown/release/adelanta_payers.wasm \
+    --source "$STELLAR_SECRET_KEY" \
+    --rpc-url "$RPC_URL" \
+    --network-passphrase "$NETWORK_PASSPHRASE")
+
+echo -e "${GREEN}✅ Payers contract deployed!${NC}"
+echo -e "Payers ID: ${YELLOW}$PAYERS_ID${NC}"
+
+echo "$PAYERS_ID" > .payers_id
+echo "Payers ID saved to .payers_id"
+
 echo ""
 echo "=========================================="
 echo -e "${GREEN}🎉 Deployment complete!${NC}"
@@ -68,3 +140,110 @@ echo "     --usdc_token <USDC_TOKEN_ID> \\"
 echo "     --treasury <TREASURY_ADDRESS> \\"
 echo "     --default_advance_bps 9000 \\"
 echo "     --protocol_fee_bps 50"
+echo "2. Initialize the pool with:"
+echo "   soroban contract invoke \\"
+echo "     --id $POOL_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --admin <ADMIN_ADDRESS> \\"
+echo "     --manager <MANAGER_ADDRESS> \\"
+echo "     --asset <USDC_TOKEN_ID> \\"
+echo "     --invoice_contract $CONTRACT_ID \\"
+echo "     --name \"Adelanta Pool Share\" \\"
+echo "     --symbol ADLP"
+echo "3. Initialize the compliance registry and connect it to the contract with:"
+echo "   soroban contract invoke \\"
+echo "     --id $COMPLIANCE_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --admin <ADMIN_ADDRESS>"
+echo "   soroban contract invoke \\"
+echo "     --id $CONTRACT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     set_compliance_registry \\"
+echo "     --caller <ADMIN_ADDRESS> \\"
+echo "     --registry $COMPLIANCE_ID"
+echo "4. Initialize the marketplace and grant it the Marketplace role with:"
+echo "   soroban contract invoke \\"
+echo "     --id $MARKETPLACE_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --invoice_contract $CONTRACT_ID"
+echo "   soroban contract invoke \\"
+echo "     --id $CONTRACT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     grant_role \\"
+echo "     --admin <ADMIN_ADDRESS> \\"
+echo "     --role Marketplace \\"
+echo "     --account $MARKETPLACE_ID"
+echo "5. Initialize the risk engine and connect it to the contract with:"
+echo "   soroban contract invoke \\"
+echo "     --id $RISK_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --invoice_contract $CONTRACT_ID"
+echo "   soroban contract invoke \\"
+echo "     --id $CONTRACT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     set_risk_engine \\"
+echo "     --admin <ADMIN_ADDRESS> \\"
+echo "     --engine $RISK_ID"
+echo "6. Initialize the settlement contract and grant it the Settlement role with:"
+echo "   soroban contract invoke \\"
+echo "     --id $SETTLEMENT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --invoice_contract $CONTRACT_ID"
+echo "   soroban contract invoke \\"
+echo "     --id $CONTRACT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     grant_role \\"
+echo "     --admin <ADMIN_ADDRESS> \\"
+echo "     --role Settlement \\"
+echo "     --account $SETTLEMENT_ID"
+echo "7. Initialize the payers contract and grant it the Payers role with:"
+echo "   soroban contract invoke \\"
+echo "     --id $PAYERS_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     initialize \\"
+echo "     --invoice_contract $CONTRACT_ID"
+echo "   soroban contract invoke \\"
+echo "     --id $CONTRACT_ID \\"
+echo "     --source \$STELLAR_SECRET_KEY \\"
+echo "     --rpc-url $RPC_URL \\"
+echo "     --network-passphrase \"$NETWORK_PASSPHRASE\" \\"
+echo "     -- \\"
+echo "     grant_role \\"
+echo "     --admin <ADMIN_ADDRESS> \\"
+echo "     --role Payers \\"
+echo "     --account $PAYERS_ID"